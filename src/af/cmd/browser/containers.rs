@@ -0,0 +1,214 @@
+use crate::cmd::browser::profiles;
+use crate::cmd::browser::structures::{Kind, Platform};
+use crate::consts::{EXT_CONTAINER, FIREFOX_CONTAINERS};
+use crate::discovery::Roots;
+
+use anyhow::{Result, anyhow};
+use clap::{
+    Arg, Command,
+    builder::{PossibleValue, TypedValueParser},
+};
+use serde::Deserialize;
+use std::{collections::BTreeSet, ffi::OsStr, fs, path::Path};
+use url::form_urlencoded;
+
+/// Firefox Multi-Account Container of a profile
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Container {
+    pub id: u32,
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Containers {
+    #[serde(default)]
+    identities: Vec<Identity>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Identity {
+    user_context_id: u32,
+    #[serde(default)]
+    public: bool,
+    name: Option<String>,
+    /// Localisation key naming the built-in containers (e.g. `userContextWork.label`)
+    #[serde(rename = "l10nID")]
+    l10n_id: Option<String>,
+}
+
+impl Identity {
+    fn name(&self) -> Option<String> {
+        if let Some(name) = &self.name {
+            return Some(name.clone());
+        }
+
+        let name = self
+            .l10n_id
+            .as_deref()?
+            .strip_prefix("userContext")?
+            .strip_suffix(".label")?;
+
+        Some(name.to_string())
+    }
+}
+
+/// Containers in the profile's `containers.json` (built-in ones unless the
+/// user changed them)
+pub fn read_containers(profile: &Path) -> Result<Vec<Container>> {
+    let content = match fs::read_to_string(profile.join(FIREFOX_CONTAINERS)) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err.into()),
+    };
+
+    let containers: Containers = serde_json::from_str(&content)?;
+
+    Ok(containers
+        .identities
+        .into_iter()
+        .filter(|identity| identity.public)
+        .filter_map(|identity| {
+            Some(Container {
+                id: identity.user_context_id,
+                name: identity.name()?,
+            })
+        })
+        .collect())
+}
+
+/// Container of the profile named like the query (ignoring case)
+pub fn find_container(profile: &Path, query: &str) -> Result<Container> {
+    let containers = read_containers(profile)?;
+
+    containers
+        .iter()
+        .find(|container| container.name.eq_ignore_ascii_case(query))
+        .cloned()
+        .ok_or_else(|| {
+            let available: Vec<_> = containers.iter().map(|c| c.name.as_str()).collect();
+
+            match available.is_empty() {
+                true => anyhow!("No containers found in {}", profile.display()),
+                false => anyhow!(
+                    "No container named {query} (available: {})",
+                    available.join(", ")
+                ),
+            }
+        })
+}
+
+/// URL handled by the "Open external links in a container" extension
+/// (e.g. `ext+container:name=Work&url=https%3A%2F%2Fexample.com`)
+pub fn container_url(container: &str, url: &str) -> String {
+    // Spaces as `%20`, the extension doesn't decode `+`
+    let encode = |value: &str| {
+        form_urlencoded::byte_serialize(value.as_bytes())
+            .collect::<String>()
+            .replace('+', "%20")
+    };
+
+    format!(
+        "{EXT_CONTAINER}name={}&url={}",
+        encode(container),
+        encode(url)
+    )
+}
+
+/// Accepts any container name, offering the containers of local Firefox-based
+/// profiles to completions and help
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ContainerParser;
+
+impl TypedValueParser for ContainerParser {
+    type Value = String;
+
+    fn parse_ref(
+        &self,
+        cmd: &Command,
+        arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        clap::builder::NonEmptyStringValueParser::new().parse_ref(cmd, arg, value)
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        let roots = Roots::from_env();
+        let gecko: Vec<_> = Kind::all().into_iter().filter(Kind::is_gecko).collect();
+
+        let names: BTreeSet<_> = profiles::find_profiles(Platform::current(), &roots.home, &gecko)
+            .iter()
+            .flat_map(|profile| read_containers(&profile.path).unwrap_or_default())
+            .map(|container| container.name)
+            .collect();
+
+        (!names.is_empty()).then(|| {
+            Box::new(names.into_iter().map(PossibleValue::new))
+                as Box<dyn Iterator<Item = PossibleValue>>
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn reads_containers_and_builds_urls() {
+        let td = TempDir::new().unwrap();
+        fs::write(
+            td.path().join(FIREFOX_CONTAINERS),
+            r#"{
+                "version": 5,
+                "lastUserContextId": 7,
+                "identities": [
+                    { "userContextId": 1, "public": true, "icon": "fingerprint", "color": "blue",
+                      "l10nID": "userContextPersonal.label", "accessKey": "userContextPersonal.accesskey" },
+                    { "userContextId": 2, "public": true, "icon": "briefcase", "color": "orange",
+                      "l10nID": "userContextWork.label", "accessKey": "userContextWork.accesskey" },
+                    { "userContextId": 5, "public": false, "icon": "", "color": "",
+                      "name": "userContextIdInternal.thumbnail", "accessKey": "" },
+                    { "userContextId": 7, "public": true, "icon": "briefcase", "color": "red",
+                      "name": "Customer A & B" }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            read_containers(td.path())
+                .unwrap()
+                .into_iter()
+                .map(|c| (c.id, c.name))
+                .collect::<Vec<_>>(),
+            vec![
+                (1, "Personal".to_string()),
+                (2, "Work".to_string()),
+                (7, "Customer A & B".to_string()),
+            ]
+        );
+
+        assert_eq!(find_container(td.path(), "work").unwrap().id, 2);
+        assert_eq!(
+            find_container(td.path(), "Shopping")
+                .unwrap_err()
+                .to_string(),
+            "No container named Shopping (available: Personal, Work, Customer A & B)"
+        );
+
+        assert_eq!(
+            container_url(
+                "Customer A & B",
+                "https://console.example.com/?tenant=a&b=1"
+            ),
+            "ext+container:name=Customer%20A%20%26%20B&url=https%3A%2F%2Fconsole.example.com%2F%3Ftenant%3Da%26b%3D1"
+        );
+
+        assert!(
+            read_containers(&td.path().join("missing"))
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
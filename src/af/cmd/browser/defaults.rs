@@ -0,0 +1,236 @@
+use crate::cmd::browser::structures::{self, Channel, Kind, Platform};
+use crate::consts::{LAUNCH_SERVICES, MIMEAPPS, XDG_SETTINGS};
+use crate::discovery::Roots;
+use crate::utils;
+use Kind::*;
+
+use log::debug;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Bundle identifiers of macOS browsers, as LaunchServices records them (lowercase)
+const BUNDLE_IDS: &[(&str, Kind, Channel)] = &[
+    ("company.thebrowser.browser", Arc, Channel::Stable),
+    ("com.brave.browser", Brave, Channel::Stable),
+    ("com.brave.browser.beta", Brave, Channel::Beta),
+    ("com.brave.browser.nightly", Brave, Channel::Nightly),
+    ("com.google.chrome", Chrome, Channel::Stable),
+    ("com.google.chrome.beta", Chrome, Channel::Beta),
+    ("com.google.chrome.dev", Chrome, Channel::Dev),
+    ("com.google.chrome.canary", Chrome, Channel::Canary),
+    ("org.chromium.chromium", Chromium, Channel::Stable),
+    ("com.microsoft.edgemac", Edge, Channel::Stable),
+    ("com.microsoft.edgemac.beta", Edge, Channel::Beta),
+    ("com.microsoft.edgemac.dev", Edge, Channel::Dev),
+    ("com.microsoft.edgemac.canary", Edge, Channel::Canary),
+    ("org.mozilla.firefox", Firefox, Channel::Stable),
+    (
+        "org.mozilla.firefoxdeveloperedition",
+        Firefox,
+        Channel::Developer,
+    ),
+    ("org.mozilla.nightly", Firefox, Channel::Nightly),
+    ("io.gitlab.librewolf-community", LibreWolf, Channel::Stable),
+    ("com.operasoftware.opera", Opera, Channel::Stable),
+    ("com.operasoftware.operanext", Opera, Channel::Beta),
+    ("com.operasoftware.operadeveloper", Opera, Channel::Dev),
+    ("com.apple.safari", Safari, Channel::Stable),
+    (
+        "com.apple.safaritechnologypreview",
+        Safari,
+        Channel::Preview,
+    ),
+    ("com.vivaldi.vivaldi", Vivaldi, Channel::Stable),
+    ("com.vivaldi.vivaldi.snapshot", Vivaldi, Channel::Dev),
+    ("app.zen-browser.zen", Zen, Channel::Stable),
+];
+
+/// Kind and channel of the system's default browser, if it's a known one
+pub fn detect(platform: Platform, roots: &Roots) -> Option<(Kind, Channel)> {
+    let detected = match platform {
+        Platform::MacOs => from_launch_services(&roots.home.join(LAUNCH_SERVICES)),
+        Platform::Linux => from_xdg_settings().or_else(|| from_mimeapps(&mimeapps_files(roots))),
+    };
+
+    debug!("Default browser: {detected:?}");
+    detected
+}
+
+/// Handler of `https` URLs from the LaunchServices preferences, Safari if the
+/// user never chose another browser
+pub fn from_launch_services(plist: &Path) -> Option<(Kind, Channel)> {
+    let Ok(value) = plist::Value::from_file(plist) else {
+        debug!("No LaunchServices preferences in {}", plist.display());
+        return Some((Safari, Channel::Stable));
+    };
+
+    let handler = value
+        .as_dictionary()?
+        .get("LSHandlers")?
+        .as_array()?
+        .iter()
+        .filter_map(plist::Value::as_dictionary)
+        .find(|handler| {
+            handler
+                .get("LSHandlerURLScheme")
+                .and_then(plist::Value::as_string)
+                .is_some_and(|scheme| scheme == "https")
+        });
+
+    let Some(handler) = handler else {
+        return Some((Safari, Channel::Stable));
+    };
+
+    let bundle_id = handler
+        .get("LSHandlerRoleAll")
+        .and_then(plist::Value::as_string)?
+        .to_lowercase();
+
+    BUNDLE_IDS
+        .iter()
+        .find(|(id, _, _)| *id == bundle_id)
+        .map(|(_, kind, channel)| (*kind, *channel))
+}
+
+/// Desktop entry reported by `xdg-settings get default-web-browser`
+fn from_xdg_settings() -> Option<(Kind, Channel)> {
+    let output = Command::new(XDG_SETTINGS)
+        .args(["get", "default-web-browser"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+
+    structures::from_desktop_id(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// `mimeapps.list` files in order of precedence
+fn mimeapps_files(roots: &Roots) -> Vec<PathBuf> {
+    let config_home = utils::config_dir()
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| roots.home.join(".config"));
+
+    [config_home, "/etc/xdg".into()]
+        .into_iter()
+        .map(|dir| dir.join(MIMEAPPS))
+        .chain(
+            roots
+                .data_dirs
+                .iter()
+                .map(|dir| dir.join("applications").join(MIMEAPPS)),
+        )
+        .collect()
+}
+
+/// Handler of `https` URLs from the first `mimeapps.list` which sets a known browser
+pub fn from_mimeapps(files: &[PathBuf]) -> Option<(Kind, Channel)> {
+    files
+        .iter()
+        .filter_map(|file| fs::read_to_string(file).ok())
+        .find_map(|content| {
+            utils::parse_ini(&content)
+                .into_iter()
+                .find(|(section, _)| section == "Default Applications")?
+                .1
+                .get("x-scheme-handler/https")?
+                .split(';')
+                .find_map(structures::from_desktop_id)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn reads_launch_services_handlers() {
+        let td = TempDir::new().unwrap();
+        let plist = td.path().join("com.apple.launchservices.secure.plist");
+
+        let write = |bundle_id: &str| {
+            fs::write(
+                &plist,
+                format!(
+                    r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+    <key>LSHandlers</key>
+    <array>
+        <dict>
+            <key>LSHandlerContentType</key>
+            <string>public.html</string>
+            <key>LSHandlerRoleAll</key>
+            <string>com.apple.safari</string>
+        </dict>
+        <dict>
+            <key>LSHandlerURLScheme</key>
+            <string>https</string>
+            <key>LSHandlerRoleAll</key>
+            <string>{bundle_id}</string>
+        </dict>
+    </array>
+</dict>
+</plist>
+"#
+                ),
+            )
+            .unwrap();
+        };
+
+        write("com.google.Chrome.beta");
+        assert_eq!(from_launch_services(&plist), Some((Chrome, Channel::Beta)));
+
+        write("org.mozilla.firefoxdeveloperedition");
+        assert_eq!(
+            from_launch_services(&plist),
+            Some((Firefox, Channel::Developer))
+        );
+
+        write("com.example.unknown");
+        assert_eq!(from_launch_services(&plist), None);
+
+        assert_eq!(
+            from_launch_services(&td.path().join("missing.plist")),
+            Some((Safari, Channel::Stable))
+        );
+    }
+
+    #[test]
+    fn reads_mimeapps_lists() {
+        let td = TempDir::new().unwrap();
+        let user = td.path().join("config/mimeapps.list");
+        let system = td.path().join("share/applications/mimeapps.list");
+
+        fs::create_dir_all(user.parent().unwrap()).unwrap();
+        fs::create_dir_all(system.parent().unwrap()).unwrap();
+        fs::write(
+            &user,
+            "[Added Associations]\nx-scheme-handler/https=google-chrome.desktop;\n",
+        )
+        .unwrap();
+        fs::write(
+            &system,
+            "[Default Applications]\nx-scheme-handler/https=org.mozilla.firefox.desktop;firefox.desktop\n",
+        )
+        .unwrap();
+
+        let files = [user.clone(), system];
+        assert_eq!(from_mimeapps(&files), Some((Firefox, Channel::Stable)));
+
+        fs::write(
+            &user,
+            "[Default Applications]\nx-scheme-handler/https=unknown.desktop;google-chrome-beta.desktop;\n",
+        )
+        .unwrap();
+        assert_eq!(from_mimeapps(&files), Some((Chrome, Channel::Beta)));
+
+        assert_eq!(
+            structures::from_desktop_id("chromium_chromium.desktop\n"),
+            Some((Chromium, Channel::Stable))
+        );
+    }
+}
@@ -1,38 +1,25 @@
-mod structures;
+pub mod containers;
+pub mod defaults;
+mod open;
+pub mod places;
+pub mod profiles;
+pub mod rules;
+pub mod structures;
+pub mod tabs;
+pub mod versions;
+
+use crate::cmd::browser::structures::{Channel, Format, Kind, Platform};
+use crate::consts::{DEFAULT_HOMEBREW_PREFIX, HOMEBREW_PREFIX};
+use crate::discovery::Roots;
 
-use crate::cmd::browser::structures::Kind;
-use crate::consts::{DEFAULT_HOMEBREW_PREFIX, FLAG_NEW_TAB, FLAG_URL, HOMEBREW_PREFIX};
-
-use anyhow::bail;
 use clap::Subcommand;
-use std::{
-    env,
-    process::{Command, Stdio},
-};
-use url::Url;
+use std::{env, path::PathBuf};
 
 #[derive(Debug, Subcommand)]
 pub enum Browser {
-    /// Group of browser subcommands (alias: b)
+    /// Open a URL in the browser chosen by routing rules (alias: o)
     #[command(visible_aliases = ["o"])]
-    Open {
-        /// Open each URL in a new tab (default: true)
-        #[arg(long, default_value_t = true, require_equals = true)]
-        new_tab: std::primitive::bool,
-
-        /// Browser to use for opening the URL
-        #[arg(
-            long,
-            short,
-            value_enum,
-            value_name = "BROWSER",
-            default_value_t = Kind::Firefox,
-        )]
-        browser: Kind,
-
-        /// The URL to open (must be a valid absolute URL)
-        url: Url,
-    },
+    Open(open::Open),
 
     /// Find installed browsers (alias: f)
     #[command(visible_aliases = ["f"])]
@@ -48,61 +35,100 @@ pub enum Browser {
         )]
         kinds: Vec<Kind>,
 
+        /// Filter results by release channels (comma-separated)
+        #[arg(
+            long = "channel",
+            short,
+            value_enum,
+            value_name = "CHANNEL",
+            value_delimiter = ',',
+            default_values_t = Channel::all(),
+        )]
+        channels: Vec<Channel>,
+
         /// Show all matching results (not just the first one per kind)
         #[arg(long, short)]
         all: bool,
 
         /// Display only the full paths to executables
-        #[arg(long, short)]
+        #[arg(long, short, conflicts_with = "format")]
         path: bool,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = Format::Table)]
+        format: Format,
+    },
+
+    /// Search the browsing history of all browsers and profiles, and open a result (alias: h)
+    #[command(visible_aliases = ["h"])]
+    History(places::Search),
+
+    /// Search the bookmarks of all browsers and profiles, and open a result (alias: b)
+    #[command(visible_aliases = ["b"])]
+    Bookmarks(places::Search),
+
+    /// List the open tabs of all browsers and profiles, or restore saved ones (alias: t)
+    #[command(visible_aliases = ["t"])]
+    Tabs(tabs::Tabs),
+
+    /// List browser profiles (alias: p)
+    #[command(visible_aliases = ["p"])]
+    Profiles {
+        /// Filter results by browser kinds (comma-separated)
+        #[arg(
+            long = "kind",
+            short,
+            value_enum,
+            value_name = "BROWSER",
+            value_delimiter = ',',
+            default_values_t = Kind::all(),
+        )]
+        kinds: Vec<Kind>,
     },
 }
 
+/// Directories searched for browsers in addition to the well-known locations
+pub fn search_dirs() -> Vec<PathBuf> {
+    vec![
+        env::var(HOMEBREW_PREFIX)
+            .unwrap_or(DEFAULT_HOMEBREW_PREFIX.to_string())
+            .into(),
+        "/Applications".into(),
+    ]
+}
+
 impl Browser {
     pub fn run(&self) -> anyhow::Result<()> {
-        let dirs = vec![
-            env::var(HOMEBREW_PREFIX)
-                .unwrap_or(DEFAULT_HOMEBREW_PREFIX.to_string())
-                .into(),
-            "/Applications".into(),
-        ];
+        let roots = Roots::from_env();
+        let dirs = search_dirs();
 
         match self {
-            Self::Open {
-                new_tab,
-                browser,
-                url,
+            Browser::Open(open) => open.run(&roots, &dirs),
+            Browser::History(search) => search.run(places::Origin::History, &roots, &dirs),
+            Browser::Bookmarks(search) => search.run(places::Origin::Bookmarks, &roots, &dirs),
+            Browser::Tabs(tabs) => tabs.run(&roots, &dirs),
+            Browser::Find {
+                kinds,
+                channels,
+                all,
+                path,
+                format,
             } => {
-                if !cfg!(target_os = "macos") {
-                    bail!("This subcommand is not supported on non-macOS systems");
-                }
-
-                let mut args = vec![FLAG_URL, url.as_str()];
+                let browsers = structures::find_browsers(&roots, &dirs, kinds, channels, *all);
 
-                if *new_tab {
-                    args.insert(0, FLAG_NEW_TAB);
+                if *path {
+                    for b in browsers {
+                        println!("{}", b.path().display());
+                    }
+                } else if !browsers.is_empty() || *format != Format::Table {
+                    println!("{}", structures::format_browsers(&browsers, *format)?);
                 }
 
-                let browser = structures::find_browser(&dirs, *browser)?;
-
-                Command::new(browser.path())
-                    .stdin(Stdio::null())
-                    .stdout(Stdio::null())
-                    .stderr(Stdio::null())
-                    .args(&args)
-                    .spawn()?;
-
                 Ok(())
             }
-            Browser::Find { kinds, all, path } => {
-                let browsers = structures::find_browsers(&dirs, kinds, *all);
-
-                for b in browsers {
-                    if *path {
-                        println!("{}", b.path().display());
-                    } else {
-                        println!("{b}");
-                    }
+            Browser::Profiles { kinds } => {
+                for profile in profiles::find_profiles(Platform::current(), &roots.home, kinds) {
+                    println!("{profile}");
                 }
 
                 Ok(())
@@ -0,0 +1,569 @@
+use crate::cmd::browser::containers::{self, ContainerParser};
+use crate::cmd::browser::defaults;
+use crate::cmd::browser::profiles::{self, Profile};
+use crate::cmd::browser::rules::{self, Route};
+use crate::cmd::browser::structures::{
+    self, Browser, Channel, Choice, Kind, Launch, Platform, Window,
+};
+use crate::config::Config;
+use crate::discovery::Roots;
+
+use anyhow::{Result, anyhow, bail};
+use clap::Args;
+use console::style;
+use log::debug;
+use std::{
+    io::{self, Read},
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+use url::Url;
+
+/// Argument reading URLs from stdin
+const STDIN: &str = "-";
+
+#[derive(Debug, Default, Args)]
+pub struct Open {
+    /// Open the URLs in new tabs, or in a new window if false (default: true, unless a rule sets the window)
+    #[arg(
+        long,
+        require_equals = true,
+        num_args = 0..=1,
+        default_missing_value = "true",
+        group = "window",
+    )]
+    new_tab: Option<bool>,
+
+    /// Open the URLs in a new window
+    #[arg(long, group = "window")]
+    new_window: bool,
+
+    /// Open each URL in an app window without tabs and toolbars (Chromium-based browsers only)
+    #[arg(long, group = "window")]
+    app: bool,
+
+    /// Open the URLs in a private (incognito) window
+    #[arg(long, visible_alias = "incognito")]
+    private: bool,
+
+    /// Keep the browser in the background (macOS only)
+    #[arg(long, short = 'g')]
+    background: bool,
+
+    /// Browser to use for opening the URL, instead of the one chosen by rules
+    /// (default: the system default browser, or firefox if it isn't a known one)
+    #[arg(long, short, value_enum, value_name = "BROWSER")]
+    browser: Option<Choice>,
+
+    /// Release channel of the browser (default: stable, if installed)
+    #[arg(long, value_enum)]
+    channel: Option<Channel>,
+
+    /// Profile to open the URL in, by directory, name or account email
+    #[arg(long, short = 'P')]
+    profile: Option<String>,
+
+    /// Firefox Multi-Account Container to open the URL in (requires the "Open external links
+    /// in a container" extension)
+    #[arg(long, value_parser = ContainerParser)]
+    container: Option<String>,
+
+    /// Print the matching rules and how the URL would be opened, without opening it
+    #[arg(long)]
+    explain: bool,
+
+    /// URLs to open, `-` reads newline-separated URLs from stdin (default: URLs in the clipboard)
+    #[arg(value_name = "URL")]
+    urls: Vec<String>,
+}
+
+/// Browser, channel, profile and window chosen for a URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Target {
+    kind: Kind,
+    channel: Option<Channel>,
+    profile: Option<String>,
+    container: Option<String>,
+    launch: Launch,
+}
+
+impl Open {
+    /// Opens the URLs as if they were given on the command line, without flags
+    pub fn new(urls: Vec<String>) -> Self {
+        Self {
+            urls,
+            ..Self::default()
+        }
+    }
+
+    /// Opens the URLs in a new window of the browser, or of the one chosen by rules
+    pub fn window(urls: Vec<String>, browser: Option<Choice>, profile: Option<String>) -> Self {
+        Self {
+            urls,
+            new_window: true,
+            browser,
+            profile,
+            ..Self::default()
+        }
+    }
+
+    pub fn run(&self, roots: &Roots, dirs: &[PathBuf]) -> Result<()> {
+        let config = Config::load()?;
+        let system = defaults::detect(Platform::current(), roots);
+        let routes = self
+            .urls()?
+            .into_iter()
+            .map(|url| rules::route(&config.browser.rules, url))
+            .collect::<Result<Vec<_>>>()?;
+
+        if self.explain {
+            for (index, route) in routes.iter().enumerate() {
+                if index > 0 {
+                    println!();
+                }
+
+                let source = match (self.browser, route.browser, system) {
+                    (Some(Choice::Default), _, _) | (None, None, Some(_)) => " (system default)",
+                    (Some(_), _, _) => " (--browser)",
+                    (None, Some(_), _) => "",
+                    (None, None, None) => " (default)",
+                };
+
+                self.target(route, system).explain(route, source);
+            }
+
+            return Ok(());
+        }
+
+        for (target, urls) in self.batches(&routes, system) {
+            target.open(roots, dirs, &urls)?;
+        }
+
+        Ok(())
+    }
+
+    /// URLs from the arguments and stdin, or from the clipboard if none are given
+    ///
+    /// Invalid URLs are reported and skipped.
+    fn urls(&self) -> Result<Vec<Url>> {
+        let mut input = String::new();
+
+        if self.urls.is_empty() {
+            input = cli_clipboard::get_contents()
+                .map_err(|err| anyhow!("No URL given and the clipboard can't be read: {err}"))?;
+        }
+
+        for arg in &self.urls {
+            match arg.as_str() {
+                STDIN => {
+                    io::stdin().read_to_string(&mut input)?;
+                }
+                url => input.push_str(url),
+            }
+
+            input.push('\n');
+        }
+
+        let urls = parse_urls(&input);
+
+        if urls.is_empty() {
+            bail!("No valid URLs to open");
+        }
+
+        Ok(urls)
+    }
+
+    /// Routed URLs grouped by target, each group opening in a single browser invocation
+    fn batches(
+        &self,
+        routes: &[Route],
+        system: Option<(Kind, Channel)>,
+    ) -> Vec<(Target, Vec<String>)> {
+        let mut batches: Vec<(Target, Vec<String>)> = vec![];
+
+        for route in routes {
+            let target = self.target(route, system);
+            let url = route.url.to_string();
+
+            match batches.iter_mut().find(|(batch, _)| *batch == target) {
+                Some((_, urls)) => urls.push(url),
+                None => batches.push((target, vec![url])),
+            }
+        }
+
+        batches
+    }
+
+    /// Combines the route with the flags, an explicit browser replacing the rule's
+    /// browser, channel and profile
+    ///
+    /// URLs no rule routes open in the system's default browser, or in Firefox
+    /// if it isn't a known one.
+    fn target(&self, route: &Route, system: Option<(Kind, Channel)>) -> Target {
+        let (default_kind, default_channel) = match system {
+            Some((kind, channel)) => (kind, Some(channel)),
+            None => (Kind::Firefox, None),
+        };
+
+        let (mut kind, mut channel, mut profile, container) = match (self.browser, route.browser) {
+            (Some(Choice::Kind(kind)), _) => (
+                kind,
+                self.channel,
+                self.profile.clone(),
+                self.container.clone(),
+            ),
+            (Some(Choice::Default), _) | (None, None) => (
+                default_kind,
+                self.channel.or(default_channel),
+                self.profile.clone(),
+                self.container.clone(),
+            ),
+            (None, Some(kind)) => (
+                kind,
+                self.channel.or(route.channel),
+                self.profile.clone().or_else(|| route.profile.clone()),
+                self.container.clone().or_else(|| route.container.clone()),
+            ),
+        };
+
+        // Containers are a Firefox feature, so unless the browser was chosen
+        // explicitly, a container moves the URL to Firefox
+        if container.is_some() && !kind.is_gecko() && self.browser.is_none() {
+            kind = Kind::Firefox;
+            channel = self.channel;
+            profile = self.profile.clone();
+        }
+
+        let window = match (self.new_window, self.app, self.new_tab) {
+            (true, _, _) | (_, _, Some(false)) => Some(Window::Window),
+            (_, true, _) => Some(Window::App),
+            (_, _, Some(true)) => Some(Window::Tab),
+            _ => None,
+        };
+
+        let launch = Launch {
+            window: window.or(route.window).unwrap_or_default(),
+            private: self.private || route.private.unwrap_or_default(),
+            background: self.background,
+        };
+
+        Target {
+            kind,
+            channel,
+            profile,
+            container,
+            launch,
+        }
+    }
+}
+
+/// Valid URLs on the lines of the input, reporting invalid ones on stderr
+fn parse_urls(input: &str) -> Vec<Url> {
+    input
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| match Url::parse(line) {
+            Ok(url) => Some(url),
+            Err(err) => {
+                eprintln!("{:>8} {line} ({err})", style("skip").yellow());
+                None
+            }
+        })
+        .collect()
+}
+
+impl Target {
+    fn open(&self, roots: &Roots, dirs: &[PathBuf], urls: &[String]) -> Result<()> {
+        let platform = Platform::current();
+        let browser = structures::find_browser(roots, dirs, self.kind, self.channel)?;
+
+        let profile = match &self.profile {
+            Some(query) => Some(profiles::find_profile(
+                platform,
+                &roots.home,
+                &browser,
+                query,
+            )?),
+            None => None,
+        };
+        let profile_args = profile.as_ref().map(Profile::args).unwrap_or_default();
+
+        let urls = match &self.container {
+            Some(name) => self.container_urls(platform, roots, &browser, profile, name, urls)?,
+            None => urls.to_vec(),
+        };
+        let urls: Vec<_> = urls.iter().map(String::as_str).collect();
+
+        for (program, args) in browser.open_commands(platform, &urls, self.launch, &profile_args)? {
+            debug!("Running {} {}", program.display(), args.join(" "));
+
+            Command::new(program)
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .args(&args)
+                .spawn()?;
+        }
+
+        Ok(())
+    }
+
+    /// URLs opening in the container of the profile (or of the default one)
+    fn container_urls(
+        &self,
+        platform: Platform,
+        roots: &Roots,
+        browser: &Browser,
+        profile: Option<Profile>,
+        name: &str,
+        urls: &[String],
+    ) -> Result<Vec<String>> {
+        if !self.kind.is_gecko() {
+            bail!("{} doesn't support containers", self.kind);
+        }
+
+        if self.launch.private {
+            bail!("Containers aren't available in private windows");
+        }
+
+        let profile = match profile {
+            Some(profile) => profile,
+            None => profiles::default_profile(platform, &roots.home, browser)?,
+        };
+        let container = containers::find_container(&profile.path, name)?;
+
+        Ok(urls
+            .iter()
+            .map(|url| containers::container_url(&container.name, url))
+            .collect())
+    }
+
+    fn explain(&self, route: &Route, source: &str) {
+        let label = |label: &str| style(format!("{label:>9}")).cyan();
+
+        if route.matched.is_empty() {
+            println!("{} {}", label("rules"), style("none matched").dim());
+        }
+
+        for name in &route.matched {
+            println!("{} {name}", label("rule"));
+        }
+
+        println!("{} {}", label("url"), route.url);
+        println!("{} {}{source}", label("browser"), self.kind);
+
+        if let Some(channel) = self.channel {
+            println!("{} {channel}", label("channel"));
+        }
+
+        if let Some(profile) = &self.profile {
+            println!("{} {profile}", label("profile"));
+        }
+
+        if let Some(container) = &self.container {
+            println!("{} {container}", label("container"));
+        }
+
+        println!("{} {}", label("window"), self.launch.window);
+
+        if self.launch.private {
+            println!("{} yes", label("private"));
+        }
+
+        if self.launch.background {
+            println!("{} yes", label("bg"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Debug, Parser)]
+    struct Cli {
+        #[command(flatten)]
+        open: Open,
+    }
+
+    fn open(args: &[&str]) -> Open {
+        Cli::parse_from(["open"].iter().chain(args)).open
+    }
+
+    fn target(args: &[&str], route: Route) -> Target {
+        open(args).target(&route, None)
+    }
+
+    fn route() -> Route {
+        Route {
+            url: Url::parse("https://github.com/acme").unwrap(),
+            browser: Some(Kind::Chrome),
+            channel: Some(Channel::Beta),
+            profile: Some("Work".into()),
+            container: None,
+            window: Some(Window::Window),
+            private: None,
+            matched: vec!["work github".into()],
+        }
+    }
+
+    #[test]
+    fn flags_override_rules() {
+        let from_rule = target(&["https://github.com/acme"], route());
+        assert_eq!(from_rule.kind, Kind::Chrome);
+        assert_eq!(from_rule.channel, Some(Channel::Beta));
+        assert_eq!(from_rule.profile.as_deref(), Some("Work"));
+        assert_eq!(from_rule.launch.window, Window::Window);
+
+        let overridden = target(
+            &["-b", "firefox", "--new-tab", "https://github.com/acme"],
+            route(),
+        );
+        assert_eq!(overridden.kind, Kind::Firefox);
+        assert_eq!(overridden.channel, None);
+        assert_eq!(overridden.profile, None);
+        assert_eq!(overridden.launch.window, Window::Tab);
+
+        let unrouted = Route {
+            browser: None,
+            channel: None,
+            profile: None,
+            container: None,
+            window: None,
+            private: None,
+            matched: vec![],
+            ..route()
+        };
+        let default = target(&["-P", "personal", "https://example.com"], unrouted);
+        assert_eq!(default.kind, Kind::Firefox);
+        assert_eq!(default.profile.as_deref(), Some("personal"));
+        assert_eq!(default.launch, Launch::default());
+
+        let app = target(&["--app", "--incognito", "https://example.com"], route());
+        assert_eq!(app.launch.window, Window::App);
+        assert!(app.launch.private);
+    }
+
+    #[test]
+    fn honours_the_system_default_browser() {
+        let system = Some((Kind::Chrome, Channel::Beta));
+        let unrouted = Route {
+            browser: None,
+            channel: None,
+            profile: None,
+            container: None,
+            window: None,
+            matched: vec![],
+            ..route()
+        };
+
+        let default = open(&["https://example.com"]).target(&unrouted, system);
+        assert_eq!(default.kind, Kind::Chrome);
+        assert_eq!(default.channel, Some(Channel::Beta));
+
+        let stable =
+            open(&["--channel", "stable", "https://example.com"]).target(&unrouted, system);
+        assert_eq!(stable.channel, Some(Channel::Stable));
+
+        // Rules win over the system default, unless it's asked for
+        let routed = open(&["https://github.com/acme"]).target(&route(), system);
+        assert_eq!(routed.kind, Kind::Chrome);
+        assert_eq!(routed.profile.as_deref(), Some("Work"));
+
+        let explicit = open(&["-b", "default", "https://github.com/acme"]).target(&route(), system);
+        assert_eq!(explicit.kind, Kind::Chrome);
+        assert_eq!(explicit.channel, Some(Channel::Beta));
+        assert_eq!(explicit.profile, None);
+
+        let firefox = open(&["-b", "default", "https://example.com"]).target(&unrouted, None);
+        assert_eq!(firefox.kind, Kind::Firefox);
+        assert_eq!(firefox.channel, None);
+    }
+
+    #[test]
+    fn moves_containers_to_firefox() {
+        let contained = target(
+            &["--container", "Customer A", "https://github.com/acme"],
+            route(),
+        );
+        assert_eq!(contained.kind, Kind::Firefox);
+        assert_eq!(contained.channel, None);
+        assert_eq!(contained.profile, None);
+        assert_eq!(contained.container.as_deref(), Some("Customer A"));
+
+        let zen = target(
+            &["-b", "zen", "--container", "Work", "https://example.com"],
+            route(),
+        );
+        assert_eq!(zen.kind, Kind::Zen);
+
+        // Rejected when opening, not silently moved
+        let chrome = target(
+            &["-b", "chrome", "--container", "Work", "https://example.com"],
+            route(),
+        );
+        assert_eq!(chrome.kind, Kind::Chrome);
+        assert_eq!(chrome.container.as_deref(), Some("Work"));
+
+        let routed = target(
+            &["https://console.example.com"],
+            Route {
+                browser: Some(Kind::Firefox),
+                channel: None,
+                profile: None,
+                container: Some("Customer A".into()),
+                ..route()
+            },
+        );
+        assert_eq!(routed.container.as_deref(), Some("Customer A"));
+    }
+
+    #[test]
+    fn skips_invalid_urls() {
+        let urls =
+            parse_urls("https://github.com/acme/af/pull/1\n\n  not a url\nhttps://example.com  \n");
+
+        assert_eq!(
+            urls.iter().map(Url::as_str).collect::<Vec<_>>(),
+            vec!["https://github.com/acme/af/pull/1", "https://example.com/"]
+        );
+    }
+
+    #[test]
+    fn batches_urls_by_target() {
+        let unrouted = |url: &str| Route {
+            url: Url::parse(url).unwrap(),
+            browser: None,
+            channel: None,
+            profile: None,
+            container: None,
+            window: None,
+            private: None,
+            matched: vec![],
+        };
+
+        let routes = [
+            route(),
+            unrouted("https://example.com"),
+            Route {
+                url: Url::parse("https://github.com/acme/af").unwrap(),
+                ..route()
+            },
+            unrouted("https://example.org"),
+        ];
+
+        let batches = open(&["-"]).batches(&routes, None);
+        let summary: Vec<_> = batches
+            .iter()
+            .map(|(target, urls)| (target.kind, urls.len()))
+            .collect();
+
+        assert_eq!(summary, vec![(Kind::Chrome, 2), (Kind::Firefox, 2)]);
+        assert_eq!(
+            batches[1].1,
+            vec!["https://example.com/", "https://example.org/"]
+        );
+    }
+}
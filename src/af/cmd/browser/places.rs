@@ -0,0 +1,597 @@
+use crate::cmd::browser::open::Open;
+use crate::cmd::browser::profiles::{self, Profile};
+use crate::cmd::browser::structures::{Kind, Platform};
+use crate::consts::{CHROMIUM_BOOKMARKS, CHROMIUM_HISTORY, FIREFOX_PLACES};
+use crate::discovery::Roots;
+
+use anyhow::{Result, bail};
+use clap::Args;
+use console::style;
+use dialoguer::{FuzzySelect, theme::ColorfulTheme};
+use log::debug;
+use rusqlite::Connection;
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fmt::{Display, Formatter},
+    fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use tempfile::TempDir;
+
+/// Reads the places of a browser's database
+type Reader = fn(&Path) -> Result<Vec<Place>>;
+
+/// Microseconds between 1601-01-01, the epoch of Chromium timestamps, and 1970-01-01
+const CHROMIUM_EPOCH_OFFSET: i64 = 11_644_473_600_000_000;
+
+#[derive(Debug, Args)]
+pub struct Search {
+    /// Words the title or URL must all contain (ignoring case)
+    query: Vec<String>,
+
+    /// Search only the browser kinds (comma-separated)
+    #[arg(
+        long = "kind",
+        short,
+        value_enum,
+        value_name = "BROWSER",
+        value_delimiter = ',',
+        default_values_t = Kind::all(),
+    )]
+    kinds: Vec<Kind>,
+
+    /// Maximum number of results
+    #[arg(long, short = 'n', default_value_t = 50)]
+    limit: usize,
+
+    /// Print the results instead of picking one to open
+    #[arg(long, short)]
+    list: bool,
+}
+
+/// What is searched, the browsing history or bookmarks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    History,
+    Bookmarks,
+}
+
+impl Display for Origin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::History => write!(f, "history"),
+            Origin::Bookmarks => write!(f, "bookmarks"),
+        }
+    }
+}
+
+/// Visited or bookmarked URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Place {
+    pub url: String,
+    pub title: String,
+    pub visits: u64,
+    /// Last visit (or when it was bookmarked), in seconds since the Unix epoch
+    pub last_used: i64,
+    /// Browsers and profiles the URL was found in (e.g. `Firefox: work`)
+    pub profiles: Vec<String>,
+}
+
+impl Display for Place {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.title.is_empty() {
+            true => write!(f, "{}", self.url),
+            false => write!(f, "{}  {}", self.title, self.url),
+        }
+    }
+}
+
+impl Place {
+    /// Returns true if the title or URL contains every term (ignoring case)
+    fn matches(&self, terms: &[String]) -> bool {
+        let title = self.title.to_lowercase();
+        let url = self.url.to_lowercase();
+
+        terms
+            .iter()
+            .all(|term| title.contains(term) || url.contains(term))
+    }
+
+    /// Frecency-like score, visits counting less the longer ago the last one was
+    fn score(&self, now: i64) -> f64 {
+        let days = (now - self.last_used).max(0) as f64 / 86_400.0;
+
+        (1.0 + (1.0 + self.visits as f64).ln()) / (1.0 + days / 7.0)
+    }
+}
+
+impl Search {
+    pub fn run(&self, origin: Origin, roots: &Roots, dirs: &[PathBuf]) -> Result<()> {
+        let profiles = profiles::find_profiles(Platform::current(), &roots.home, &self.kinds);
+        let places = profiles
+            .iter()
+            .flat_map(|profile| read_places(profile, origin))
+            .collect::<Vec<_>>();
+
+        let terms: Vec<_> = self.query.iter().map(|term| term.to_lowercase()).collect();
+        let mut places = rank(merge(places), &terms, now());
+        places.truncate(self.limit);
+
+        match (places.is_empty(), self.query.is_empty()) {
+            (true, true) => bail!("Nothing found in the {origin}"),
+            (true, false) => bail!("Nothing in the {origin} matches {:?}", self.query.join(" ")),
+            _ => {}
+        }
+
+        if self.list {
+            for place in &places {
+                println!("{place}  {}", style(place.profiles.join(", ")).dim());
+            }
+
+            return Ok(());
+        }
+
+        let Some(index) = FuzzySelect::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!("Select a URL from the {origin} to open"))
+            .items(&places)
+            .interact_opt()?
+        else {
+            return Ok(());
+        };
+
+        Open::new(vec![places[index].url.clone()]).run(roots, dirs)
+    }
+}
+
+/// Places of the profile, reporting unreadable databases on stderr
+fn read_places(profile: &Profile, origin: Origin) -> Vec<Place> {
+    let (file, read): (_, Reader) = match (profile.kind.is_gecko(), origin) {
+        (true, Origin::History) => (FIREFOX_PLACES, firefox_history),
+        (true, Origin::Bookmarks) => (FIREFOX_PLACES, firefox_bookmarks),
+        (false, Origin::History) => (CHROMIUM_HISTORY, chromium_history),
+        (false, Origin::Bookmarks) => (CHROMIUM_BOOKMARKS, chromium_bookmarks),
+    };
+
+    let path = profile.path.join(file);
+    if !path.exists() {
+        debug!("No {origin} in {}", profile.path.display());
+        return vec![];
+    }
+
+    let label = format!("{}: {}", profile.kind, profile.name);
+
+    match read(&path) {
+        Ok(places) => places
+            .into_iter()
+            .map(|place| Place {
+                profiles: vec![label.clone()],
+                ..place
+            })
+            .collect(),
+        Err(err) => {
+            eprintln!("{:>8} {} ({err:#})", style("skip").yellow(), path.display());
+            vec![]
+        }
+    }
+}
+
+/// Places with the same URL combined, their visits added up
+pub fn merge(places: Vec<Place>) -> Vec<Place> {
+    let mut merged: BTreeMap<String, Place> = BTreeMap::new();
+
+    for place in places {
+        match merged.get_mut(&place.url) {
+            Some(existing) => {
+                existing.visits += place.visits;
+                existing.last_used = existing.last_used.max(place.last_used);
+
+                if existing.title.is_empty() {
+                    existing.title = place.title;
+                }
+
+                for profile in place.profiles {
+                    if !existing.profiles.contains(&profile) {
+                        existing.profiles.push(profile);
+                    }
+                }
+            }
+            None => {
+                merged.insert(place.url.clone(), place);
+            }
+        }
+    }
+
+    merged.into_values().collect()
+}
+
+/// Places matching all terms, best first
+pub fn rank(places: Vec<Place>, terms: &[String], now: i64) -> Vec<Place> {
+    let mut places: Vec<_> = places
+        .into_iter()
+        .filter(|place| place.matches(terms))
+        .collect();
+
+    places.sort_by(|a, b| {
+        b.score(now)
+            .total_cmp(&a.score(now))
+            .then_with(|| b.last_used.cmp(&a.last_used))
+    });
+
+    places
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// Copy of a database the browser may be holding locked, in a private
+/// directory removed when dropped
+struct Snapshot {
+    dir: TempDir,
+    db: PathBuf,
+}
+
+impl Snapshot {
+    /// Copies the database with its write-ahead log, which holds the latest
+    /// changes while the browser is running
+    fn new(path: &Path) -> Result<Self> {
+        let dir = tempfile::Builder::new().prefix("af-").tempdir()?;
+
+        let snapshot = Self {
+            db: dir.path().join(path.file_name().unwrap_or_default()),
+            dir,
+        };
+
+        fs::copy(path, &snapshot.db)?;
+
+        let mut wal = path.as_os_str().to_owned();
+        wal.push("-wal");
+
+        match fs::copy(
+            &wal,
+            snapshot
+                .dir
+                .path()
+                .join(Path::new(&wal).file_name().unwrap()),
+        ) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+            _ => {}
+        }
+
+        Ok(snapshot)
+    }
+
+    fn open(&self) -> Result<Connection> {
+        Ok(Connection::open(&self.db)?)
+    }
+}
+
+/// Runs the query on a copy of the database, mapping rows of
+/// `url, title, visits, last used` to places
+fn query_places(path: &Path, sql: &str, to_unix_secs: fn(i64) -> i64) -> Result<Vec<Place>> {
+    let snapshot = Snapshot::new(path)?;
+    let conn = snapshot.open()?;
+    let mut stmt = conn.prepare(sql)?;
+
+    let places = stmt
+        .query_map([], |row| {
+            Ok(Place {
+                url: row.get(0)?,
+                title: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                visits: row.get::<_, Option<i64>>(2)?.unwrap_or_default().max(0) as u64,
+                last_used: to_unix_secs(row.get::<_, Option<i64>>(3)?.unwrap_or_default()),
+                profiles: vec![],
+            })
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+
+    Ok(places)
+}
+
+fn firefox_time(micros: i64) -> i64 {
+    micros / 1_000_000
+}
+
+fn chromium_time(micros: i64) -> i64 {
+    match micros {
+        0 => 0,
+        micros => (micros - CHROMIUM_EPOCH_OFFSET) / 1_000_000,
+    }
+}
+
+/// Visited pages from the `places.sqlite` of Firefox-based browsers
+pub fn firefox_history(path: &Path) -> Result<Vec<Place>> {
+    query_places(
+        path,
+        "SELECT url, title, visit_count, last_visit_date FROM moz_places
+         WHERE hidden = 0 AND visit_count > 0",
+        firefox_time,
+    )
+}
+
+/// Bookmarks from the `places.sqlite` of Firefox-based browsers
+pub fn firefox_bookmarks(path: &Path) -> Result<Vec<Place>> {
+    query_places(
+        path,
+        "SELECT p.url, coalesce(b.title, p.title), p.visit_count,
+                coalesce(p.last_visit_date, b.dateAdded)
+         FROM moz_bookmarks b JOIN moz_places p ON p.id = b.fk
+         WHERE b.type = 1",
+        firefox_time,
+    )
+}
+
+/// Visited pages from the `History` database of Chromium-based browsers
+pub fn chromium_history(path: &Path) -> Result<Vec<Place>> {
+    query_places(
+        path,
+        "SELECT url, title, visit_count, last_visit_time FROM urls
+         WHERE hidden = 0 AND visit_count > 0",
+        chromium_time,
+    )
+}
+
+/// `Bookmarks` JSON of Chromium-based browsers
+#[derive(Debug, Deserialize)]
+struct Bookmarks {
+    roots: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BookmarkNode {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    name: String,
+    url: Option<String>,
+    /// Microseconds since 1601, as a string
+    date_last_used: Option<String>,
+    date_added: Option<String>,
+    #[serde(default)]
+    children: Vec<BookmarkNode>,
+}
+
+impl BookmarkNode {
+    fn collect(self, places: &mut Vec<Place>) {
+        let time = |date: Option<&str>| {
+            date.and_then(|date| date.parse().ok())
+                .filter(|micros| *micros > 0)
+                .map(chromium_time)
+        };
+
+        if let (true, Some(url)) = (self.kind == "url", self.url) {
+            places.push(Place {
+                url,
+                title: self.name,
+                visits: 0,
+                last_used: time(self.date_last_used.as_deref())
+                    .or_else(|| time(self.date_added.as_deref()))
+                    .unwrap_or_default(),
+                profiles: vec![],
+            });
+        }
+
+        for child in self.children {
+            child.collect(places);
+        }
+    }
+}
+
+/// Bookmarks from the `Bookmarks` file of Chromium-based browsers
+pub fn chromium_bookmarks(path: &Path) -> Result<Vec<Place>> {
+    let bookmarks: Bookmarks = serde_json::from_str(&fs::read_to_string(path)?)?;
+    let mut places = vec![];
+
+    // Besides the folders, roots hold metadata like `sync_transaction_version`
+    for root in bookmarks.roots.into_values() {
+        if let Ok(node) = serde_json::from_value::<BookmarkNode>(root) {
+            node.collect(&mut places);
+        }
+    }
+
+    Ok(places)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const DAY: i64 = 86_400;
+    const NOW: i64 = 1_700_000_000;
+
+    fn place(url: &str, title: &str, visits: u64, last_used: i64, profile: &str) -> Place {
+        Place {
+            url: url.into(),
+            title: title.into(),
+            visits,
+            last_used,
+            profiles: vec![profile.into()],
+        }
+    }
+
+    #[test]
+    fn reads_firefox_places_while_locked() {
+        let td = TempDir::new().unwrap();
+        let path = td.path().join(FIREFOX_PLACES);
+
+        // The browser keeps the database open in WAL mode, recent changes
+        // living only in the log until a checkpoint
+        let browser = Connection::open(&path).unwrap();
+        browser
+            .execute_batch(
+                "PRAGMA journal_mode = WAL;
+                 PRAGMA wal_autocheckpoint = 0;
+                 CREATE TABLE moz_places (
+                     id INTEGER PRIMARY KEY, url TEXT, title TEXT, visit_count INTEGER,
+                     hidden INTEGER DEFAULT 0, last_visit_date INTEGER
+                 );
+                 CREATE TABLE moz_bookmarks (
+                     id INTEGER PRIMARY KEY, type INTEGER, fk INTEGER, title TEXT,
+                     dateAdded INTEGER
+                 );
+                 INSERT INTO moz_places VALUES
+                     (1, 'https://github.com/acme/af/issues/7', 'Crash on start', 3, 0, 1700000000000000),
+                     (2, 'https://example.com/', NULL, 0, 0, NULL),
+                     (3, 'https://github.com/acme/af/pulls', 'Pull requests', 1, 1, 1700000000000000);
+                 INSERT INTO moz_bookmarks VALUES
+                     (1, 2, NULL, 'toolbar', 0),
+                     (2, 1, 2, 'Example', 1600000000000000),
+                     (3, 1, 1, NULL, 1650000000000000);",
+            )
+            .unwrap();
+
+        assert_eq!(
+            firefox_history(&path).unwrap(),
+            vec![Place {
+                url: "https://github.com/acme/af/issues/7".into(),
+                title: "Crash on start".into(),
+                visits: 3,
+                last_used: NOW,
+                profiles: vec![],
+            }]
+        );
+
+        let bookmarks = firefox_bookmarks(&path).unwrap();
+        assert_eq!(
+            bookmarks
+                .iter()
+                .map(|p| (p.title.as_str(), p.last_used))
+                .collect::<Vec<_>>(),
+            vec![("Example", 1_600_000_000), ("Crash on start", NOW)]
+        );
+
+        drop(browser);
+    }
+
+    #[test]
+    fn reads_chromium_history_and_bookmarks() {
+        let td = TempDir::new().unwrap();
+        let history = td.path().join(CHROMIUM_HISTORY);
+
+        Connection::open(&history)
+            .unwrap()
+            .execute_batch(&format!(
+                "CREATE TABLE urls (
+                     id INTEGER PRIMARY KEY, url TEXT, title TEXT, visit_count INTEGER,
+                     last_visit_time INTEGER, hidden INTEGER DEFAULT 0
+                 );
+                 INSERT INTO urls VALUES
+                     (1, 'https://github.com/acme/af/issues/7', 'Crash on start', 2, {}, 0),
+                     (2, 'https://accounts.example.com/redirect', '', 1, {}, 1);",
+                NOW * 1_000_000 + CHROMIUM_EPOCH_OFFSET,
+                NOW * 1_000_000 + CHROMIUM_EPOCH_OFFSET,
+            ))
+            .unwrap();
+
+        assert_eq!(
+            chromium_history(&history).unwrap(),
+            vec![Place {
+                url: "https://github.com/acme/af/issues/7".into(),
+                title: "Crash on start".into(),
+                visits: 2,
+                last_used: NOW,
+                profiles: vec![],
+            }]
+        );
+
+        let bookmarks = td.path().join(CHROMIUM_BOOKMARKS);
+        fs::write(
+            &bookmarks,
+            format!(
+                r#"{{
+                    "checksum": "abc",
+                    "roots": {{
+                        "bookmark_bar": {{
+                            "type": "folder", "name": "Bookmarks bar",
+                            "children": [
+                                {{ "type": "url", "name": "Docs", "url": "https://docs.example.com/",
+                                   "date_added": "{}", "date_last_used": "0" }},
+                                {{ "type": "folder", "name": "Work", "children": [
+                                    {{ "type": "url", "name": "CI", "url": "https://ci.example.com/",
+                                       "date_added": "0", "date_last_used": "{}" }}
+                                ] }}
+                            ]
+                        }},
+                        "other": {{ "type": "folder", "name": "Other", "children": [] }},
+                        "sync_transaction_version": "12"
+                    }},
+                    "version": 1
+                }}"#,
+                NOW * 1_000_000 + CHROMIUM_EPOCH_OFFSET,
+                (NOW + DAY) * 1_000_000 + CHROMIUM_EPOCH_OFFSET,
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(
+            chromium_bookmarks(&bookmarks)
+                .unwrap()
+                .iter()
+                .map(|p| (p.title.as_str(), p.url.as_str(), p.last_used))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Docs", "https://docs.example.com/", NOW),
+                ("CI", "https://ci.example.com/", NOW + DAY),
+            ]
+        );
+    }
+
+    #[test]
+    fn merges_and_ranks_places() {
+        let places = vec![
+            place(
+                "https://github.com/acme/af/issues/7",
+                "",
+                2,
+                NOW - DAY,
+                "Chrome: Work",
+            ),
+            place(
+                "https://github.com/acme/af/issues/7",
+                "Crash on start",
+                3,
+                NOW - 2 * DAY,
+                "Firefox: default",
+            ),
+            place(
+                "https://github.com/acme/af/issues/1",
+                "Old issue",
+                40,
+                NOW - 365 * DAY,
+                "Firefox: default",
+            ),
+            place(
+                "https://github.com/acme/af/issues/9",
+                "Recent issue",
+                1,
+                NOW,
+                "Chrome: Work",
+            ),
+            place("https://example.com/", "Example", 100, NOW, "Chrome: Work"),
+        ];
+
+        let ranked = rank(merge(places), &["acme".into(), "issues".into()], NOW);
+
+        assert_eq!(
+            ranked.iter().map(|p| p.url.as_str()).collect::<Vec<_>>(),
+            vec![
+                "https://github.com/acme/af/issues/7",
+                "https://github.com/acme/af/issues/9",
+                "https://github.com/acme/af/issues/1",
+            ]
+        );
+        assert_eq!(ranked[0].title, "Crash on start");
+        assert_eq!(ranked[0].visits, 5);
+        assert_eq!(ranked[0].last_used, NOW - DAY);
+        assert_eq!(ranked[0].profiles, vec!["Chrome: Work", "Firefox: default"]);
+
+        assert_eq!(rank(merge(ranked), &["crash".into()], NOW).len(), 1);
+    }
+}
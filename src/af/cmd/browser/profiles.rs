@@ -0,0 +1,469 @@
+use crate::cmd::browser::structures::{Browser, Channel, Kind, Platform};
+use crate::consts::{
+    CHROMIUM_LOCAL_STATE, FIREFOX_INSTALLS, FIREFOX_PROFILES, FIREFOX_SIGNED_IN_USER, FLAG_PROFILE,
+    FLAG_PROFILE_DIRECTORY,
+};
+use crate::utils;
+use Kind::*;
+
+use anyhow::{Result, anyhow};
+use log::debug;
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    fmt::{Display, Formatter},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Browser profile which URLs can be opened in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub kind: Kind,
+    /// Channel using the profile, `None` if all channels share it (Firefox-based browsers)
+    pub channel: Option<Channel>,
+    /// Profile directory (Chromium) or name (Firefox) passed to the browser
+    pub id: String,
+    /// Name shown in the browser
+    pub name: String,
+    /// Account signed in to the browser
+    pub email: Option<String>,
+    pub path: PathBuf,
+    /// Last used (Chromium) or default (Firefox) profile
+    pub default: bool,
+}
+
+impl Display for Profile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let channel = self.channel.map(|c| c.to_string());
+
+        write!(
+            f,
+            "{:<9} {:<9} {:<20} {}",
+            self.kind.to_string(),
+            channel.as_deref().unwrap_or("all"),
+            self.id,
+            self.name,
+        )?;
+
+        if let Some(email) = &self.email {
+            write!(f, " <{email}>")?;
+        }
+
+        if self.default {
+            write!(f, " (default)")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Profile {
+    /// Returns true if the directory, name or account email equals the query (ignoring case)
+    pub fn matches(&self, query: &str) -> bool {
+        [Some(&self.id), Some(&self.name), self.email.as_ref()]
+            .into_iter()
+            .flatten()
+            .any(|value| value.eq_ignore_ascii_case(query))
+    }
+
+    /// Returns true if the browser uses the profile
+    pub fn belongs_to(&self, browser: &Browser) -> bool {
+        self.kind == browser.kind() && self.channel.is_none_or(|c| c == browser.channel())
+    }
+
+    /// Command line arguments selecting the profile
+    pub fn args(&self) -> Vec<String> {
+        match self.kind.is_gecko() {
+            true => vec![FLAG_PROFILE.to_string(), self.id.clone()],
+            false => vec![format!("{FLAG_PROFILE_DIRECTORY}={}", self.id)],
+        }
+    }
+}
+
+/// Directory where a browser keeps its profiles
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Store {
+    kind: Kind,
+    channel: Option<Channel>,
+    dir: PathBuf,
+}
+
+impl Store {
+    fn new(kind: Kind, channel: impl Into<Option<Channel>>, dir: PathBuf) -> Self {
+        Self {
+            kind,
+            channel: channel.into(),
+            dir,
+        }
+    }
+
+    fn profiles(&self) -> Result<Vec<Profile>> {
+        match self.kind.is_gecko() {
+            true => self.gecko_profiles(),
+            false => self.chromium_profiles(),
+        }
+    }
+
+    /// Profiles from the `Local State` JSON of Chromium-based browsers
+    fn chromium_profiles(&self) -> Result<Vec<Profile>> {
+        let content = fs::read_to_string(self.dir.join(CHROMIUM_LOCAL_STATE))?;
+        let state: LocalState = serde_json::from_str(&content)?;
+        let last_used = state.profile.last_used.as_deref().unwrap_or("Default");
+
+        Ok(state
+            .profile
+            .info_cache
+            .into_iter()
+            .map(|(id, info)| Profile {
+                kind: self.kind,
+                channel: self.channel,
+                path: self.dir.join(&id),
+                default: id == last_used,
+                email: Some(info.user_name).filter(|email| !email.is_empty()),
+                name: info.name,
+                id,
+            })
+            .collect())
+    }
+
+    /// Profiles from `profiles.ini`, with defaults from its `Install` sections and `installs.ini`
+    fn gecko_profiles(&self) -> Result<Vec<Profile>> {
+        let profiles = utils::parse_ini(&fs::read_to_string(self.dir.join(FIREFOX_PROFILES))?);
+        let installs = fs::read_to_string(self.dir.join(FIREFOX_INSTALLS))
+            .map(|content| utils::parse_ini(&content))
+            .unwrap_or_default();
+
+        let install_defaults: BTreeSet<&str> = profiles
+            .iter()
+            .filter(|(section, _)| section.starts_with("Install"))
+            .chain(&installs)
+            .filter_map(|(_, values)| values.get("Default"))
+            .map(String::as_str)
+            .collect();
+
+        Ok(profiles
+            .iter()
+            .filter(|(section, _)| section.starts_with("Profile"))
+            .filter_map(|(_, values)| {
+                let name = values.get("Name")?;
+                let relative = values.get("Path")?;
+                let path = match values.get("IsRelative").map(String::as_str) {
+                    Some("0") => PathBuf::from(relative),
+                    _ => self.dir.join(relative),
+                };
+
+                let default = match install_defaults.is_empty() {
+                    true => values.get("Default").is_some_and(|v| v == "1"),
+                    false => install_defaults.contains(relative.as_str()),
+                };
+
+                Some(Profile {
+                    kind: self.kind,
+                    channel: self.channel,
+                    id: name.clone(),
+                    name: name.clone(),
+                    email: signed_in_email(&path),
+                    path,
+                    default,
+                })
+            })
+            .collect())
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct LocalState {
+    #[serde(default)]
+    profile: ProfileState,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ProfileState {
+    #[serde(default)]
+    info_cache: BTreeMap<String, ProfileInfo>,
+    last_used: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProfileInfo {
+    #[serde(default)]
+    name: String,
+    /// Email of the signed-in Google (or Microsoft) account, empty if signed out
+    #[serde(default)]
+    user_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SignedInUser {
+    account_data: AccountData,
+}
+
+#[derive(Debug, Deserialize)]
+struct AccountData {
+    email: String,
+}
+
+/// Email of the Firefox account the profile is signed in to
+fn signed_in_email(profile: &Path) -> Option<String> {
+    let content = fs::read_to_string(profile.join(FIREFOX_SIGNED_IN_USER)).ok()?;
+    let user: SignedInUser = serde_json::from_str(&content).ok()?;
+
+    Some(user.account_data.email)
+}
+
+/// Profile directories of the kinds' browsers, for every channel with its own profiles
+fn stores(platform: Platform, home: &Path, kinds: &[Kind]) -> Vec<Store> {
+    let support = home.join("Library/Application Support");
+    let config = home.join(".config");
+
+    let chromium = |kind, channel, mac: &str, linux: Option<&str>| match platform {
+        Platform::MacOs => Some(Store::new(kind, channel, support.join(mac))),
+        Platform::Linux => linux.map(|dir| Store::new(kind, channel, config.join(dir))),
+    };
+
+    let gecko = |kind, mac: &str, linux: &str| match platform {
+        Platform::MacOs => Some(Store::new(kind, None, support.join(mac))),
+        Platform::Linux => Some(Store::new(kind, None, home.join(linux))),
+    };
+
+    let all = [
+        chromium(Arc, Channel::Stable, "Arc/User Data", None),
+        chromium(
+            Brave,
+            Channel::Stable,
+            "BraveSoftware/Brave-Browser",
+            Some("BraveSoftware/Brave-Browser"),
+        ),
+        chromium(
+            Brave,
+            Channel::Beta,
+            "BraveSoftware/Brave-Browser-Beta",
+            Some("BraveSoftware/Brave-Browser-Beta"),
+        ),
+        chromium(
+            Brave,
+            Channel::Nightly,
+            "BraveSoftware/Brave-Browser-Nightly",
+            Some("BraveSoftware/Brave-Browser-Nightly"),
+        ),
+        chromium(
+            Chrome,
+            Channel::Stable,
+            "Google/Chrome",
+            Some("google-chrome"),
+        ),
+        chromium(
+            Chrome,
+            Channel::Beta,
+            "Google/Chrome Beta",
+            Some("google-chrome-beta"),
+        ),
+        chromium(
+            Chrome,
+            Channel::Dev,
+            "Google/Chrome Dev",
+            Some("google-chrome-unstable"),
+        ),
+        chromium(
+            Chrome,
+            Channel::Canary,
+            "Google/Chrome Canary",
+            Some("google-chrome-canary"),
+        ),
+        chromium(Chromium, Channel::Stable, "Chromium", Some("chromium")),
+        chromium(
+            Edge,
+            Channel::Stable,
+            "Microsoft Edge",
+            Some("microsoft-edge"),
+        ),
+        chromium(
+            Edge,
+            Channel::Beta,
+            "Microsoft Edge Beta",
+            Some("microsoft-edge-beta"),
+        ),
+        chromium(
+            Edge,
+            Channel::Dev,
+            "Microsoft Edge Dev",
+            Some("microsoft-edge-dev"),
+        ),
+        chromium(Edge, Channel::Canary, "Microsoft Edge Canary", None),
+        chromium(Vivaldi, Channel::Stable, "Vivaldi", Some("vivaldi")),
+        chromium(
+            Vivaldi,
+            Channel::Dev,
+            "Vivaldi Snapshot",
+            Some("vivaldi-snapshot"),
+        ),
+        gecko(Firefox, "Firefox", ".mozilla/firefox"),
+        gecko(LibreWolf, "librewolf", ".librewolf"),
+        gecko(Zen, "zen", ".zen"),
+    ];
+
+    all.into_iter()
+        .flatten()
+        .filter(|store| kinds.contains(&store.kind))
+        .collect()
+}
+
+/// Profiles of the kinds' browsers found in the home directory
+pub fn find_profiles(platform: Platform, home: &Path, kinds: &[Kind]) -> Vec<Profile> {
+    stores(platform, home, kinds)
+        .into_iter()
+        .filter(|store| store.dir.is_dir())
+        .flat_map(|store| match store.profiles() {
+            Ok(profiles) => profiles,
+            Err(err) => {
+                debug!("Failed to read profiles in {}: {err}", store.dir.display());
+                vec![]
+            }
+        })
+        .collect()
+}
+
+/// Profile of the browser matching the query
+pub fn find_profile(
+    platform: Platform,
+    home: &Path,
+    browser: &Browser,
+    query: &str,
+) -> Result<Profile> {
+    let profiles: Vec<_> = find_profiles(platform, home, &[browser.kind()])
+        .into_iter()
+        .filter(|profile| profile.belongs_to(browser))
+        .collect();
+
+    profiles
+        .iter()
+        .find(|profile| profile.matches(query))
+        .cloned()
+        .ok_or_else(|| {
+            let available: Vec<_> = profiles.iter().map(|p| p.name.as_str()).collect();
+
+            match available.is_empty() {
+                true => anyhow!("No profiles found for {}", browser.kind()),
+                false => anyhow!(
+                    "No {} profile matches {query} (available: {})",
+                    browser.kind(),
+                    available.join(", ")
+                ),
+            }
+        })
+}
+
+/// Default profile of the browser, or its first one
+pub fn default_profile(platform: Platform, home: &Path, browser: &Browser) -> Result<Profile> {
+    let profiles: Vec<_> = find_profiles(platform, home, &[browser.kind()])
+        .into_iter()
+        .filter(|profile| profile.belongs_to(browser))
+        .collect();
+
+    profiles
+        .iter()
+        .find(|profile| profile.default)
+        .or(profiles.first())
+        .cloned()
+        .ok_or_else(|| anyhow!("No profiles found for {}", browser.kind()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn reads_chromium_local_state() {
+        let td = TempDir::new().unwrap();
+        write(
+            &td.path().join(".config/google-chrome-beta/Local State"),
+            r#"{
+                "profile": {
+                    "last_used": "Profile 1",
+                    "info_cache": {
+                        "Default": { "name": "Personal", "user_name": "" },
+                        "Profile 1": { "name": "Work", "user_name": "jane@work.example" }
+                    }
+                }
+            }"#,
+        );
+        // Unreadable stores are skipped
+        write(&td.path().join(".config/google-chrome/Local State"), "{");
+
+        let profiles = find_profiles(Platform::Linux, td.path(), &Kind::all());
+        let summary: Vec<_> = profiles.iter().map(ToString::to_string).collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                "Chrome    beta      Default              Personal",
+                "Chrome    beta      Profile 1            Work <jane@work.example> (default)",
+            ]
+        );
+
+        assert!(profiles[1].matches("JANE@work.example"));
+        assert!(profiles[1].matches("work"));
+        assert!(!profiles[0].matches("Work"));
+        assert_eq!(profiles[1].args(), vec!["--profile-directory=Profile 1"]);
+        assert_eq!(
+            profiles[1].path,
+            td.path().join(".config/google-chrome-beta/Profile 1")
+        );
+    }
+
+    #[test]
+    fn reads_firefox_profiles_ini() {
+        let td = TempDir::new().unwrap();
+        let dir = td.path().join("Library/Application Support/Firefox");
+
+        write(
+            &dir.join(FIREFOX_PROFILES),
+            "[Install4F96D1932A9F858E]\nDefault=Profiles/abc.work\nLocked=1\n\n\
+             [Profile1]\nName=work\nIsRelative=1\nPath=Profiles/abc.work\n\n\
+             [Profile0]\nName=default\nIsRelative=1\nPath=Profiles/xyz.default\nDefault=1\n\n\
+             [General]\nStartWithLastProfile=1\nVersion=2\n",
+        );
+        write(
+            &dir.join("Profiles/abc.work").join(FIREFOX_SIGNED_IN_USER),
+            r#"{"version": 1, "accountData": {"email": "jane@work.example", "uid": "1"}}"#,
+        );
+
+        let profiles = find_profiles(Platform::MacOs, td.path(), &[Firefox, Chrome]);
+
+        assert_eq!(
+            profiles,
+            vec![
+                Profile {
+                    kind: Firefox,
+                    channel: None,
+                    id: "work".into(),
+                    name: "work".into(),
+                    email: Some("jane@work.example".into()),
+                    path: dir.join("Profiles/abc.work"),
+                    default: true,
+                },
+                Profile {
+                    kind: Firefox,
+                    channel: None,
+                    id: "default".into(),
+                    name: "default".into(),
+                    email: None,
+                    path: dir.join("Profiles/xyz.default"),
+                    default: false,
+                },
+            ]
+        );
+
+        assert_eq!(profiles[0].args(), vec![FLAG_PROFILE, "work"]);
+        assert!(find_profiles(Platform::Linux, td.path(), &[Firefox]).is_empty());
+    }
+}
@@ -0,0 +1,323 @@
+use crate::cmd::browser::structures::{Channel, Kind, Window};
+
+use anyhow::{Context, Result, bail};
+use glob::{MatchOptions, Pattern};
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use url::Url;
+
+/// Rule choosing how `af browser open` handles matching URLs
+///
+/// All conditions given must match. Rules without a browser only rewrite the
+/// URL and let the following rules see the result, the first matching rule
+/// with a browser or container decides where the URL opens.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Rule {
+    /// Name printed by `--explain` (defaults to the rule's position)
+    pub name: Option<String>,
+    /// Host globs (e.g. `*.github.com`), any of them must match
+    #[serde(default)]
+    pub hosts: Vec<String>,
+    /// Path globs (e.g. `/acme/**`), any of them must match
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// Regex the whole URL must match
+    pub regex: Option<String>,
+    /// Query parameters with globs their values must match
+    #[serde(default)]
+    pub query: BTreeMap<String, String>,
+    /// Globs of query parameters removed from the URL (e.g. `utm_*`)
+    #[serde(default)]
+    pub strip_query: Vec<String>,
+    /// Replacement of the `regex` match, which can refer to its groups
+    /// (e.g. `zoommtg://zoom.us/join?confno=$1`)
+    pub rewrite: Option<String>,
+    pub browser: Option<Kind>,
+    pub channel: Option<Channel>,
+    /// Profile directory, name or account email
+    pub profile: Option<String>,
+    /// Firefox Multi-Account Container (implies `browser = "firefox"` if no browser is set)
+    pub container: Option<String>,
+    /// `tab`, `window` or `app`
+    pub window: Option<Window>,
+    /// Open in a private (incognito) window
+    pub private: Option<bool>,
+}
+
+/// Result of applying the rules to a URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    pub url: Url,
+    pub browser: Option<Kind>,
+    pub channel: Option<Channel>,
+    pub profile: Option<String>,
+    pub container: Option<String>,
+    pub window: Option<Window>,
+    pub private: Option<bool>,
+    /// Names of the rules which matched, in order
+    pub matched: Vec<String>,
+}
+
+impl Rule {
+    fn name(&self, index: usize) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| format!("#{}", index + 1))
+    }
+
+    fn matches(&self, url: &Url) -> Result<bool> {
+        let host = url.host_str().unwrap_or_default();
+
+        if !self.hosts.is_empty() && !any_glob(&self.hosts, host, false)? {
+            return Ok(false);
+        }
+
+        if !self.paths.is_empty() && !any_glob(&self.paths, url.path(), true)? {
+            return Ok(false);
+        }
+
+        if let Some(regex) = self.regex()?
+            && !regex.is_match(url.as_str())
+        {
+            return Ok(false);
+        }
+
+        for (key, value) in &self.query {
+            let pattern = Pattern::new(value).with_context(|| format!("Invalid glob {value}"))?;
+            let found = url
+                .query_pairs()
+                .any(|(k, v)| k == key.as_str() && pattern.matches(&v));
+
+            if !found {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// URL with the rule's rewrites applied
+    fn apply(&self, url: Url) -> Result<Url> {
+        let mut url = strip_query(url, &self.strip_query)?;
+
+        match (self.regex()?, &self.rewrite) {
+            (Some(regex), Some(rewrite)) => {
+                let rewritten = regex.replace(url.as_str(), rewrite.as_str());
+                url = Url::parse(&rewritten)
+                    .with_context(|| format!("Rewritten URL {rewritten} is invalid"))?;
+            }
+            (None, Some(_)) => bail!("A rewrite requires a regex"),
+            _ => {}
+        }
+
+        Ok(url)
+    }
+
+    fn regex(&self) -> Result<Option<Regex>> {
+        self.regex
+            .as_deref()
+            .map(|regex| Regex::new(regex).with_context(|| format!("Invalid regex {regex}")))
+            .transpose()
+    }
+}
+
+/// Applies the rules to the URL in order
+pub fn route(rules: &[Rule], url: Url) -> Result<Route> {
+    let mut route = Route {
+        url,
+        browser: None,
+        channel: None,
+        profile: None,
+        container: None,
+        window: None,
+        private: None,
+        matched: vec![],
+    };
+
+    for (index, rule) in rules.iter().enumerate() {
+        let name = rule.name(index);
+
+        if !rule
+            .matches(&route.url)
+            .with_context(|| format!("In rule {name}"))?
+        {
+            continue;
+        }
+
+        route.url = rule
+            .apply(route.url)
+            .with_context(|| format!("In rule {name}"))?;
+        route.matched.push(name);
+
+        if rule.browser.is_some() || rule.container.is_some() {
+            route.browser = rule.browser.or(Some(Kind::Firefox));
+            route.channel = rule.channel;
+            route.profile = rule.profile.clone();
+            route.container = rule.container.clone();
+            route.window = rule.window;
+            route.private = rule.private;
+            break;
+        }
+    }
+
+    Ok(route)
+}
+
+fn any_glob(globs: &[String], value: &str, literal_separator: bool) -> Result<bool> {
+    let options = MatchOptions {
+        case_sensitive: literal_separator,
+        require_literal_separator: literal_separator,
+        require_literal_leading_dot: false,
+    };
+
+    for glob in globs {
+        let pattern = Pattern::new(glob).with_context(|| format!("Invalid glob {glob}"))?;
+        if pattern.matches_with(value, options) {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Removes query parameters with names matching any of the globs
+fn strip_query(mut url: Url, globs: &[String]) -> Result<Url> {
+    if globs.is_empty() || url.query().is_none() {
+        return Ok(url);
+    }
+
+    let patterns = globs
+        .iter()
+        .map(|glob| Pattern::new(glob).with_context(|| format!("Invalid glob {glob}")))
+        .collect::<Result<Vec<_>>>()?;
+
+    let kept: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| !patterns.iter().any(|p| p.matches(key)))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+
+    if kept.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(kept);
+    }
+
+    Ok(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    const CONFIG: &str = r#"
+        [[browser.rules]]
+        name = "strip tracking"
+        strip-query = ["utm_*", "fbclid"]
+
+        [[browser.rules]]
+        name = "zoom app"
+        hosts = ["zoom.us", "*.zoom.us"]
+        regex = '^https://[^/]+/j/(\d+)\?pwd=(\w+)'
+        rewrite = "zoommtg://zoom.us/join?confno=$1&pwd=$2"
+        browser = "firefox"
+
+        [[browser.rules]]
+        name = "work github"
+        hosts = ["github.com"]
+        paths = ["/acme", "/acme/**", "/acme-labs/**"]
+        browser = "chrome"
+        profile = "jane@acme.example"
+        window = "window"
+
+        [[browser.rules]]
+        name = "customer consoles"
+        hosts = ["*.console.example.com"]
+        container = "Customer A"
+
+        [[browser.rules]]
+        hosts = ["docs.google.com"]
+        query = { authuser = "1" }
+        browser = "chrome"
+        channel = "beta"
+    "#;
+
+    fn rules() -> Vec<Rule> {
+        toml::from_str::<Config>(CONFIG).unwrap().browser.rules
+    }
+
+    fn route(url: &str) -> Route {
+        super::route(&rules(), Url::parse(url).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn routes_by_host_and_path() {
+        let work = route("https://github.com/acme/af/pull/1?utm_source=slack&tab=files");
+
+        assert_eq!(
+            work.url.as_str(),
+            "https://github.com/acme/af/pull/1?tab=files"
+        );
+        assert_eq!(work.browser, Some(Kind::Chrome));
+        assert_eq!(work.profile.as_deref(), Some("jane@acme.example"));
+        assert_eq!(work.window, Some(Window::Window));
+        assert_eq!(work.matched, vec!["strip tracking", "work github"]);
+
+        let personal = route("https://github.com/acmeish/af");
+        assert_eq!(personal.browser, None);
+        assert_eq!(personal.matched, vec!["strip tracking"]);
+
+        let docs = route("https://DOCS.google.com/document/d/1?authuser=1");
+        assert_eq!(docs.browser, Some(Kind::Chrome));
+        assert_eq!(docs.channel, Some(Channel::Beta));
+        assert_eq!(docs.matched, vec!["strip tracking", "#5"]);
+
+        let console = route("https://eu.console.example.com/");
+        assert_eq!(console.browser, Some(Kind::Firefox));
+        assert_eq!(console.container.as_deref(), Some("Customer A"));
+        assert_eq!(console.matched, vec!["strip tracking", "customer consoles"]);
+
+        assert_eq!(
+            route("https://docs.google.com/document/d/1?authuser=0").browser,
+            None
+        );
+    }
+
+    #[test]
+    fn rewrites_urls() {
+        let zoom = route("https://acme.zoom.us/j/123456?pwd=secret&utm_medium=email");
+
+        assert_eq!(
+            zoom.url.as_str(),
+            "zoommtg://zoom.us/join?confno=123456&pwd=secret"
+        );
+        assert_eq!(zoom.matched, vec!["strip tracking", "zoom app"]);
+
+        let stripped = route("https://example.com/?fbclid=1&utm_source=x");
+        assert_eq!(stripped.url.as_str(), "https://example.com/");
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        let invalid: Config = toml::from_str(
+            "[[browser.rules]]\nname = \"broken\"\nrewrite = \"x\"\nbrowser = \"firefox\"\n",
+        )
+        .unwrap();
+        let err = super::route(
+            &invalid.browser.rules,
+            Url::parse("https://example.com").unwrap(),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            format!("{err:#}"),
+            "In rule broken: A rewrite requires a regex"
+        );
+
+        assert!(toml::from_str::<Config>("[[browser.rules]]\nbrowsr = \"firefox\"\n").is_err());
+        assert!(toml::from_str::<Config>("[[browser.rules]]\nbrowser = \"netscape\"\n").is_err());
+    }
+}
@@ -1,28 +1,184 @@
-use crate::consts::{BREW, FLAG_PREFIX, FLAG_VERSION};
+use crate::cmd::browser::defaults;
+use crate::cmd::browser::versions::{self, VersionCache};
+use crate::consts::{
+    BREW, FLAG_APP, FLAG_APPLICATION, FLAG_BACKGROUND, FLAG_INCOGNITO, FLAG_INPRIVATE,
+    FLAG_NEW_TAB, FLAG_NEW_WINDOW, FLAG_PREFIX, FLAG_PRIVATE, FLAG_PRIVATE_WINDOW, FLAG_VERSION,
+    OPEN,
+};
+use crate::discovery::{PROBE_TIMEOUT, Roots};
+use crate::ides::discovery;
+use crate::utils;
 use Kind::*;
 
 use anyhow::{anyhow, bail};
-use clap::ValueEnum;
+use clap::{ValueEnum, builder::PossibleValue};
 use glob::glob;
-use log::{debug, trace};
+use log::debug;
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     ffi::OsStr,
     fmt,
     fmt::{Display, Formatter},
     fs,
     path::{Path, PathBuf},
     process::Command,
+    sync::LazyLock,
 };
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Kind {
+    Arc,
     Brave,
     Chrome,
+    Chromium,
     Edge,
     Firefox,
+    #[value(name = "librewolf")]
+    LibreWolf,
     Opera,
+    Safari,
+    Vivaldi,
+    Zen,
+}
+
+/// Browser chosen on the command line, a kind or the system's default browser
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Choice {
+    Default,
+    Kind(Kind),
+}
+
+impl ValueEnum for Choice {
+    fn value_variants<'a>() -> &'a [Self] {
+        static CHOICES: LazyLock<Vec<Choice>> = LazyLock::new(|| {
+            std::iter::once(Choice::Default)
+                .chain(Kind::all().into_iter().map(Choice::Kind))
+                .collect()
+        });
+
+        &CHOICES
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        match self {
+            Choice::Default => Some(PossibleValue::new("default").help("System default browser")),
+            Choice::Kind(kind) => kind.to_possible_value(),
+        }
+    }
+}
+
+/// Release channel of a browser build
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    #[default]
+    Stable,
+    Beta,
+    Dev,
+    Canary,
+    Nightly,
+    /// Firefox Developer Edition
+    Developer,
+    /// Safari Technology Preview
+    Preview,
+}
+
+/// Where a browser installation was discovered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Source {
+    /// `/Applications` or `~/Applications`
+    Applications,
+    /// `bin` of a Homebrew or Linuxbrew prefix
+    HomebrewBin,
+    Caskroom,
+    /// Directory given in addition to the well-known locations
+    CustomDir,
+    Path,
+    Desktop,
+    Flatpak,
+    Snap,
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Source::Applications => "applications",
+            Source::HomebrewBin => "homebrew-bin",
+            Source::Caskroom => "caskroom",
+            Source::CustomDir => "custom-dir",
+            Source::Path => "path",
+            Source::Desktop => "desktop",
+            Source::Flatpak => "flatpak",
+            Source::Snap => "snap",
+        };
+
+        write!(f, "{s}")
+    }
+}
+
+/// Output format of `af browser find`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Aligned columns for reading
+    #[default]
+    Table,
+    Json,
+    /// Tab-separated values with a header line
+    Tsv,
+}
+
+/// Window the URLs open in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Window {
+    /// New tabs of an existing window
+    #[default]
+    Tab,
+    Window,
+    /// Chromium app window without tabs and toolbars, one per URL
+    App,
+}
+
+impl Display for Window {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Window::Tab => write!(f, "new tab"),
+            Window::Window => write!(f, "new window"),
+            Window::App => write!(f, "app window"),
+        }
+    }
+}
+
+/// How a browser opens URLs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Launch {
+    pub window: Window,
+    /// Private (incognito) window
+    pub private: bool,
+    /// Keep the browser from taking focus (macOS only)
+    pub background: bool,
+}
+
+/// Operating system family, deciding executable names and discovery locations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    MacOs,
+    Linux,
+}
+
+impl Platform {
+    pub fn current() -> Self {
+        match cfg!(target_os = "macos") {
+            true => Platform::MacOs,
+            false => Platform::Linux,
+        }
+    }
 }
 
 impl Display for Kind {
@@ -31,12 +187,270 @@ impl Display for Kind {
     }
 }
 
-struct BrowserPath(Kind, PathBuf);
+impl Display for Channel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+impl Channel {
+    pub fn all() -> Vec<Channel> {
+        Self::value_variants().to_vec()
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Channel::Stable => "stable",
+            Channel::Beta => "beta",
+            Channel::Dev => "dev",
+            Channel::Canary => "canary",
+            Channel::Nightly => "nightly",
+            Channel::Developer => "developer",
+            Channel::Preview => "preview",
+        }
+    }
+
+    /// Channel named after the version in `--version` output
+    /// (e.g. "Google Chrome 122.0.6261.6 dev" or "Vivaldi 6.6.3271.4 snapshot")
+    fn from_marker(marker: &str) -> Option<Channel> {
+        match marker.to_lowercase().as_str() {
+            "stable" => Some(Channel::Stable),
+            "beta" => Some(Channel::Beta),
+            "dev" | "unstable" | "snapshot" => Some(Channel::Dev),
+            "canary" => Some(Channel::Canary),
+            "nightly" => Some(Channel::Nightly),
+            _ => None,
+        }
+    }
+}
+
+/// Build of a browser in one release channel
+#[derive(Debug)]
+struct Variant {
+    kind: Kind,
+    channel: Channel,
+    /// macOS application bundle name
+    app: Option<&'static str>,
+    /// Executable inside the bundle's `Contents/MacOS`
+    app_bin: &'static str,
+    /// Executable names on Linux, which are also the names of their `.desktop` entries
+    bins: &'static [&'static str],
+}
+
+static VARIANTS: &[Variant] = &[
+    Variant {
+        kind: Arc,
+        channel: Channel::Stable,
+        app: Some("Arc.app"),
+        app_bin: "Arc",
+        bins: &[],
+    },
+    Variant {
+        kind: Brave,
+        channel: Channel::Stable,
+        app: Some("Brave Browser.app"),
+        app_bin: "Brave Browser",
+        bins: &["brave-browser", "brave", "brave-browser-stable"],
+    },
+    Variant {
+        kind: Brave,
+        channel: Channel::Beta,
+        app: Some("Brave Browser Beta.app"),
+        app_bin: "Brave Browser Beta",
+        bins: &["brave-browser-beta"],
+    },
+    Variant {
+        kind: Brave,
+        channel: Channel::Nightly,
+        app: Some("Brave Browser Nightly.app"),
+        app_bin: "Brave Browser Nightly",
+        bins: &["brave-browser-nightly"],
+    },
+    Variant {
+        kind: Chrome,
+        channel: Channel::Stable,
+        app: Some("Google Chrome.app"),
+        app_bin: "Google Chrome",
+        bins: &["google-chrome", "google-chrome-stable"],
+    },
+    Variant {
+        kind: Chrome,
+        channel: Channel::Beta,
+        app: Some("Google Chrome Beta.app"),
+        app_bin: "Google Chrome Beta",
+        bins: &["google-chrome-beta"],
+    },
+    Variant {
+        kind: Chrome,
+        channel: Channel::Dev,
+        app: Some("Google Chrome Dev.app"),
+        app_bin: "Google Chrome Dev",
+        bins: &["google-chrome-unstable"],
+    },
+    Variant {
+        kind: Chrome,
+        channel: Channel::Canary,
+        app: Some("Google Chrome Canary.app"),
+        app_bin: "Google Chrome Canary",
+        bins: &["google-chrome-canary"],
+    },
+    Variant {
+        kind: Chromium,
+        channel: Channel::Stable,
+        app: Some("Chromium.app"),
+        app_bin: "Chromium",
+        bins: &["chromium", "chromium-browser"],
+    },
+    Variant {
+        kind: Edge,
+        channel: Channel::Stable,
+        app: Some("Microsoft Edge.app"),
+        app_bin: "Microsoft Edge",
+        bins: &["microsoft-edge", "microsoft-edge-stable"],
+    },
+    Variant {
+        kind: Edge,
+        channel: Channel::Beta,
+        app: Some("Microsoft Edge Beta.app"),
+        app_bin: "Microsoft Edge Beta",
+        bins: &["microsoft-edge-beta"],
+    },
+    Variant {
+        kind: Edge,
+        channel: Channel::Dev,
+        app: Some("Microsoft Edge Dev.app"),
+        app_bin: "Microsoft Edge Dev",
+        bins: &["microsoft-edge-dev"],
+    },
+    Variant {
+        kind: Edge,
+        channel: Channel::Canary,
+        app: Some("Microsoft Edge Canary.app"),
+        app_bin: "Microsoft Edge Canary",
+        bins: &[],
+    },
+    Variant {
+        kind: Firefox,
+        channel: Channel::Stable,
+        app: Some("Firefox.app"),
+        app_bin: "firefox",
+        bins: &["firefox"],
+    },
+    Variant {
+        kind: Firefox,
+        channel: Channel::Developer,
+        app: Some("Firefox Developer Edition.app"),
+        app_bin: "firefox",
+        bins: &["firefox-developer-edition"],
+    },
+    Variant {
+        kind: Firefox,
+        channel: Channel::Nightly,
+        app: Some("Firefox Nightly.app"),
+        app_bin: "firefox",
+        bins: &["firefox-nightly"],
+    },
+    Variant {
+        kind: LibreWolf,
+        channel: Channel::Stable,
+        app: Some("LibreWolf.app"),
+        app_bin: "librewolf",
+        bins: &["librewolf"],
+    },
+    Variant {
+        kind: Opera,
+        channel: Channel::Stable,
+        app: Some("Opera.app"),
+        app_bin: "Opera",
+        bins: &["opera"],
+    },
+    Variant {
+        kind: Opera,
+        channel: Channel::Beta,
+        app: Some("Opera Beta.app"),
+        app_bin: "Opera",
+        bins: &["opera-beta"],
+    },
+    Variant {
+        kind: Opera,
+        channel: Channel::Dev,
+        app: Some("Opera Developer.app"),
+        app_bin: "Opera",
+        bins: &["opera-developer"],
+    },
+    Variant {
+        kind: Safari,
+        channel: Channel::Stable,
+        app: Some("Safari.app"),
+        app_bin: "Safari",
+        bins: &[],
+    },
+    Variant {
+        kind: Safari,
+        channel: Channel::Preview,
+        app: Some("Safari Technology Preview.app"),
+        app_bin: "Safari Technology Preview",
+        bins: &[],
+    },
+    Variant {
+        kind: Vivaldi,
+        channel: Channel::Stable,
+        app: Some("Vivaldi.app"),
+        app_bin: "Vivaldi",
+        bins: &["vivaldi", "vivaldi-stable"],
+    },
+    Variant {
+        kind: Vivaldi,
+        channel: Channel::Dev,
+        app: Some("Vivaldi Snapshot.app"),
+        app_bin: "Vivaldi Snapshot",
+        bins: &["vivaldi-snapshot"],
+    },
+    Variant {
+        kind: Zen,
+        channel: Channel::Stable,
+        app: Some("Zen.app"),
+        app_bin: "zen",
+        bins: &["zen", "zen-browser"],
+    },
+];
+
+struct BrowserPath(&'static Variant, PathBuf, Source);
+
+impl Variant {
+    /// Variants of the kinds in the channels, in table order
+    fn matching(kinds: &[Kind], channels: &[Channel]) -> Vec<&'static Variant> {
+        VARIANTS
+            .iter()
+            .filter(|v| kinds.contains(&v.kind) && channels.contains(&v.channel))
+            .collect()
+    }
+
+    /// Executable names, on macOS the one inside the `.app` bundle
+    fn bins(&self, platform: Platform) -> Vec<&'static str> {
+        match platform {
+            Platform::MacOs => self.app.map(|_| self.app_bin).into_iter().collect(),
+            Platform::Linux => self.bins.to_vec(),
+        }
+    }
+
+    /// The first variant with the executable, stable builds taking precedence
+    /// (e.g. all Firefox bundles contain `firefox`)
+    fn from_bin(platform: Platform, bin: &str) -> Option<&'static Variant> {
+        VARIANTS.iter().find(|v| v.bins(platform).contains(&bin))
+    }
+
+    fn with_path(platform: Platform, path: PathBuf, source: Source) -> Option<BrowserPath> {
+        path.file_name()
+            .and_then(OsStr::to_str)
+            .and_then(|bin| Self::from_bin(platform, bin))
+            .map(|variant| BrowserPath(variant, path.to_owned(), source))
+    }
 
-impl From<(Kind, PathBuf)> for BrowserPath {
-    fn from(value: (Kind, PathBuf)) -> Self {
-        let (kind, path) = value;
-        Self(kind, path)
+    /// Executable of the variant's bundle in a directory of applications
+    fn bundle_bin(&self, dir: &Path) -> Option<PathBuf> {
+        let app = self.app?;
+        Some(dir.join(app).join("Contents/MacOS").join(self.app_bin))
     }
 }
 
@@ -45,98 +459,200 @@ impl Kind {
         Self::value_variants().to_vec()
     }
 
-    fn with_path(path: PathBuf) -> Option<BrowserPath> {
-        path.file_name()
-            .and_then(OsStr::to_str)
-            .and_then(Self::from_bin)
-            .map(|kind| BrowserPath(kind, path.to_owned()))
+    fn flatpak(&self) -> Option<&'static str> {
+        match self {
+            Brave => Some("com.brave.Browser"),
+            Chrome => Some("com.google.Chrome"),
+            Chromium => Some("org.chromium.Chromium"),
+            Edge => Some("com.microsoft.Edge"),
+            Firefox => Some("org.mozilla.firefox"),
+            LibreWolf => Some("io.gitlab.librewolf-community"),
+            Opera => Some("com.opera.Opera"),
+            Vivaldi => Some("com.vivaldi.Vivaldi"),
+            Zen => Some("app.zen_browser.zen"),
+            Arc | Safari => None,
+        }
     }
 
-    fn bin(&self) -> &str {
+    fn snap(&self) -> Option<&'static str> {
         match self {
-            Chrome => "Google Chrome",
-            Edge => "Microsoft Edge",
-            Brave => "Brave Browser",
-            Opera => "Opera",
-            Firefox => "firefox",
+            Brave => Some("brave"),
+            Chromium => Some("chromium"),
+            Firefox => Some("firefox"),
+            Opera => Some("opera"),
+            Vivaldi => Some("vivaldi"),
+            Arc | Chrome | Edge | LibreWolf | Safari | Zen => None,
         }
     }
 
-    fn from_bin(bin: &str) -> Option<Kind> {
-        Kind::value_variants()
-            .iter()
-            .cloned()
-            .find(|&k| k.bin() == bin)
+    /// Returns true for browsers built on Firefox, which share its command line
+    pub fn is_gecko(&self) -> bool {
+        matches!(self, Firefox | LibreWolf | Zen)
     }
 
-    fn app(&self) -> &str {
+    /// Returns false for browsers without a usable command line (Safari and Arc),
+    /// which are launched and versioned through their application bundles only
+    fn has_command_line(&self) -> bool {
+        !matches!(self, Arc | Safari)
+    }
+
+    /// Arguments opening the URLs in a running instance (or starting one)
+    pub fn open_args(&self, urls: &[&str], launch: Launch) -> anyhow::Result<Vec<String>> {
+        let mut args = vec![];
+
+        if self.is_gecko() {
+            // Each URL takes its own flag, tabs of a private window can't be chosen
+            let flag = match (launch.window, launch.private) {
+                (Window::App, _) => bail!("{self} doesn't support app windows"),
+                (_, true) => FLAG_PRIVATE_WINDOW,
+                (Window::Tab, false) => FLAG_NEW_TAB,
+                (Window::Window, false) => FLAG_NEW_WINDOW,
+            };
+
+            for url in urls {
+                args.extend([flag.to_string(), url.to_string()]);
+            }
+
+            return Ok(args);
+        }
+
+        if launch.private {
+            args.push(self.private_flag().to_string());
+        }
+
+        // Chromium-based browsers open new tabs by default
+        match launch.window {
+            Window::Tab => args.extend(urls.iter().map(|url| url.to_string())),
+            Window::Window => {
+                args.push(FLAG_NEW_WINDOW.to_string());
+                args.extend(urls.iter().map(|url| url.to_string()));
+            }
+            Window::App => match urls {
+                [url] => args.push(format!("{FLAG_APP}={url}")),
+                _ => bail!("App windows open a single URL"),
+            },
+        }
+
+        Ok(args)
+    }
+
+    fn private_flag(&self) -> &'static str {
         match self {
-            Chrome => "Google Chrome.app",
-            Edge => "Microsoft Edge.app",
-            Brave => "Brave Browser.app",
-            Opera => "Opera.app",
-            Firefox => "Firefox.app",
+            Edge => FLAG_INPRIVATE,
+            Opera => FLAG_PRIVATE,
+            Firefox | LibreWolf | Zen => FLAG_PRIVATE_WINDOW,
+            Arc | Brave | Chrome | Chromium | Safari | Vivaldi => FLAG_INCOGNITO,
         }
     }
 
     pub fn name(&self) -> &str {
         match self {
+            Arc => "Arc",
+            Brave => "Brave Browser",
             Chrome => "Google Chrome",
+            Chromium => "Chromium",
             Edge => "Microsoft Edge",
-            Brave => "Brave Browser",
-            Opera => "Opera",
             Firefox => "Mozilla Firefox",
+            LibreWolf => "LibreWolf",
+            Opera => "Opera",
+            Safari => "Safari",
+            Vivaldi => "Vivaldi",
+            Zen => "Zen Browser",
         }
     }
 
     fn short_name(&self) -> &str {
         match self {
+            Arc => "Arc",
+            Brave => "Brave",
             Chrome => "Chrome",
+            Chromium => "Chromium",
             Edge => "Edge",
-            Brave => "Brave",
-            Opera => "Opera",
             Firefox => "Firefox",
+            LibreWolf => "LibreWolf",
+            Opera => "Opera",
+            Safari => "Safari",
+            Vivaldi => "Vivaldi",
+            Zen => "Zen",
         }
     }
 
-    fn applications_path(&self) -> PathBuf {
-        Path::new("/Applications")
-            .join(self.app())
-            .join("Contents/MacOS")
-            .join(self.bin())
+    /// Version and channel from `--version` output, falling back to the channel
+    /// of the executable when the output doesn't name one
+    fn parse_version(&self, output: &str, channel: Channel) -> anyhow::Result<(String, Channel)> {
+        let output = output.trim();
+
+        // Names vary between builds ("Chromium 120.0.6099.224 built on Debian 12.4",
+        // "Google Chrome 122.0.6261.6 dev", and Opera prints the bare version)
+        let mut tokens = output
+            .split_whitespace()
+            .skip_while(|token| !token.starts_with(|c: char| c.is_ascii_digit()));
+
+        let version = tokens
+            .next()
+            .ok_or(anyhow!("invalid version: {output}"))?
+            .to_string();
+
+        let channel = tokens
+            .next()
+            .and_then(Channel::from_marker)
+            .or_else(|| self.channel_from_version(&version, channel))
+            .unwrap_or(channel);
+
+        Ok((version, channel))
     }
 
-    fn parse_version(&self, version: &str) -> anyhow::Result<(String, String)> {
-        let version = version.trim();
-        let name = self.name().to_string();
+    /// Firefox pre-releases mark the version itself (`124.0a1` nightly,
+    /// `123.0b9` beta), forks like Zen use their own schemes (`1.7.6b` is stable)
+    fn channel_from_version(&self, version: &str, channel: Channel) -> Option<Channel> {
+        if !matches!(self, Firefox | LibreWolf) {
+            return None;
+        }
+
+        let (_, minor) = version.split_once('.')?;
+        let milestone = minor.find(['a', 'b'])?;
+        let (digits, rest) = minor.split_at(milestone);
+        let (marker, number) = rest.split_at(1);
 
-        if self == &Opera {
-            return Ok((name, version.to_string()));
+        if digits.is_empty()
+            || number.is_empty()
+            || !format!("{digits}{number}")
+                .bytes()
+                .all(|b| b.is_ascii_digit())
+        {
+            return None;
         }
 
-        version
-            .strip_prefix(&name)
-            .map(str::trim)
-            .map(|v| (name, v.to_string()))
-            .ok_or(anyhow!("invalid version: {version}"))
+        match marker {
+            "a" => Some(Channel::Nightly),
+            // Developer Edition is built from the beta branch
+            _ if channel != Channel::Developer => Some(Channel::Beta),
+            _ => None,
+        }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Browser installation, serialised as the stable output of `af browser find --format json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct Browser {
-    name: String,
     kind: Kind,
-    path: PathBuf,
+    name: String,
+    channel: Channel,
     version: String,
+    path: PathBuf,
+    source: Source,
+    /// Whether this is the system's default browser
+    default: bool,
 }
 
 impl Display for Browser {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "{:<name_max$} {:<version_max$} {}",
+            "{:<name_max$} {:<version_max$} {:<channel_max$} {}",
             self.name,
             self.version,
+            self.channel,
             self.path.display(),
             name_max = Kind::value_variants()
                 .iter()
@@ -145,42 +661,203 @@ impl Display for Browser {
                 .max()
                 .unwrap_or_default(),
             version_max = 13,
-        )
+            channel_max = Channel::value_variants()
+                .iter()
+                .map(Channel::name)
+                .map(str::len)
+                .max()
+                .unwrap_or_default(),
+        )?;
+
+        if self.default {
+            write!(f, " (default)")?;
+        }
+
+        Ok(())
     }
 }
 
 impl Browser {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     pub fn path(&self) -> &Path {
         &self.path
     }
 
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    pub fn channel(&self) -> Channel {
+        self.channel
+    }
+
+    /// Programs and arguments opening the URLs, a single invocation unless
+    /// every URL needs its own window (app windows)
+    ///
+    /// Browsers without a URL command line on macOS (Safari and Arc), and all
+    /// of them in the background, go through `open` with their bundle.
+    pub fn open_commands(
+        &self,
+        platform: Platform,
+        urls: &[&str],
+        launch: Launch,
+        profile_args: &[String],
+    ) -> anyhow::Result<Vec<(PathBuf, Vec<String>)>> {
+        let bundle = match platform {
+            Platform::MacOs => self.bundle(),
+            Platform::Linux => None,
+        };
+
+        if launch.background || !self.kind.has_command_line() {
+            let Some(bundle) = bundle else {
+                match launch.background {
+                    true => bail!("Opening URLs in the background is only supported on macOS"),
+                    false => bail!("{} can only open URLs on macOS", self.kind),
+                }
+            };
+
+            let tab_only = Launch {
+                background: launch.background,
+                ..Launch::default()
+            };
+
+            if launch != tab_only || !profile_args.is_empty() {
+                bail!(
+                    "{} opens URLs through `open`, which supports only new tabs without profiles",
+                    self.kind
+                );
+            }
+
+            let args = launch
+                .background
+                .then_some(FLAG_BACKGROUND)
+                .into_iter()
+                .chain([FLAG_APPLICATION])
+                .map(str::to_string)
+                .chain([bundle.display().to_string()])
+                .chain(urls.iter().map(|url| url.to_string()))
+                .collect();
+
+            return Ok(vec![(OPEN.into(), args)]);
+        }
+
+        let batches = match launch.window {
+            Window::App => urls.iter().map(std::slice::from_ref).collect(),
+            _ => vec![urls],
+        };
+
+        batches
+            .into_iter()
+            .map(|urls| {
+                let args = self.kind.open_args(urls, launch)?;
+                Ok((self.path.clone(), [profile_args, &args].concat()))
+            })
+            .collect()
+    }
+
+    /// The `.app` bundle containing the executable
+    fn bundle(&self) -> Option<&Path> {
+        self.path
+            .ancestors()
+            .nth(3)
+            .filter(|bundle| bundle.extension().is_some_and(|ext| ext == "app"))
+    }
+
     fn version(&self) -> String {
-        format!("{} {}", self.name, self.version)
+        format!("{} {} {}", self.name, self.version, self.channel)
     }
 }
 
-/// Entry point to find all known browser executables
-pub fn find_browsers(extra_dirs: &[PathBuf], kinds: &[Kind], all: bool) -> Vec<Browser> {
-    let mut all_kind_paths = vec![];
+/// Browsers rendered in the format, one line per browser for tables and TSV
+pub fn format_browsers(browsers: &[Browser], format: Format) -> anyhow::Result<String> {
+    let lines: Vec<String> = match format {
+        Format::Table => browsers.iter().map(Browser::to_string).collect(),
+        Format::Json => return Ok(serde_json::to_string_pretty(browsers)?),
+        Format::Tsv => ["kind\tname\tchannel\tversion\tpath\tsource\tdefault".to_string()]
+            .into_iter()
+            .chain(browsers.iter().map(|b| {
+                format!(
+                    "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                    b.kind.to_possible_value().unwrap().get_name(),
+                    b.name,
+                    b.channel,
+                    b.version,
+                    b.path.display(),
+                    b.source,
+                    b.default,
+                )
+            }))
+            .collect(),
+    };
+
+    Ok(lines.join("\n"))
+}
 
-    // 1. Hardcoded .app locations
-    all_kind_paths.extend(find_in_known_locations(kinds));
+/// Kind and channel of the browser an XDG desktop entry launches (e.g.
+/// `google-chrome-beta.desktop`, flatpak's `org.mozilla.firefox.desktop` or
+/// snap's `firefox_firefox.desktop`)
+pub fn from_desktop_id(id: &str) -> Option<(Kind, Channel)> {
+    let id = id.trim();
+    let id = id.strip_suffix(".desktop").unwrap_or(id);
 
-    // 2. Homebrew
-    if let Some(homebrew_prefix) = detect_homebrew_prefix() {
-        all_kind_paths.extend(find_in_homebrew(&homebrew_prefix, kinds));
+    if let Some(variant) = Variant::from_bin(Platform::Linux, id) {
+        return Some((variant.kind, variant.channel));
     }
 
-    // 3. Extra user-provided dirs
-    all_kind_paths.extend(find_in_custom_dirs(extra_dirs, kinds));
+    Kind::all()
+        .into_iter()
+        .find(|kind| {
+            kind.flatpak() == Some(id)
+                || kind
+                    .snap()
+                    .is_some_and(|snap| id == format!("{snap}_{snap}"))
+        })
+        .map(|kind| (kind, Channel::Stable))
+}
+
+/// Entry point to find all known browser executables
+pub fn find_browsers(
+    roots: &Roots,
+    extra_dirs: &[PathBuf],
+    kinds: &[Kind],
+    channels: &[Channel],
+    all: bool,
+) -> Vec<Browser> {
+    let platform = Platform::current();
+    let homebrew_prefix = detect_homebrew_prefix();
+    let variants = Variant::matching(kinds, channels);
+    let all_variant_paths = find_paths(platform, roots, homebrew_prefix, extra_dirs, &variants);
+
+    // Collecting from the parallel iterator keeps the order of preference
+    let cache = VersionCache::load();
+    let probed: Vec<_> = all_variant_paths
+        .into_par_iter()
+        .map(|BrowserPath(variant, path, source)| {
+            let browser = get_browser(variant, &path, source, &cache);
+            (path, browser)
+        })
+        .collect();
+
+    if let Err(err) = cache.save() {
+        debug!("Failed to save the browser cache: {err:#}");
+    }
 
     // Deduplicate by <binary> --version
     let mut seen_unique = BTreeMap::new();
     let mut seen_all = BTreeMap::new();
+    let default = defaults::detect(platform, roots);
+
+    for (path, browser) in probed {
+        match browser {
+            Ok(browser) if !channels.contains(&browser.channel) => {
+                debug!("Skipping {} {} build", path.display(), browser.channel);
+            }
+            Ok(mut browser) => {
+                browser.default = default == Some((browser.kind, browser.channel));
 
-    for BrowserPath(kind, path) in all_kind_paths {
-        match get_browser(&kind, &path) {
-            Ok(browser) => {
                 // insert returns None if the key was not already present
                 if seen_unique
                     .insert(browser.version(), browser.clone())
@@ -203,51 +880,122 @@ pub fn find_browsers(extra_dirs: &[PathBuf], kinds: &[Kind], all: bool) -> Vec<B
     result.values().cloned().collect()
 }
 
-pub fn find_browser(extra_dirs: &[PathBuf], kind: Kind) -> anyhow::Result<Browser> {
-    match find_browsers(extra_dirs, &[kind], false).first() {
-        Some(b) => Ok(b.clone()),
-        None => bail!("No browser found for {}", kind),
-    }
+/// Finds the browser in the channel, or the stable one if no channel is given
+/// (any other channel if only pre-releases are installed)
+pub fn find_browser(
+    roots: &Roots,
+    extra_dirs: &[PathBuf],
+    kind: Kind,
+    channel: Option<Channel>,
+) -> anyhow::Result<Browser> {
+    let channels = channel.map(|c| vec![c]).unwrap_or_else(Channel::all);
+    let browsers = find_browsers(roots, extra_dirs, &[kind], &channels, false);
+
+    browsers
+        .iter()
+        .find(|b| b.channel == Channel::Stable)
+        .or(browsers.first())
+        .cloned()
+        .ok_or_else(|| match channel {
+            Some(channel) => anyhow!("No {channel} browser found for {kind}"),
+            None => anyhow!("No browser found for {kind}"),
+        })
 }
 
-/// Helper to extract a normalized version string from `--version` output
-fn get_browser(kind: &Kind, path: &Path) -> anyhow::Result<Browser> {
-    Command::new(path)
-        .arg(FLAG_VERSION)
-        .output()
-        .map_err(|e| anyhow!(e))
-        .and_then(|output| {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let stderr = String::from_utf8_lossy(&output.stderr);
-
-            trace!(
-                "{} --version: (stdout: {}) (stderr: {})",
-                path.display(),
-                stdout.trim(),
-                stderr.trim(),
-            );
+/// Candidate executables from the platform's locations, in order of preference
+fn find_paths(
+    platform: Platform,
+    roots: &Roots,
+    homebrew_prefix: Option<PathBuf>,
+    extra_dirs: &[PathBuf],
+    variants: &[&'static Variant],
+) -> Vec<BrowserPath> {
+    let mut all_variant_paths = vec![];
 
-            if output.status.success() {
-                let (name, version) = kind.parse_version(stdout.trim())?;
-                return Ok(Browser {
-                    name,
-                    version,
-                    kind: *kind,
-                    path: path.to_path_buf(),
-                });
+    match platform {
+        Platform::MacOs => {
+            // 1. Known .app locations
+            all_variant_paths.extend(find_in_known_locations(roots, variants));
+
+            // 2. Homebrew
+            if let Some(homebrew_prefix) = homebrew_prefix {
+                all_variant_paths.extend(find_in_homebrew(platform, &homebrew_prefix, variants));
             }
 
-            bail!("non-zero exit code: {}", output.status.code().unwrap_or(-1));
-        })
+            // 3. Extra user-provided dirs
+            all_variant_paths.extend(find_in_custom_dirs(extra_dirs, variants));
+        }
+        Platform::Linux => {
+            // 1. Executables on PATH
+            all_variant_paths.extend(find_on_path(platform, roots, variants));
+
+            // 2. XDG .desktop entries
+            all_variant_paths.extend(find_in_desktop_files(platform, roots, variants));
+
+            // 3. Flatpak exports and snaps
+            all_variant_paths.extend(find_in_flatpaks(roots, variants));
+            all_variant_paths.extend(find_in_snaps(roots, variants));
+
+            // 4. Linuxbrew
+            let prefixes = homebrew_prefix
+                .into_iter()
+                .chain(linuxbrew_prefixes(roots))
+                .collect::<BTreeSet<_>>();
+
+            for prefix in prefixes {
+                all_variant_paths.extend(find_executables_in_dir(
+                    platform,
+                    &prefix.join("bin"),
+                    Source::HomebrewBin,
+                ));
+            }
+        }
+    }
+
+    all_variant_paths
+        .retain(|BrowserPath(variant, _, _)| variants.iter().any(|v| std::ptr::eq(*v, *variant)));
+    all_variant_paths
+}
+
+/// Reads the version from installation metadata, or from `--version` output
+fn get_browser(
+    variant: &Variant,
+    path: &Path,
+    source: Source,
+    cache: &VersionCache,
+) -> anyhow::Result<Browser> {
+    let output = match versions::read_metadata(path) {
+        Some(version) => version,
+        None if variant.kind.has_command_line() => cache.probe(path, PROBE_TIMEOUT)?,
+        None => bail!("no version in the application bundle"),
+    };
+
+    let (version, channel) = variant.kind.parse_version(&output, variant.channel)?;
+
+    Ok(Browser {
+        name: variant.kind.name().to_string(),
+        kind: variant.kind,
+        channel,
+        version,
+        path: path.to_path_buf(),
+        source,
+        default: false,
+    })
 }
 
-/// Look in `/Applications/...` for known browser `.app` bundles
-fn find_in_known_locations(kinds: &[Kind]) -> Vec<BrowserPath> {
-    kinds
+/// Look in `/Applications` and `~/Applications` for known browser `.app` bundles
+fn find_in_known_locations(roots: &Roots, variants: &[&'static Variant]) -> Vec<BrowserPath> {
+    variants
         .par_iter()
-        .cloned()
-        .filter(|kind| kind.applications_path().exists())
-        .map(|kind| (kind, kind.applications_path()).into())
+        .flat_map(|&variant| {
+            roots
+                .applications
+                .iter()
+                .filter_map(|dir| variant.bundle_bin(dir))
+                .filter(|path| path.exists())
+                .map(|path| BrowserPath(variant, path, Source::Applications))
+                .collect::<Vec<_>>()
+        })
         .collect()
 }
 
@@ -263,18 +1011,22 @@ fn detect_homebrew_prefix() -> Option<PathBuf> {
 }
 
 /// Look under `$(brew --prefix)/bin` and `Caskroom` for known browser executables
-fn find_in_homebrew(prefix: &Path, kinds: &[Kind]) -> Vec<BrowserPath> {
+fn find_in_homebrew(
+    platform: Platform,
+    prefix: &Path,
+    variants: &[&'static Variant],
+) -> Vec<BrowserPath> {
     let bin_dir = prefix.join("bin");
     let caskroom_dir = prefix.join("Caskroom");
 
-    let bin_matches = find_executables_in_dir(&bin_dir);
-    let cask_matches = scan_caskroom(&caskroom_dir, kinds);
+    let bin_matches = find_executables_in_dir(platform, &bin_dir, Source::HomebrewBin);
+    let cask_matches = scan_caskroom(&caskroom_dir, variants);
 
     bin_matches.into_iter().chain(cask_matches).collect()
 }
 
-/// Search Caskroom for any known browsers
-fn scan_caskroom(caskroom: &Path, kinds: &[Kind]) -> Vec<BrowserPath> {
+/// Search Caskroom for the bundles of known browsers (e.g. `google-chrome@canary/latest/Google Chrome Canary.app`)
+fn scan_caskroom(caskroom: &Path, variants: &[&'static Variant]) -> Vec<BrowserPath> {
     if !caskroom.is_dir() {
         return Vec::new();
     }
@@ -283,62 +1035,127 @@ fn scan_caskroom(caskroom: &Path, kinds: &[Kind]) -> Vec<BrowserPath> {
         .into_iter()
         .flatten()
         .flatten()
-        .filter_map(|entry| {
-            let entry_name = entry.file_name().to_string_lossy().to_lowercase();
-
-            kinds.iter().cloned().find_map(|kind| {
-                if !entry_name.contains(kind.bin()) {
-                    return None;
-                }
+        .flat_map(|entry| {
+            variants
+                .iter()
+                .filter_map(|&variant| {
+                    let pattern = variant.bundle_bin(&entry.path().join("*"))?;
 
-                let pattern = entry
-                    .path()
-                    .join("latest")
-                    .join("*.app/Contents/MacOS")
-                    .join(kind.bin());
-
-                glob(pattern.to_string_lossy().as_ref())
-                    .ok()?
-                    .flatten()
-                    .next()
-                    .map(|p| (kind, p).into())
-            })
+                    glob(pattern.to_string_lossy().as_ref())
+                        .ok()?
+                        .flatten()
+                        .next()
+                        .map(|p| BrowserPath(variant, p, Source::Caskroom))
+                })
+                .collect::<Vec<_>>()
         })
         .collect()
 }
 
 /// Search user-provided directories for `.app/Contents/MacOS/<bin>`
-fn find_in_custom_dirs(dirs: &[PathBuf], kinds: &[Kind]) -> Vec<BrowserPath> {
+fn find_in_custom_dirs(dirs: &[PathBuf], variants: &[&'static Variant]) -> Vec<BrowserPath> {
     dirs.par_iter()
         .flat_map(|base| {
-            fs::read_dir(base)
-                .into_iter()
-                .flatten()
-                .flatten()
-                .filter_map(|entry| {
-                    let path = entry.path();
-                    let is_app = path.extension().and_then(|e| e.to_str()) == Some("app");
-
-                    if !is_app {
-                        return None;
-                    }
-
-                    for kind in kinds {
-                        let exec = path.join("Contents/MacOS").join(kind.bin());
-                        if exec.exists() {
-                            return Some(BrowserPath(*kind, exec));
-                        }
-                    }
-
-                    None
+            variants
+                .iter()
+                .filter_map(|&variant| {
+                    let exec = variant.bundle_bin(base)?;
+                    exec.exists()
+                        .then_some(BrowserPath(variant, exec, Source::CustomDir))
                 })
                 .collect::<Vec<_>>()
         })
         .collect()
 }
 
+/// Look for executables named like known browsers in `PATH` directories
+fn find_on_path(
+    platform: Platform,
+    roots: &Roots,
+    variants: &[&'static Variant],
+) -> Vec<BrowserPath> {
+    variants
+        .iter()
+        .flat_map(|&variant| {
+            variant.bins(platform).into_iter().flat_map(move |bin| {
+                roots
+                    .path
+                    .iter()
+                    .map(move |dir| dir.join(bin))
+                    .filter(|path| utils::is_executable(path))
+                    .map(move |path| BrowserPath(variant, path, Source::Path))
+            })
+        })
+        .collect()
+}
+
+/// Look for XDG `.desktop` entries launching a known browser executable
+///
+/// Entries launching through wrappers (e.g. `flatpak run`) are skipped, those
+/// installations are found through their own exports.
+fn find_in_desktop_files(
+    platform: Platform,
+    roots: &Roots,
+    variants: &[&'static Variant],
+) -> Vec<BrowserPath> {
+    variants
+        .iter()
+        .flat_map(|&variant| {
+            variant.bins.iter().flat_map(move |id| {
+                roots
+                    .data_dirs
+                    .iter()
+                    .map(move |dir| dir.join("applications").join(format!("{id}.desktop")))
+                    .filter_map(|file| discovery::desktop_exec(&file))
+                    .filter_map(|exec| discovery::resolve_exec(roots, &exec))
+                    .filter_map(move |path| Variant::with_path(platform, path, Source::Desktop))
+                    .filter(move |BrowserPath(found, _, _)| found.kind == variant.kind)
+                    .map(move |BrowserPath(_, path, source)| BrowserPath(variant, path, source))
+            })
+        })
+        .collect()
+}
+
+/// Look for flatpak exports (of stable builds) in system-wide and per-user installations
+fn find_in_flatpaks(roots: &Roots, variants: &[&'static Variant]) -> Vec<BrowserPath> {
+    variants
+        .iter()
+        .filter(|variant| variant.channel == Channel::Stable)
+        .filter_map(|&variant| Some((variant, variant.kind.flatpak()?)))
+        .flat_map(|(variant, id)| {
+            roots
+                .flatpak
+                .iter()
+                .map(move |root| root.join("exports/bin").join(id))
+                .filter(|path| path.exists())
+                .map(move |path| BrowserPath(variant, path, Source::Flatpak))
+        })
+        .collect()
+}
+
+/// Look for snap launchers (of stable builds) in `/snap/bin`
+fn find_in_snaps(roots: &Roots, variants: &[&'static Variant]) -> Vec<BrowserPath> {
+    variants
+        .iter()
+        .filter(|variant| variant.channel == Channel::Stable)
+        .filter_map(|&variant| {
+            let path = roots.snap.join("bin").join(variant.kind.snap()?);
+            path.exists()
+                .then_some(BrowserPath(variant, path, Source::Snap))
+        })
+        .collect()
+}
+
+/// Default Linuxbrew prefixes (system-wide and per-user)
+fn linuxbrew_prefixes(roots: &Roots) -> Vec<PathBuf> {
+    vec![
+        "/home/linuxbrew/.linuxbrew".into(),
+        roots.home.join(".linuxbrew"),
+    ]
+}
+
 /// Find exact executable names in a flat directory
-fn find_executables_in_dir(dir: &Path) -> Vec<BrowserPath> {
+fn find_executables_in_dir(platform: Platform, dir: &Path, source: Source) -> Vec<BrowserPath> {
     if !dir.is_dir() {
         return vec![];
     }
@@ -349,6 +1166,513 @@ fn find_executables_in_dir(dir: &Path) -> Vec<BrowserPath> {
         .flatten()
         .map(|entry| entry.path())
         .filter(|path| path.is_file())
-        .filter_map(Kind::with_path)
+        .filter_map(|path| Variant::with_path(platform, path, source))
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::testing::{roots, write_executable};
+    use tempfile::TempDir;
+
+    fn variant(kind: Kind, channel: Channel) -> &'static Variant {
+        Variant::matching(&[kind], &[channel])[0]
+    }
+
+    #[test]
+    fn finds_linux_browsers_in_fake_tree() {
+        let td = TempDir::new().unwrap();
+        let roots = roots(&td);
+        let root = td.path();
+
+        write_executable(&root.join("bin/google-chrome-stable"), "#!/bin/sh\n");
+        write_executable(&root.join("bin/google-chrome-canary"), "#!/bin/sh\n");
+        write_executable(&root.join("bin/firefox-nightly"), "#!/bin/sh\n");
+        write_executable(&root.join("opt/brave/brave-browser"), "#!/bin/sh\n");
+        write_executable(&root.join("flatpak/exports/bin/org.mozilla.firefox"), "");
+        write_executable(&root.join("snap/bin/chromium"), "");
+        write_executable(&root.join("home/.linuxbrew/bin/firefox"), "");
+        write_executable(&root.join("home/.linuxbrew/bin/unrelated"), "");
+
+        fs::create_dir_all(root.join("share/applications")).unwrap();
+        fs::write(
+            root.join("share/applications/brave-browser.desktop"),
+            format!(
+                "[Desktop Entry]\nExec={} %U\n",
+                root.join("opt/brave/brave-browser").display()
+            ),
+        )
+        .unwrap();
+        // Wrapped launchers are left to the flatpak exports
+        fs::write(
+            root.join("share/applications/firefox.desktop"),
+            "[Desktop Entry]\nExec=/usr/bin/flatpak run org.mozilla.firefox %u\n",
+        )
+        .unwrap();
+
+        let find = |kinds: &[Kind], channels: &[Channel]| -> Vec<_> {
+            let variants = Variant::matching(kinds, channels);
+
+            find_paths(Platform::Linux, &roots, None, &[], &variants)
+                .into_iter()
+                .map(|BrowserPath(variant, path, source)| {
+                    (
+                        variant.kind,
+                        variant.channel,
+                        path.strip_prefix(root).unwrap().to_owned(),
+                        source,
+                    )
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            find(&Kind::all(), &Channel::all()),
+            vec![
+                (
+                    Chrome,
+                    Channel::Stable,
+                    "bin/google-chrome-stable".into(),
+                    Source::Path
+                ),
+                (
+                    Chrome,
+                    Channel::Canary,
+                    "bin/google-chrome-canary".into(),
+                    Source::Path
+                ),
+                (
+                    Firefox,
+                    Channel::Nightly,
+                    "bin/firefox-nightly".into(),
+                    Source::Path
+                ),
+                (
+                    Brave,
+                    Channel::Stable,
+                    "opt/brave/brave-browser".into(),
+                    Source::Desktop
+                ),
+                (
+                    Firefox,
+                    Channel::Stable,
+                    "flatpak/exports/bin/org.mozilla.firefox".into(),
+                    Source::Flatpak
+                ),
+                (
+                    Chromium,
+                    Channel::Stable,
+                    "snap/bin/chromium".into(),
+                    Source::Snap
+                ),
+                (
+                    Firefox,
+                    Channel::Stable,
+                    "home/.linuxbrew/bin/firefox".into(),
+                    Source::HomebrewBin
+                ),
+            ]
+        );
+
+        assert_eq!(find(&[Firefox], &Channel::all()).len(), 3);
+        assert_eq!(
+            find(&[Chrome, Firefox], &[Channel::Canary]),
+            vec![(
+                Chrome,
+                Channel::Canary,
+                "bin/google-chrome-canary".into(),
+                Source::Path
+            )]
+        );
+    }
+
+    #[test]
+    fn reads_versions_from_stub_executables() {
+        let td = TempDir::new().unwrap();
+        let chromium = td.path().join("chromium");
+        write_executable(
+            &chromium,
+            "#!/bin/sh\necho 'Chromium 120.0.6099.224 built on Debian 12.4, running on Debian 12.4'\n",
+        );
+
+        let browser = get_browser(
+            variant(Chromium, Channel::Stable),
+            &chromium,
+            Source::Path,
+            &VersionCache::default(),
+        )
+        .unwrap();
+        assert_eq!(browser.name(), "Chromium");
+        assert_eq!(browser.version, "120.0.6099.224");
+        assert_eq!(browser.channel(), Channel::Stable);
+
+        // The output wins over the channel implied by the executable's name
+        let chrome = td.path().join("google-chrome");
+        write_executable(
+            &chrome,
+            "#!/bin/sh\necho 'Google Chrome 122.0.6261.6 dev'\n",
+        );
+
+        let browser = get_browser(
+            variant(Chrome, Channel::Stable),
+            &chrome,
+            Source::Path,
+            &VersionCache::default(),
+        )
+        .unwrap();
+        assert_eq!(browser.version, "122.0.6261.6");
+        assert_eq!(browser.channel(), Channel::Dev);
+
+        write_executable(&chromium, "#!/bin/sh\nexit 1\n");
+        assert!(
+            get_browser(
+                variant(Chromium, Channel::Stable),
+                &chromium,
+                Source::Path,
+                &VersionCache::default()
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn parses_channels_from_versions() {
+        let cases = [
+            (Chrome, "Google Chrome 122.0.6200.0 canary", Channel::Stable),
+            (Chrome, "Google Chrome 121.0.6167.57 beta", Channel::Stable),
+            (Vivaldi, "Vivaldi 6.6.3271.4 snapshot", Channel::Stable),
+            (Opera, "106.0.4998.70", Channel::Beta),
+            (Firefox, "Mozilla Firefox 124.0a1", Channel::Stable),
+            (Firefox, "Mozilla Firefox 123.0b9", Channel::Stable),
+            (Firefox, "Mozilla Firefox 123.0b9", Channel::Developer),
+            (Zen, "Zen Browser 1.7.6b", Channel::Stable),
+            (Firefox, "Mozilla Firefox 115.9.1esr", Channel::Stable),
+            (LibreWolf, "Mozilla LibreWolf 133.0-1", Channel::Stable),
+        ];
+
+        let parsed: Vec<_> = cases
+            .iter()
+            .map(|(kind, output, channel)| kind.parse_version(output, *channel).unwrap())
+            .map(|(version, channel)| format!("{version} {channel}"))
+            .collect();
+
+        assert_eq!(
+            parsed,
+            vec![
+                "122.0.6200.0 canary",
+                "121.0.6167.57 beta",
+                "6.6.3271.4 dev",
+                "106.0.4998.70 beta",
+                "124.0a1 nightly",
+                "123.0b9 beta",
+                "123.0b9 developer",
+                "1.7.6b stable",
+                "115.9.1esr stable",
+                "133.0-1 stable",
+            ]
+        );
+
+        assert!(
+            Chrome
+                .parse_version("Google Chrome", Channel::Stable)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn reads_versions_from_app_bundles() {
+        let td = TempDir::new().unwrap();
+        let contents = td.path().join("Safari Technology Preview.app/Contents");
+        let exec = contents.join("MacOS/Safari Technology Preview");
+        write_executable(&exec, "#!/bin/sh\nexit 1\n");
+        fs::write(
+            contents.join("Info.plist"),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+    <key>CFBundleShortVersionString</key>
+    <string>18.4</string>
+</dict>
+</plist>
+"#,
+        )
+        .unwrap();
+
+        let browser = get_browser(
+            variant(Safari, Channel::Preview),
+            &exec,
+            Source::Path,
+            &VersionCache::default(),
+        )
+        .unwrap();
+        assert_eq!(browser.version, "18.4");
+        assert_eq!(browser.channel(), Channel::Preview);
+
+        let commands = browser
+            .open_commands(
+                Platform::MacOs,
+                &["https://example.com"],
+                Launch::default(),
+                &[],
+            )
+            .unwrap();
+        assert_eq!(
+            commands,
+            vec![(
+                PathBuf::from(OPEN),
+                vec![
+                    FLAG_APPLICATION.to_string(),
+                    td.path()
+                        .join("Safari Technology Preview.app")
+                        .display()
+                        .to_string(),
+                    "https://example.com".to_string(),
+                ]
+            )]
+        );
+
+        let private = Launch {
+            private: true,
+            ..Launch::default()
+        };
+        assert!(
+            browser
+                .open_commands(Platform::MacOs, &["https://example.com"], private, &[])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn maps_executables_per_platform() {
+        let kind_and_channel = |platform, bin| {
+            Variant::from_bin(platform, bin).map(|variant| (variant.kind, variant.channel))
+        };
+
+        assert_eq!(
+            kind_and_channel(Platform::Linux, "brave"),
+            Some((Brave, Channel::Stable))
+        );
+        assert_eq!(kind_and_channel(Platform::Linux, "Brave Browser"), None);
+        assert_eq!(
+            kind_and_channel(Platform::Linux, "google-chrome-unstable"),
+            Some((Chrome, Channel::Dev))
+        );
+        assert_eq!(
+            kind_and_channel(Platform::MacOs, "Google Chrome Canary"),
+            Some((Chrome, Channel::Canary))
+        );
+        assert_eq!(
+            kind_and_channel(Platform::MacOs, "firefox"),
+            Some((Firefox, Channel::Stable))
+        );
+        assert_eq!(kind_and_channel(Platform::MacOs, "google-chrome"), None);
+
+        for kind in Kind::all() {
+            assert!(
+                !Variant::matching(&[kind], &[Channel::Stable]).is_empty(),
+                "{kind} has no stable variant"
+            );
+        }
+    }
+
+    #[test]
+    fn builds_argv_per_kind_and_mode() {
+        let urls = ["https://example.com", "https://example.org"];
+        let argv = |kind: Kind, window, private| {
+            let launch = Launch {
+                window,
+                private,
+                background: false,
+            };
+
+            kind.open_args(&urls, launch)
+                .map(|args| args.join(" "))
+                .unwrap_or_else(|err| format!("error: {err}"))
+        };
+
+        let cases = [
+            (Firefox, Window::Tab, false),
+            (Firefox, Window::Window, false),
+            (Zen, Window::Tab, true),
+            (LibreWolf, Window::App, false),
+            (Chrome, Window::Tab, false),
+            (Chrome, Window::Window, true),
+            (Chrome, Window::App, false),
+            (Brave, Window::Tab, true),
+            (Edge, Window::Window, true),
+            (Opera, Window::Tab, true),
+            (Vivaldi, Window::Window, false),
+        ];
+
+        let produced: Vec<_> = cases
+            .into_iter()
+            .map(|(kind, window, private)| format!("{kind}: {}", argv(kind, window, private)))
+            .collect();
+
+        assert_eq!(
+            produced,
+            vec![
+                "Firefox: --new-tab https://example.com --new-tab https://example.org",
+                "Firefox: --new-window https://example.com --new-window https://example.org",
+                "Zen: --private-window https://example.com --private-window https://example.org",
+                "LibreWolf: error: LibreWolf doesn't support app windows",
+                "Chrome: https://example.com https://example.org",
+                "Chrome: --incognito --new-window https://example.com https://example.org",
+                "Chrome: error: App windows open a single URL",
+                "Brave: --incognito https://example.com https://example.org",
+                "Edge: --inprivate --new-window https://example.com https://example.org",
+                "Opera: --private https://example.com https://example.org",
+                "Vivaldi: --new-window https://example.com https://example.org",
+            ]
+        );
+    }
+
+    #[test]
+    fn builds_commands_per_platform() {
+        let browser = |kind, path: &str| Browser {
+            kind,
+            name: Kind::name(&kind).to_string(),
+            channel: Channel::Stable,
+            version: "1.0".into(),
+            path: path.into(),
+            source: Source::Applications,
+            default: false,
+        };
+
+        let chrome = browser(
+            Chrome,
+            "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+        );
+        let urls = ["https://example.com", "https://example.org"];
+        let profile = ["--profile-directory=Profile 1".to_string()];
+
+        let app = Launch {
+            window: Window::App,
+            ..Launch::default()
+        };
+        let commands = chrome
+            .open_commands(Platform::MacOs, &urls, app, &profile)
+            .unwrap();
+        assert_eq!(
+            commands,
+            vec![
+                (
+                    chrome.path.clone(),
+                    vec![
+                        "--profile-directory=Profile 1".to_string(),
+                        "--app=https://example.com".to_string(),
+                    ]
+                ),
+                (
+                    chrome.path.clone(),
+                    vec![
+                        "--profile-directory=Profile 1".to_string(),
+                        "--app=https://example.org".to_string(),
+                    ]
+                ),
+            ]
+        );
+
+        let background = Launch {
+            background: true,
+            ..Launch::default()
+        };
+        assert_eq!(
+            chrome
+                .open_commands(Platform::MacOs, &urls[..1], background, &[])
+                .unwrap(),
+            vec![(
+                PathBuf::from(OPEN),
+                vec![
+                    FLAG_BACKGROUND.to_string(),
+                    FLAG_APPLICATION.to_string(),
+                    "/Applications/Google Chrome.app".to_string(),
+                    "https://example.com".to_string(),
+                ]
+            )]
+        );
+        assert!(
+            chrome
+                .open_commands(Platform::MacOs, &urls, background, &profile)
+                .is_err()
+        );
+
+        let firefox = browser(Firefox, "/usr/bin/firefox");
+        assert!(
+            firefox
+                .open_commands(Platform::Linux, &urls, background, &[])
+                .is_err()
+        );
+        assert_eq!(
+            firefox
+                .open_commands(Platform::Linux, &urls[..1], Launch::default(), &[])
+                .unwrap(),
+            vec![(
+                PathBuf::from("/usr/bin/firefox"),
+                vec![FLAG_NEW_TAB.to_string(), "https://example.com".to_string()]
+            )]
+        );
+    }
+
+    #[test]
+    fn formats_browsers_for_find() {
+        let browsers = [
+            Browser {
+                kind: Firefox,
+                name: "Firefox".into(),
+                channel: Channel::Nightly,
+                version: "124.0a1".into(),
+                path: "/Applications/Firefox Nightly.app/Contents/MacOS/firefox".into(),
+                source: Source::Applications,
+                default: true,
+            },
+            Browser {
+                kind: LibreWolf,
+                name: "LibreWolf".into(),
+                channel: Channel::Stable,
+                version: "133.0-1".into(),
+                path: "/opt/homebrew/bin/librewolf".into(),
+                source: Source::HomebrewBin,
+                default: false,
+            },
+        ];
+
+        assert_eq!(
+            format_browsers(&browsers, Format::Json).unwrap(),
+            r#"[
+  {
+    "kind": "firefox",
+    "name": "Firefox",
+    "channel": "nightly",
+    "version": "124.0a1",
+    "path": "/Applications/Firefox Nightly.app/Contents/MacOS/firefox",
+    "source": "applications",
+    "default": true
+  },
+  {
+    "kind": "librewolf",
+    "name": "LibreWolf",
+    "channel": "stable",
+    "version": "133.0-1",
+    "path": "/opt/homebrew/bin/librewolf",
+    "source": "homebrew-bin",
+    "default": false
+  }
+]"#
+        );
+
+        assert_eq!(
+            format_browsers(&browsers, Format::Tsv).unwrap(),
+            "kind\tname\tchannel\tversion\tpath\tsource\tdefault\n\
+             firefox\tFirefox\tnightly\t124.0a1\t/Applications/Firefox Nightly.app/Contents/MacOS/firefox\tapplications\ttrue\n\
+             librewolf\tLibreWolf\tstable\t133.0-1\t/opt/homebrew/bin/librewolf\thomebrew-bin\tfalse"
+        );
+
+        assert_eq!(
+            format_browsers(&browsers[1..], Format::Table).unwrap(),
+            browsers[1].to_string()
+        );
+        assert_eq!(format_browsers(&[], Format::Json).unwrap(), "[]");
+    }
+}
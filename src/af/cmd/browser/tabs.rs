@@ -0,0 +1,667 @@
+use crate::cmd::browser::open::Open;
+use crate::cmd::browser::profiles::{self, Profile};
+use crate::cmd::browser::structures::{Choice, Kind, Platform};
+use crate::consts::{CHROMIUM_SESSIONS, FIREFOX_SESSION, FIREFOX_SESSION_RECOVERY};
+use crate::discovery::Roots;
+
+use anyhow::{Context, Result, anyhow, bail};
+use clap::{Args, ValueEnum};
+use console::style;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Header of Firefox's LZ4-compressed JSON files
+const MOZLZ4_MAGIC: &[u8] = b"mozLz40\0";
+
+/// Header of Chromium's session command files
+const SNSS_MAGIC: &[u8] = b"SNSS";
+
+// Chromium session commands read to rebuild the open tabs
+const SET_TAB_WINDOW: u8 = 0;
+const SET_TAB_INDEX_IN_WINDOW: u8 = 2;
+const NAVIGATION_PATH_PRUNED_FROM_BACK: u8 = 5;
+const UPDATE_TAB_NAVIGATION: u8 = 6;
+const SET_SELECTED_NAVIGATION_INDEX: u8 = 7;
+const NAVIGATION_PATH_PRUNED_FROM_FRONT: u8 = 11;
+const TAB_CLOSED: u8 = 16;
+const WINDOW_CLOSED: u8 = 17;
+
+#[derive(Debug, Args)]
+pub struct Tabs {
+    /// List tabs only of the browser kinds (comma-separated)
+    #[arg(
+        long = "kind",
+        short,
+        value_enum,
+        value_name = "BROWSER",
+        value_delimiter = ',',
+        default_values_t = Kind::all(),
+    )]
+    kinds: Vec<Kind>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = Format::Markdown)]
+    format: Format,
+
+    /// Reopen the tabs saved in the file (JSON or markdown output of this command),
+    /// each window in a new window
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["kinds", "format"])]
+    restore: Option<PathBuf>,
+
+    /// Browser to restore the tabs in, instead of the one chosen by rules
+    #[arg(long, short, value_enum, value_name = "BROWSER", requires = "restore")]
+    browser: Option<Choice>,
+
+    /// Profile to restore the tabs in, by directory, name or account email
+    #[arg(long, short = 'P', requires = "restore")]
+    profile: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Lists of links, a heading per profile and window
+    Markdown,
+    Json,
+}
+
+/// Open tabs of a browser profile
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    pub browser: Kind,
+    pub profile: String,
+    /// Tabs of each window, in order
+    pub windows: Vec<Vec<Tab>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tab {
+    pub title: String,
+    pub url: String,
+}
+
+impl Tabs {
+    pub fn run(&self, roots: &Roots, dirs: &[PathBuf]) -> Result<()> {
+        if let Some(file) = &self.restore {
+            return self.restore(file, roots, dirs);
+        }
+
+        let sessions: Vec<_> =
+            profiles::find_profiles(Platform::current(), &roots.home, &self.kinds)
+                .iter()
+                .filter_map(read_session)
+                .filter(|session| !session.windows.is_empty())
+                .collect();
+
+        match self.format {
+            Format::Json => println!("{}", serde_json::to_string_pretty(&sessions)?),
+            Format::Markdown => print!("{}", to_markdown(&sessions)),
+        }
+
+        Ok(())
+    }
+
+    fn restore(&self, file: &Path, roots: &Roots, dirs: &[PathBuf]) -> Result<()> {
+        let content = fs::read_to_string(file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let sessions = parse_sessions(&content)?;
+
+        for window in sessions.into_iter().flat_map(|session| session.windows) {
+            let urls: Vec<String> = window
+                .into_iter()
+                .map(|tab| tab.url)
+                .filter(|url| !url.is_empty())
+                .collect();
+
+            // Opening no URLs would fall back to the clipboard
+            if urls.is_empty() {
+                continue;
+            }
+
+            Open::window(urls, self.browser, self.profile.clone()).run(roots, dirs)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Open tabs of the profile, reporting unreadable session files on stderr
+fn read_session(profile: &Profile) -> Option<Session> {
+    let windows = match profile.kind.is_gecko() {
+        true => [FIREFOX_SESSION_RECOVERY, FIREFOX_SESSION]
+            .into_iter()
+            .map(|file| profile.path.join(file))
+            .find(|path| path.exists())
+            .map(|path| (firefox_windows(&path), path)),
+        false => latest_chromium_session(&profile.path.join(CHROMIUM_SESSIONS))
+            .map(|path| (chromium_windows(&path), path)),
+    };
+
+    match windows {
+        Some((Ok(windows), _)) => Some(Session {
+            browser: profile.kind,
+            profile: profile.name.clone(),
+            windows,
+        }),
+        Some((Err(err), path)) => {
+            eprintln!("{:>8} {} ({err:#})", style("skip").yellow(), path.display());
+            None
+        }
+        None => {
+            debug!("No session in {}", profile.path.display());
+            None
+        }
+    }
+}
+
+/// Sessions as markdown, a heading per profile and window
+pub fn to_markdown(sessions: &[Session]) -> String {
+    let mut out = String::new();
+
+    for session in sessions {
+        let _ = writeln!(out, "## {}: {}\n", session.browser, session.profile);
+
+        for (index, window) in session.windows.iter().enumerate() {
+            let _ = writeln!(out, "### Window {}\n", index + 1);
+
+            for tab in window {
+                let _ = writeln!(
+                    out,
+                    "- [{}]({})",
+                    tab.title.replace('[', "\\[").replace(']', "\\]"),
+                    tab.url.replace('(', "%28").replace(')', "%29"),
+                );
+            }
+
+            out.push('\n');
+        }
+    }
+
+    out
+}
+
+/// Sessions saved as JSON or markdown
+pub fn parse_sessions(content: &str) -> Result<Vec<Session>> {
+    if content.trim_start().starts_with('[') {
+        return Ok(serde_json::from_str(content)?);
+    }
+
+    let mut sessions: Vec<Session> = vec![];
+
+    for line in content.lines().map(str::trim) {
+        if let Some(heading) = line.strip_prefix("## ") {
+            let (browser, profile) = heading
+                .split_once(": ")
+                .ok_or_else(|| anyhow!("Invalid profile heading: {line}"))?;
+
+            sessions.push(Session {
+                browser: Kind::from_str(browser, true).map_err(|err| anyhow!(err))?,
+                profile: profile.to_string(),
+                windows: vec![],
+            });
+        } else if line.starts_with("### ") {
+            let Some(session) = sessions.last_mut() else {
+                bail!("Window outside of a profile: {line}");
+            };
+
+            session.windows.push(vec![]);
+        } else if let Some(link) = line.strip_prefix("- [") {
+            let Some(window) = sessions.last_mut().and_then(|s| s.windows.last_mut()) else {
+                bail!("Tab outside of a window: {line}");
+            };
+
+            let (title, url) = link
+                .strip_suffix(')')
+                .and_then(|link| link.rsplit_once("]("))
+                .ok_or_else(|| anyhow!("Invalid link: {line}"))?;
+
+            window.push(Tab {
+                title: title.replace("\\[", "[").replace("\\]", "]"),
+                url: url.to_string(),
+            });
+        }
+    }
+
+    Ok(sessions)
+}
+
+/// Decompresses Firefox's `mozlz4` files (a header, the decompressed size and an LZ4 block)
+pub fn decode_mozlz4(data: &[u8]) -> Result<Vec<u8>> {
+    let rest = data
+        .strip_prefix(MOZLZ4_MAGIC)
+        .ok_or_else(|| anyhow!("Not a mozlz4 file"))?;
+
+    if rest.len() < 4 {
+        bail!("Truncated mozlz4 file");
+    }
+
+    let (size, block) = rest.split_at(4);
+    let size = u32::from_le_bytes(size.try_into()?) as usize;
+
+    Ok(lz4_flex::block::decompress(block, size)?)
+}
+
+#[derive(Debug, Deserialize)]
+struct FirefoxSession {
+    #[serde(default)]
+    windows: Vec<FirefoxWindow>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FirefoxWindow {
+    #[serde(default)]
+    tabs: Vec<FirefoxTab>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FirefoxTab {
+    #[serde(default)]
+    entries: Vec<FirefoxEntry>,
+    /// 1-based index of the current entry in the tab's history
+    #[serde(default)]
+    index: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct FirefoxEntry {
+    url: String,
+    #[serde(default)]
+    title: String,
+}
+
+/// Windows from Firefox's `sessionstore.jsonlz4` or `recovery.jsonlz4`
+pub fn firefox_windows(path: &Path) -> Result<Vec<Vec<Tab>>> {
+    let session: FirefoxSession = serde_json::from_slice(&decode_mozlz4(&fs::read(path)?)?)?;
+
+    Ok(session
+        .windows
+        .into_iter()
+        .map(|window| {
+            window
+                .tabs
+                .into_iter()
+                .filter_map(|tab| {
+                    let index = tab.index.clamp(1, tab.entries.len().max(1)) - 1;
+                    let entry = tab.entries.into_iter().nth(index)?;
+
+                    Some(Tab {
+                        title: entry.title,
+                        url: entry.url,
+                    })
+                })
+                .collect::<Vec<_>>()
+        })
+        .filter(|tabs| !tabs.is_empty())
+        .collect())
+}
+
+/// Newest `Session_<time>` file of a Chromium profile, or the `Current Session`
+/// file of older versions
+fn latest_chromium_session(dir: &Path) -> Option<PathBuf> {
+    let newest = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("Session_"))
+        })
+        .max();
+
+    newest.or_else(|| {
+        let current = dir.parent()?.join("Current Session");
+        current.exists().then_some(current)
+    })
+}
+
+/// Chromium tab rebuilt from session commands
+#[derive(Debug, Default)]
+struct ChromiumTab {
+    window: i32,
+    index: i32,
+    navigations: BTreeMap<i32, Tab>,
+    selected: Option<i32>,
+}
+
+/// Reads the little-endian integers and pickled strings of session commands
+struct Payload<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Payload<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.offset..self.offset + len)
+            .ok_or_else(|| anyhow!("Truncated session command"))?;
+        // Pickled values are aligned to 4 bytes
+        self.offset += len.next_multiple_of(4);
+
+        Ok(bytes)
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_le_bytes(self.bytes(4)?.try_into()?))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.i32()?.max(0) as usize;
+        Ok(String::from_utf8_lossy(self.bytes(len)?).into_owned())
+    }
+
+    fn string16(&mut self) -> Result<String> {
+        let len = self.i32()?.max(0) as usize;
+        let units: Vec<u16> = self
+            .bytes(len * 2)?
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+            .collect();
+
+        Ok(String::from_utf16_lossy(&units))
+    }
+}
+
+/// Windows from the commands of a Chromium `Session_<time>` (SNSS) file
+pub fn chromium_windows(path: &Path) -> Result<Vec<Vec<Tab>>> {
+    parse_snss(&fs::read(path)?)
+}
+
+fn parse_snss(data: &[u8]) -> Result<Vec<Vec<Tab>>> {
+    let rest = data
+        .strip_prefix(SNSS_MAGIC)
+        .ok_or_else(|| anyhow!("Not a session file"))?;
+
+    let version = rest
+        .get(..4)
+        .map(|version| i32::from_le_bytes(version.try_into().unwrap()))
+        .ok_or_else(|| anyhow!("Truncated session file"))?;
+
+    // Versions 2 and 4 are encrypted
+    if version != 1 && version != 3 {
+        bail!("Unsupported session file version {version}");
+    }
+
+    let mut tabs: BTreeMap<i32, ChromiumTab> = BTreeMap::new();
+    let mut closed_windows = vec![];
+    let mut commands = &rest[4..];
+
+    while commands.len() >= 2 {
+        let size = u16::from_le_bytes([commands[0], commands[1]]) as usize;
+        let Some(command) = commands.get(2..2 + size).filter(|c| !c.is_empty()) else {
+            // The last command may be cut short while the browser writes it
+            break;
+        };
+        commands = &commands[2 + size..];
+
+        let mut payload = Payload::new(&command[1..]);
+
+        match command[0] {
+            SET_TAB_WINDOW => {
+                let window = payload.i32()?;
+                tabs.entry(payload.i32()?).or_default().window = window;
+            }
+            SET_TAB_INDEX_IN_WINDOW => {
+                let tab = payload.i32()?;
+                tabs.entry(tab).or_default().index = payload.i32()?;
+            }
+            SET_SELECTED_NAVIGATION_INDEX => {
+                let tab = payload.i32()?;
+                tabs.entry(tab).or_default().selected = Some(payload.i32()?);
+            }
+            UPDATE_TAB_NAVIGATION => {
+                // Pickle with the payload size first
+                payload.i32()?;
+                let tab = payload.i32()?;
+                let index = payload.i32()?;
+                let url = payload.string()?;
+                let title = payload.string16()?;
+
+                tabs.entry(tab)
+                    .or_default()
+                    .navigations
+                    .insert(index, Tab { title, url });
+            }
+            NAVIGATION_PATH_PRUNED_FROM_BACK => {
+                let tab = payload.i32()?;
+                let index = payload.i32()?;
+                tabs.entry(tab)
+                    .or_default()
+                    .navigations
+                    .retain(|i, _| *i < index);
+            }
+            NAVIGATION_PATH_PRUNED_FROM_FRONT => {
+                let tab = tabs.entry(payload.i32()?).or_default();
+                let count = payload.i32()?;
+
+                tab.navigations = std::mem::take(&mut tab.navigations)
+                    .into_iter()
+                    .filter(|(i, _)| *i >= count)
+                    .map(|(i, nav)| (i - count, nav))
+                    .collect();
+                tab.selected = tab.selected.map(|selected| selected - count);
+            }
+            TAB_CLOSED => {
+                tabs.remove(&payload.i32()?);
+            }
+            WINDOW_CLOSED => closed_windows.push(payload.i32()?),
+            _ => {}
+        }
+    }
+
+    let mut windows: BTreeMap<i32, Vec<(i32, Tab)>> = BTreeMap::new();
+
+    for tab in tabs.into_values() {
+        if closed_windows.contains(&tab.window) {
+            continue;
+        }
+
+        let current = tab
+            .selected
+            .and_then(|selected| tab.navigations.get(&selected))
+            .or_else(|| tab.navigations.values().last());
+
+        if let Some(current) = current {
+            windows
+                .entry(tab.window)
+                .or_default()
+                .push((tab.index, current.clone()));
+        }
+    }
+
+    Ok(windows
+        .into_values()
+        .map(|mut tabs| {
+            tabs.sort_by_key(|(index, _)| *index);
+            tabs.into_iter().map(|(_, tab)| tab).collect()
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn tab(title: &str, url: &str) -> Tab {
+        Tab {
+            title: title.into(),
+            url: url.into(),
+        }
+    }
+
+    /// Session command with a payload of little-endian integers
+    fn command(id: u8, ints: &[i32]) -> Vec<u8> {
+        let payload: Vec<u8> = ints.iter().flat_map(|i| i.to_le_bytes()).collect();
+        framed(id, &payload)
+    }
+
+    fn framed(id: u8, payload: &[u8]) -> Vec<u8> {
+        let mut command = ((payload.len() + 1) as u16).to_le_bytes().to_vec();
+        command.push(id);
+        command.extend(payload);
+        command
+    }
+
+    fn navigation(tab: i32, index: i32, url: &str, title: &str) -> Vec<u8> {
+        let pad = |bytes: &mut Vec<u8>| bytes.resize(bytes.len().next_multiple_of(4), 0);
+
+        let mut pickle = vec![];
+        pickle.extend(tab.to_le_bytes());
+        pickle.extend(index.to_le_bytes());
+        pickle.extend((url.len() as i32).to_le_bytes());
+        pickle.extend(url.as_bytes());
+        pad(&mut pickle);
+
+        let title: Vec<u16> = title.encode_utf16().collect();
+        pickle.extend((title.len() as i32).to_le_bytes());
+        pickle.extend(title.iter().flat_map(|unit| unit.to_le_bytes()));
+        pad(&mut pickle);
+        // Fields after the title aren't read
+        pickle.extend(0i32.to_le_bytes());
+
+        let mut payload = (pickle.len() as u32).to_le_bytes().to_vec();
+        payload.extend(pickle);
+        framed(UPDATE_TAB_NAVIGATION, &payload)
+    }
+
+    #[test]
+    fn reads_firefox_sessions() {
+        let td = TempDir::new().unwrap();
+        let path = td.path().join("recovery.jsonlz4");
+        let json = r#"{
+            "windows": [
+                { "tabs": [
+                    { "entries": [
+                        { "url": "https://github.com/acme/af", "title": "acme/af" },
+                        { "url": "https://github.com/acme/af/issues/7", "title": "Crash on start" }
+                    ], "index": 2 },
+                    { "entries": [{ "url": "about:blank" }], "index": 1 }
+                ] },
+                { "tabs": [] }
+            ],
+            "_closedWindows": []
+        }"#;
+
+        let mut data = MOZLZ4_MAGIC.to_vec();
+        data.extend((json.len() as u32).to_le_bytes());
+        data.extend(lz4_flex::block::compress(json.as_bytes()));
+        fs::write(&path, data).unwrap();
+
+        assert_eq!(
+            firefox_windows(&path).unwrap(),
+            vec![vec![
+                tab("Crash on start", "https://github.com/acme/af/issues/7"),
+                tab("", "about:blank"),
+            ]]
+        );
+
+        fs::write(&path, json).unwrap();
+        assert!(firefox_windows(&path).is_err());
+    }
+
+    #[test]
+    fn rebuilds_chromium_tabs_from_commands() {
+        let mut data = SNSS_MAGIC.to_vec();
+        data.extend(3i32.to_le_bytes());
+
+        for command in [
+            command(SET_TAB_WINDOW, &[1, 10]),
+            command(SET_TAB_INDEX_IN_WINDOW, &[10, 1]),
+            navigation(10, 0, "https://example.com/", "Example"),
+            navigation(10, 1, "https://github.com/acme/af", "acme/af"),
+            command(SET_SELECTED_NAVIGATION_INDEX, &[10, 0]),
+            command(SET_TAB_WINDOW, &[1, 11]),
+            command(SET_TAB_INDEX_IN_WINDOW, &[11, 0]),
+            navigation(11, 0, "https://ci.example.com/", "CI — build #7"),
+            command(SET_TAB_WINDOW, &[1, 12]),
+            navigation(12, 0, "https://closed.example.com/", "Closed"),
+            command(TAB_CLOSED, &[12, 0, 0]),
+            command(SET_TAB_WINDOW, &[2, 13]),
+            navigation(13, 0, "https://docs.example.com/", "Docs"),
+            command(SET_TAB_WINDOW, &[3, 14]),
+            navigation(14, 0, "https://gone.example.com/", "Gone"),
+            command(WINDOW_CLOSED, &[3, 0, 0]),
+            command(9, &[1, 0]),
+        ] {
+            data.extend(command);
+        }
+
+        // A command cut short by a browser still writing
+        data.extend([40, 0, UPDATE_TAB_NAVIGATION]);
+
+        assert_eq!(
+            parse_snss(&data).unwrap(),
+            vec![
+                vec![
+                    tab("CI — build #7", "https://ci.example.com/"),
+                    tab("Example", "https://example.com/"),
+                ],
+                vec![tab("Docs", "https://docs.example.com/")],
+            ]
+        );
+
+        assert!(parse_snss(b"SNSS\x02\x00\x00\x00").is_err());
+    }
+
+    #[test]
+    fn round_trips_markdown() {
+        let sessions = vec![
+            Session {
+                browser: Kind::Firefox,
+                profile: "default-release".into(),
+                windows: vec![
+                    vec![
+                        tab("[WIP] Crash on start", "https://github.com/acme/af/pull/7"),
+                        tab("", "https://example.com/"),
+                    ],
+                    vec![tab(
+                        "Rust (language)",
+                        "https://en.wikipedia.org/wiki/Rust_(language)",
+                    )],
+                ],
+            },
+            Session {
+                browser: Kind::LibreWolf,
+                profile: "Work: Acme".into(),
+                windows: vec![vec![tab("CI", "https://ci.example.com/")]],
+            },
+        ];
+
+        let markdown = to_markdown(&sessions);
+        assert_eq!(
+            markdown,
+            "## Firefox: default-release\n\n\
+             ### Window 1\n\n\
+             - [\\[WIP\\] Crash on start](https://github.com/acme/af/pull/7)\n\
+             - [](https://example.com/)\n\n\
+             ### Window 2\n\n\
+             - [Rust (language)](https://en.wikipedia.org/wiki/Rust_%28language%29)\n\n\
+             ## LibreWolf: Work: Acme\n\n\
+             ### Window 1\n\n\
+             - [CI](https://ci.example.com/)\n\n"
+        );
+
+        let parsed = parse_sessions(&markdown).unwrap();
+        assert_eq!(parsed[1], sessions[1]);
+        assert_eq!(parsed[0].windows[0], sessions[0].windows[0]);
+        assert_eq!(
+            parsed[0].windows[1][0].url,
+            "https://en.wikipedia.org/wiki/Rust_%28language%29"
+        );
+
+        let json = serde_json::to_string(&sessions).unwrap();
+        assert_eq!(parse_sessions(&json).unwrap(), sessions);
+    }
+}
@@ -0,0 +1,237 @@
+use crate::consts::BROWSER_CACHE;
+use crate::discovery;
+use crate::utils;
+
+use anyhow::{Context, Result};
+use log::{debug, trace};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, SystemTime},
+};
+
+/// `--version` output of browser executables, remembered until they change
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct VersionCache {
+    #[serde(default)]
+    entries: Mutex<BTreeMap<PathBuf, Entry>>,
+
+    #[serde(skip)]
+    path: Option<PathBuf>,
+
+    #[serde(skip)]
+    changed: AtomicBool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    /// Modification time of the executable when it was probed
+    modified: SystemTime,
+    output: String,
+}
+
+impl VersionCache {
+    /// Loads the cache from `$XDG_CACHE_HOME/af/browsers.json`
+    pub fn load() -> Self {
+        Self::load_from(utils::cache_dir().join(BROWSER_CACHE))
+    }
+
+    /// Loads the cache from the given file, starting empty if it can't be read
+    pub fn load_from(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+
+        let cache = fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| Ok(serde_json::from_str::<Self>(&content)?))
+            .unwrap_or_else(|err| {
+                debug!("Browser cache {} not loaded: {err}", path.display());
+                Self::default()
+            });
+
+        Self {
+            path: Some(path),
+            ..cache
+        }
+    }
+
+    /// Writes the cache if anything was probed since it was loaded
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        if !self.changed.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// `--version` output of the executable, probing it only if it changed since the last probe
+    pub fn probe(&self, path: &Path, timeout: Duration) -> Result<String> {
+        let modified = fs::metadata(path)?.modified()?;
+
+        if let Some(entry) = self.entries.lock().unwrap().get(path)
+            && entry.modified == modified
+        {
+            trace!("Using cached version of {}", path.display());
+            return Ok(entry.output.clone());
+        }
+
+        let output = discovery::probe(path, timeout)?;
+
+        self.entries.lock().unwrap().insert(
+            path.to_path_buf(),
+            Entry {
+                modified,
+                output: output.clone(),
+            },
+        );
+        self.changed.store(true, Ordering::Relaxed);
+
+        Ok(output)
+    }
+}
+
+/// Version from installation metadata, which doesn't need running the browser
+pub fn read_metadata(path: &Path) -> Option<String> {
+    bundle_version(path)
+        .or_else(|| application_ini_version(path))
+        .or_else(|| snap_version(path))
+}
+
+/// `CFBundleShortVersionString` from the `Info.plist` of the bundle containing the executable
+fn bundle_version(path: &Path) -> Option<String> {
+    let contents = path
+        .parent()
+        .filter(|dir| dir.ends_with("Contents/MacOS"))?
+        .parent()?;
+    let info = plist::Value::from_file(contents.join("Info.plist")).ok()?;
+
+    info.as_dictionary()?
+        .get("CFBundleShortVersionString")?
+        .as_string()
+        .map(str::to_string)
+}
+
+/// `Version` from the `application.ini` Firefox-based browsers install next to
+/// their executable (e.g. `/usr/lib/firefox/application.ini`)
+fn application_ini_version(path: &Path) -> Option<String> {
+    let path = fs::canonicalize(path).ok()?;
+    let content = fs::read_to_string(path.parent()?.join("application.ini")).ok()?;
+
+    utils::parse_ini(&content)
+        .into_iter()
+        .find(|(section, _)| section == "App")?
+        .1
+        .remove("Version")
+}
+
+/// `version` from the metadata of snaps launched from `/snap/bin`
+fn snap_version(path: &Path) -> Option<String> {
+    let bin = path.parent().filter(|dir| dir.ends_with("bin"))?;
+    let name = path.file_name()?;
+    let meta = bin.parent()?.join(name).join("current/meta/snap.yaml");
+
+    fs::read_to_string(meta)
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("version:"))
+        .map(|version| version.trim().trim_matches(['\'', '"']).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::{PROBE_TIMEOUT, testing::write_executable};
+    use tempfile::TempDir;
+
+    fn set_modified(path: &Path, secs: u64) {
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+            .unwrap();
+    }
+
+    #[test]
+    fn caches_probes_until_the_executable_changes() {
+        let td = TempDir::new().unwrap();
+        let chrome = td.path().join("google-chrome");
+        let cache_file = td.path().join("cache/browsers.json");
+
+        write_executable(&chrome, "#!/bin/sh\necho 'Google Chrome 120.0.1'\n");
+        set_modified(&chrome, 1_000);
+
+        let cache = VersionCache::load_from(&cache_file);
+        assert_eq!(
+            cache.probe(&chrome, PROBE_TIMEOUT).unwrap(),
+            "Google Chrome 120.0.1"
+        );
+        cache.save().unwrap();
+
+        // Same modification time, so the stale output is still used
+        write_executable(&chrome, "#!/bin/sh\necho 'Google Chrome 121.0.1'\n");
+        set_modified(&chrome, 1_000);
+
+        let cache = VersionCache::load_from(&cache_file);
+        assert_eq!(
+            cache.probe(&chrome, PROBE_TIMEOUT).unwrap(),
+            "Google Chrome 120.0.1"
+        );
+
+        set_modified(&chrome, 2_000);
+        assert_eq!(
+            cache.probe(&chrome, PROBE_TIMEOUT).unwrap(),
+            "Google Chrome 121.0.1"
+        );
+    }
+
+    #[test]
+    fn reads_linux_package_metadata() {
+        let td = TempDir::new().unwrap();
+        let root = td.path();
+
+        write_executable(&root.join("usr/lib/firefox/firefox"), "");
+        fs::write(
+            root.join("usr/lib/firefox/application.ini"),
+            "[App]\nVendor=Mozilla\nName=Firefox\nVersion=124.0a1\n",
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("usr/bin")).unwrap();
+        std::os::unix::fs::symlink(
+            root.join("usr/lib/firefox/firefox"),
+            root.join("usr/bin/firefox"),
+        )
+        .unwrap();
+
+        fs::create_dir_all(root.join("snap/chromium/current/meta")).unwrap();
+        fs::write(
+            root.join("snap/chromium/current/meta/snap.yaml"),
+            "name: chromium\nversion: '120.0.6099.224'\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            read_metadata(&root.join("usr/bin/firefox")).as_deref(),
+            Some("124.0a1")
+        );
+        assert_eq!(
+            read_metadata(&root.join("snap/bin/chromium")).as_deref(),
+            Some("120.0.6099.224")
+        );
+        assert_eq!(read_metadata(&root.join("usr/bin/chromium")), None);
+    }
+}
@@ -0,0 +1,446 @@
+use crate::cmd::browser::{
+    self,
+    structures::{Channel, Kind},
+};
+use crate::consts::*;
+use crate::discovery::Roots;
+use crate::dotfiles::Dotfiles;
+use crate::ides::discovery;
+use crate::utils;
+
+use anyhow::{Result, bail};
+use clap::Args;
+use console::style;
+use octocrab::Octocrab;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    env,
+    fmt::{Display, Formatter},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Check the developer environment the other applets depend on
+#[derive(Debug, Args)]
+pub struct Doctor {
+    /// Print results as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Level::Pass => style("pass").green(),
+            Level::Warn => style("warn").yellow(),
+            Level::Fail => style("fail").red(),
+        };
+
+        write!(f, "{label}")
+    }
+}
+
+/// Result of a single check, with a hint on how to fix it if it didn't pass
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Check {
+    pub name: String,
+    pub level: Level,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+impl Check {
+    fn pass(name: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            level: Level::Pass,
+            message: message.into(),
+            hint: None,
+        }
+    }
+
+    fn warn(name: impl Into<String>, message: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            level: Level::Warn,
+            hint: Some(hint.into()),
+            ..Self::pass(name, message)
+        }
+    }
+
+    fn fail(name: impl Into<String>, message: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            level: Level::Fail,
+            ..Self::warn(name, message, hint)
+        }
+    }
+}
+
+impl Display for Check {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {:<16} {}", self.level, self.name, self.message)?;
+
+        if let Some(hint) = &self.hint {
+            write!(f, "\n{:21}{}", "", style(format!("hint: {hint}")).dim())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Doctor {
+    pub async fn run(&self) -> Result<()> {
+        let home = PathBuf::from(env::var(HOME).unwrap_or_default());
+
+        let mut checks = vec![check_git(), check_brew()];
+        checks.extend(check_ssh(&home));
+        checks.push(check_dir(PROJECTS_PATH, env::var(PROJECTS_PATH).ok()));
+        checks.push(check_dotfiles(env::var(DOTFILES_PATH).ok(), &home));
+        checks.push(check_ides());
+        checks.push(check_browsers());
+        checks.push(check_github_token().await);
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&checks)?);
+        } else {
+            checks.iter().for_each(|check| println!("{check}"));
+        }
+
+        let failed = checks
+            .iter()
+            .filter(|check| check.level == Level::Fail)
+            .count();
+
+        if failed > 0 {
+            bail!("{failed} check(s) failed");
+        }
+
+        Ok(())
+    }
+}
+
+/// First line of `<command> --version`, `None` if it can't be run
+fn version(command: &str) -> Option<String> {
+    utils::run_command(command, &[FLAG_VERSION])
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .next()
+                .map(|line| line.trim().to_string())
+        })
+}
+
+fn check_git() -> Check {
+    match version(GIT) {
+        Some(version) => Check::pass(GIT, version),
+        None => Check::fail(
+            GIT,
+            "not found",
+            "Install git (e.g. `brew install git` or your distribution's package)",
+        ),
+    }
+}
+
+fn check_brew() -> Check {
+    match version(BREW) {
+        Some(version) => Check::pass(BREW, version),
+        // Homebrew is optional outside of macOS
+        None if !cfg!(target_os = "macos") => Check::warn(
+            BREW,
+            "not found, browsers installed with Homebrew won't be discovered",
+            "Install Homebrew from https://brew.sh",
+        ),
+        None => Check::fail(BREW, "not found", "Install Homebrew from https://brew.sh"),
+    }
+}
+
+/// Checks the agent is running and has a key loaded for each host in `~/.ssh/config`
+fn check_ssh(home: &Path) -> Vec<Check> {
+    const NAME: &str = "ssh-agent";
+
+    if env::var_os(SSH_AUTH_SOCK).is_none() {
+        return vec![Check::fail(
+            NAME,
+            format!("not running (${SSH_AUTH_SOCK} is not set)"),
+            "Start it with `eval \"$(ssh-agent -s)\"` and load keys with `ssh-add`",
+        )];
+    }
+
+    let loaded = match utils::run_command(SSH_ADD, &[FLAG_LIST_PUBLIC_KEYS]) {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(key_material)
+            .collect::<Vec<_>>(),
+        Ok(_) => vec![],
+        Err(_) => {
+            return vec![Check::fail(
+                NAME,
+                format!("{SSH_ADD} not found"),
+                "Install OpenSSH",
+            )];
+        }
+    };
+
+    if loaded.is_empty() {
+        return vec![Check::fail(
+            NAME,
+            "no keys loaded",
+            "Load a key with `ssh-add ~/.ssh/id_ed25519`",
+        )];
+    }
+
+    let config = fs::read_to_string(home.join(".ssh/config")).unwrap_or_default();
+    let mut checks = vec![Check::pass(NAME, format!("{} key(s) loaded", loaded.len()))];
+
+    for (host, identities) in ssh_hosts(&config, home) {
+        let name = format!("ssh {host}");
+        let is_loaded = identities.iter().any(|identity| {
+            let mut public = identity.clone().into_os_string();
+            public.push(".pub");
+
+            fs::read_to_string(public)
+                .ok()
+                .and_then(|key| key_material(&key))
+                .is_some_and(|key| loaded.contains(&key))
+        });
+
+        checks.push(match is_loaded {
+            true => Check::pass(name, "key loaded"),
+            false => Check::warn(
+                name,
+                "no key for the host is loaded",
+                format!("ssh-add {}", utils::format_path(&identities[0])),
+            ),
+        });
+    }
+
+    checks
+}
+
+/// Key type and base64 blob of a public key line, without the comment
+fn key_material(line: &str) -> Option<String> {
+    let mut fields = line.split_whitespace();
+    let (kind, key) = (fields.next()?, fields.next()?);
+
+    Some(format!("{kind} {key}"))
+}
+
+/// Hosts with identity files from an ssh config (patterns with wildcards are skipped)
+fn ssh_hosts(config: &str, home: &Path) -> BTreeMap<String, Vec<PathBuf>> {
+    let mut hosts: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+    let mut current: Vec<String> = vec![];
+
+    for line in config.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (keyword, value) = line
+            .split_once(|c: char| c.is_whitespace() || c == '=')
+            .map(|(keyword, value)| (keyword, value.trim_start_matches([' ', '\t', '=']).trim()))
+            .unwrap_or((line, ""));
+
+        match keyword.to_lowercase().as_str() {
+            "host" => {
+                current = value
+                    .split_whitespace()
+                    .filter(|pattern| !pattern.contains(['*', '?', '!']))
+                    .map(str::to_string)
+                    .collect();
+            }
+            "match" => current.clear(),
+            "identityfile" => {
+                let value = value.trim_matches('"');
+                let identity = match value.strip_prefix("~/") {
+                    Some(rest) => home.join(rest),
+                    None => PathBuf::from(value),
+                };
+
+                for host in &current {
+                    hosts
+                        .entry(host.clone())
+                        .or_default()
+                        .push(identity.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    hosts
+}
+
+fn check_dir(name: &str, value: Option<String>) -> Check {
+    let hint = format!("Set ${name} to an existing directory (e.g. in your shell rc file)");
+
+    match value.filter(|value| !value.is_empty()) {
+        Some(value) if Path::new(&value).is_dir() => Check::pass(name, value),
+        Some(value) => Check::fail(name, format!("{value} is not a directory"), hint),
+        None => Check::warn(name, "not set", hint),
+    }
+}
+
+fn check_dotfiles(value: Option<String>, home: &Path) -> Check {
+    let check = check_dir(DOTFILES_PATH, value.clone());
+
+    let (Level::Pass, Some(path)) = (check.level, value) else {
+        return check;
+    };
+
+    match Dotfiles::load(Path::new(&path), home) {
+        Ok(_) => check,
+        Err(err) => Check::warn(
+            DOTFILES_PATH,
+            format!("{:#}", err),
+            format!("Declare links in {DOTFILES_MANIFEST} to use `af dot link`"),
+        ),
+    }
+}
+
+fn check_ides() -> Check {
+    const NAME: &str = "IDEs";
+
+    let installed = discovery::installed();
+
+    if installed.is_empty() {
+        return Check::warn(
+            NAME,
+            "none of the supported IDEs were found",
+            "Install one, or check what's discovered with `af ide find --all -v`",
+        );
+    }
+
+    let ids: Vec<_> = installed
+        .iter()
+        .map(|installation| installation.id())
+        .collect();
+
+    Check::pass(NAME, ids.join(", "))
+}
+
+fn check_browsers() -> Check {
+    const NAME: &str = "browsers";
+
+    let browsers = browser::structures::find_browsers(
+        &Roots::from_env(),
+        &browser::search_dirs(),
+        &Kind::all(),
+        &Channel::all(),
+        false,
+    );
+
+    if browsers.is_empty() {
+        return Check::warn(
+            NAME,
+            "none of the supported browsers were found",
+            "Check what's discovered with `af browser find --all -v`",
+        );
+    }
+
+    let names: Vec<_> = browsers.iter().map(|browser| browser.name()).collect();
+
+    Check::pass(NAME, names.join(", "))
+}
+
+async fn check_github_token() -> Check {
+    const NAME: &str = "GitHub token";
+
+    let Some(token) = [GITHUB_TOKEN, GH_TOKEN]
+        .into_iter()
+        .find_map(|var| env::var(var).ok().filter(|token| !token.is_empty()))
+    else {
+        return Check::warn(
+            NAME,
+            format!("${GITHUB_TOKEN} is not set, API requests are rate limited"),
+            format!("Export ${GITHUB_TOKEN} (e.g. from `gh auth token`)"),
+        );
+    };
+
+    let user = match Octocrab::builder().personal_token(token).build() {
+        Ok(octocrab) => octocrab.current().user().await,
+        Err(err) => Err(err),
+    };
+
+    match user {
+        Ok(user) => Check::pass(NAME, format!("authenticated as {}", user.login)),
+        Err(err) => Check::fail(
+            NAME,
+            format!("rejected by GitHub: {err}"),
+            format!("Create a new token (e.g. `gh auth refresh`) and update ${GITHUB_TOKEN}"),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn parses_ssh_config_hosts() {
+        let home = Path::new("/home/me");
+        let config = r#"
+            # Personal
+            Host github.com gh
+                IdentityFile ~/.ssh/personal
+            Host *.corp
+                IdentityFile ~/.ssh/corp
+            Host gitlab.com
+                User git
+                IdentityFile=/keys/gitlab
+            Match exec "true"
+                IdentityFile ~/.ssh/other
+        "#;
+
+        assert_eq!(
+            ssh_hosts(config, home),
+            BTreeMap::from([
+                ("gh".to_string(), vec![home.join(".ssh/personal")]),
+                ("github.com".to_string(), vec![home.join(".ssh/personal")]),
+                (
+                    "gitlab.com".to_string(),
+                    vec![PathBuf::from("/keys/gitlab")]
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn compares_keys_without_comments() {
+        assert_eq!(
+            key_material("ssh-ed25519 AAAAC3Nz me@laptop\n"),
+            key_material("ssh-ed25519 AAAAC3Nz /home/me/.ssh/id_ed25519"),
+        );
+        assert_eq!(key_material(""), None);
+    }
+
+    #[test]
+    fn checks_directories() {
+        let td = TempDir::new().unwrap();
+        let dir = td.path().display().to_string();
+        let file = td.path().join("file");
+        fs::write(&file, "").unwrap();
+
+        assert_eq!(check_dir(PROJECTS_PATH, Some(dir)).level, Level::Pass);
+        assert_eq!(check_dir(PROJECTS_PATH, None).level, Level::Warn);
+        assert_eq!(
+            check_dir(PROJECTS_PATH, Some(file.display().to_string())).level,
+            Level::Fail
+        );
+        assert_eq!(
+            check_dotfiles(Some(td.path().display().to_string()), td.path()).level,
+            Level::Warn
+        );
+    }
+}
@@ -0,0 +1,231 @@
+use crate::cmd::dot::{DotfilesPath, print_diff_line};
+use crate::consts::{COMMIT, EDITOR, FLAG_MESSAGE, GIT, GIT_DIRECTORY, SH, VISUAL};
+use crate::ides::{self, Location, detect, discovery};
+use crate::utils;
+
+use anyhow::{Result, anyhow, bail};
+use clap::{Args, builder::PossibleValuesParser};
+use console::style;
+use dialoguer::{Confirm, FuzzySelect, Input, theme::ColorfulTheme};
+use git2::{DiffOptions, Repository, Status};
+use log::debug;
+use std::{env, path::Path, process::Command};
+
+#[derive(Debug, Args)]
+pub struct Edit {
+    #[command(flatten)]
+    dotfiles: DotfilesPath,
+
+    /// Open the file in this IDE instead of $VISUAL/$EDITOR
+    #[arg(long, short, value_parser = PossibleValuesParser::new(ides::ids()))]
+    ide: Option<String>,
+
+    /// Part of the file path to search for (opens the file directly if only one matches)
+    query: Option<String>,
+}
+
+impl Edit {
+    pub fn run(&self) -> Result<()> {
+        let repo = Repository::open(self.dotfiles.dir()?)?;
+        let root = repo
+            .workdir()
+            .ok_or_else(|| anyhow!("Dotfiles repository has no working directory"))?;
+
+        let files = tracked_files(&repo)?;
+        let query = self.query.as_deref().unwrap_or_default();
+
+        let file = match matching(&files, query).as_slice() {
+            [file] => file.to_string(),
+            _ => {
+                let Some(index) = FuzzySelect::with_theme(&ColorfulTheme::default())
+                    .with_prompt("Select a dotfile to edit")
+                    .with_initial_text(query)
+                    .items(&files)
+                    .interact_opt()?
+                else {
+                    return Ok(());
+                };
+
+                files[index].clone()
+            }
+        };
+
+        if !self.open(root, &file)? {
+            return Ok(());
+        }
+
+        let status = repo.status_file(Path::new(&file))?;
+        if !status.intersects(
+            Status::WT_MODIFIED
+                | Status::WT_DELETED
+                | Status::WT_TYPECHANGE
+                | Status::INDEX_MODIFIED
+                | Status::INDEX_DELETED
+                | Status::INDEX_TYPECHANGE,
+        ) {
+            println!("No changes to {}", style(&file).bold());
+            return Ok(());
+        }
+
+        let theme = ColorfulTheme::default();
+
+        if Confirm::with_theme(&theme)
+            .with_prompt("Show the diff?")
+            .default(true)
+            .interact()?
+        {
+            let mut options = DiffOptions::new();
+            options.pathspec(&file);
+
+            // Against HEAD, so staged changes show up too
+            let head = repo.head().and_then(|head| head.peel_to_tree()).ok();
+            repo.diff_tree_to_workdir_with_index(head.as_ref(), Some(&mut options))?
+                .print(git2::DiffFormat::Patch, print_diff_line)?;
+        }
+
+        if !Confirm::with_theme(&theme)
+            .with_prompt("Commit the change?")
+            .default(true)
+            .interact()?
+        {
+            return Ok(());
+        }
+
+        let message: String = Input::with_theme(&theme)
+            .with_prompt("Commit message")
+            .default(commit_message(&file))
+            .interact_text()?;
+
+        // Committing through git keeps hooks and commit signing working
+        let root = root.to_string_lossy();
+        let output = utils::run_command(
+            GIT,
+            &[
+                GIT_DIRECTORY,
+                &root,
+                COMMIT,
+                FLAG_MESSAGE,
+                &message,
+                "--",
+                &file,
+            ],
+        )?;
+
+        if !output.status.success() {
+            bail!(
+                "Failed to commit {file}: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        println!("Committed {}", style(&file).bold());
+
+        Ok(())
+    }
+
+    /// Opens the file and waits for the editor (or the user, for IDEs) to
+    /// finish, `false` if the user didn't
+    fn open(&self, root: &Path, file: &str) -> Result<bool> {
+        let path = root.join(file);
+        let editor = [VISUAL, EDITOR]
+            .into_iter()
+            .find_map(|var| env::var(var).ok().filter(|editor| !editor.is_empty()));
+
+        let ide = match (&self.ide, editor) {
+            (Some(id), _) => Some(id.clone()),
+            (None, Some(editor)) => {
+                debug!("Opening {} with {editor}", path.display());
+
+                // Editors are often configured with arguments (e.g. `code --wait`)
+                let status = Command::new(SH)
+                    .args(["-c", &format!("{editor} \"$@\""), SH])
+                    .arg(&path)
+                    .status()?;
+
+                if !status.success() {
+                    bail!("{editor} exited with {status}");
+                }
+
+                return Ok(true);
+            }
+            (None, None) => detect::detect().map(|detected| detected.id().to_string()),
+        };
+
+        let Some(id) = ide else {
+            bail!("Set $EDITOR or use --ide to choose how to edit dotfiles");
+        };
+
+        let installed = discovery::installed();
+        let installation = installed
+            .iter()
+            .find(|installation| installation.id() == id)
+            .ok_or_else(|| anyhow!("IDE {id} is not installed"))?;
+
+        installation.open(Some(root), Some(&Location::new(&path, None, None)))?;
+
+        let done = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!("Editing in {}, done?", installation.name))
+            .default(true)
+            .show_default(false)
+            .interact()?;
+
+        Ok(done)
+    }
+}
+
+/// Paths of files in the repository's index
+fn tracked_files(repo: &Repository) -> Result<Vec<String>> {
+    let index = repo.index()?;
+
+    Ok(index
+        .iter()
+        .map(|entry| String::from_utf8_lossy(&entry.path).into_owned())
+        .collect())
+}
+
+/// Files containing the query, ignoring case (all of them for an empty query)
+fn matching<'a>(files: &'a [String], query: &str) -> Vec<&'a str> {
+    let query = query.to_lowercase();
+
+    files
+        .iter()
+        .filter(|file| file.to_lowercase().contains(&query))
+        .map(String::as_str)
+        .collect()
+}
+
+fn commit_message(file: &str) -> String {
+    format!("Update {file}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn matches_tracked_files() {
+        let td = TempDir::new().unwrap();
+        let repo = Repository::init(td.path()).unwrap();
+
+        fs::create_dir_all(td.path().join("zsh")).unwrap();
+        for file in ["zsh/.zshrc", "zsh/.zprofile", ".gitconfig", "untracked"] {
+            fs::write(td.path().join(file), "").unwrap();
+        }
+
+        let mut index = repo.index().unwrap();
+        for file in ["zsh/.zshrc", "zsh/.zprofile", ".gitconfig"] {
+            index.add_path(Path::new(file)).unwrap();
+        }
+        index.write().unwrap();
+
+        let files = tracked_files(&repo).unwrap();
+        assert_eq!(files, vec![".gitconfig", "zsh/.zprofile", "zsh/.zshrc"]);
+
+        assert_eq!(matching(&files, "ZSHRC"), vec!["zsh/.zshrc"]);
+        assert_eq!(matching(&files, "zsh").len(), 2);
+        assert_eq!(matching(&files, "").len(), 3);
+        assert!(matching(&files, "untracked").is_empty());
+    }
+}
@@ -0,0 +1,176 @@
+use crate::cmd::dot::DotfilesPath;
+use crate::cmd::dot::templates::Data;
+use crate::dotfiles::links::{self, Action, OnConflict};
+use crate::dotfiles::templates;
+use crate::state::State;
+use crate::utils;
+
+use anyhow::{Result, bail};
+use clap::Args;
+use console::style;
+use log::debug;
+
+#[derive(Debug, Args)]
+pub struct Link {
+    #[command(flatten)]
+    dotfiles: DotfilesPath,
+
+    /// Move existing targets aside (to <target>.af-backup) before linking
+    #[arg(long, conflicts_with = "adopt")]
+    backup: bool,
+
+    /// Move existing targets into the dotfiles directory, replacing the sources
+    #[arg(long)]
+    adopt: bool,
+
+    /// Print what would be done without changing anything
+    #[arg(long, short = 'n')]
+    dry_run: bool,
+}
+
+impl Link {
+    pub fn run(&self) -> Result<()> {
+        let dotfiles = self.dotfiles.load()?;
+        let on_conflict = match (self.backup, self.adopt) {
+            (true, _) => OnConflict::Backup,
+            (_, true) => OnConflict::Adopt,
+            _ => OnConflict::Skip,
+        };
+
+        let mut skipped = 0;
+
+        for link in dotfiles.links()? {
+            let action = link.plan(on_conflict);
+            let description = link.describe(&dotfiles.root);
+
+            match &action {
+                Action::Keep => {
+                    debug!("Already linked: {description}");
+                    continue;
+                }
+                Action::Skip(reason) => {
+                    skipped += 1;
+                    println!(
+                        "{:>8} {description} ({reason})",
+                        style(action.verb()).yellow()
+                    );
+                    continue;
+                }
+                Action::Backup(backup) => println!(
+                    "{:>8} {description} (to {})",
+                    style(action.verb()).cyan(),
+                    utils::format_path(backup)
+                ),
+                _ => println!("{:>8} {description}", style(action.verb()).green()),
+            }
+
+            if !self.dry_run {
+                link.apply(&action)?;
+            }
+        }
+
+        if skipped > 0 {
+            bail!("{skipped} link(s) skipped, resolve conflicts with --backup or --adopt");
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct Unlink {
+    #[command(flatten)]
+    dotfiles: DotfilesPath,
+
+    /// Print what would be removed without changing anything
+    #[arg(long, short = 'n')]
+    dry_run: bool,
+}
+
+impl Unlink {
+    pub fn run(&self) -> Result<()> {
+        let dotfiles = self.dotfiles.load()?;
+
+        for link in dotfiles.links()? {
+            if link.unlink(&dotfiles.root, self.dry_run)? {
+                println!(
+                    "{:>8} {}",
+                    style("unlink").green(),
+                    link.describe(&dotfiles.root)
+                );
+            } else {
+                debug!(
+                    "Not a link into the dotfiles directory: {}",
+                    link.target.display()
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct Status {
+    #[command(flatten)]
+    dotfiles: DotfilesPath,
+
+    #[command(flatten)]
+    data: Data,
+}
+
+impl Status {
+    pub fn run(&self) -> Result<()> {
+        let dotfiles = self.dotfiles.load()?;
+
+        for link in dotfiles.links()? {
+            let status = link.status();
+            let label = format!("{status:>8}");
+            let label = match status {
+                links::Status::Linked => style(label).green(),
+                links::Status::Missing => style(label).yellow(),
+                _ => style(label).red(),
+            };
+
+            match (status, link.destination()) {
+                (links::Status::Broken | links::Status::Foreign, Some(destination)) => println!(
+                    "{label} {} (points to {})",
+                    link.describe(&dotfiles.root),
+                    destination.display()
+                ),
+                _ => println!("{label} {}", link.describe(&dotfiles.root)),
+            }
+        }
+
+        let templates = dotfiles.templates()?;
+        if templates.is_empty() {
+            return Ok(());
+        }
+
+        let variables = self.data.load(&dotfiles)?;
+        let state = State::load()?;
+
+        for template in templates {
+            let description = template.describe(&dotfiles.root);
+            let rendered = match template.render(&variables) {
+                Ok(rendered) => rendered,
+                Err(err) => {
+                    println!("{:>8} {description} ({err:#})", style("error").red());
+                    continue;
+                }
+            };
+
+            let status = template.status(&rendered, &state);
+            let label = format!("{status:>8}");
+            let label = match status {
+                templates::Status::Rendered => style(label).green(),
+                templates::Status::Missing | templates::Status::Outdated => style(label).yellow(),
+                _ => style(label).red(),
+            };
+
+            println!("{label} {description}");
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,180 @@
+mod doctor;
+mod edit;
+mod links;
+mod templates;
+
+use crate::consts::{DOTFILES_PATH, GO, HOME};
+use crate::dotfiles::Dotfiles;
+use crate::ides;
+use crate::ides::preferences::{self, Project};
+use crate::ides::{detect, discovery};
+use anyhow::{Result, anyhow, bail};
+use clap::{Args, Subcommand, ValueHint, builder::PossibleValuesParser, value_parser};
+use clio::ClioPath;
+use console::style;
+use git2::{DiffDelta, DiffHunk, DiffLine};
+use std::{env, path::Path};
+
+#[derive(Debug, Args)]
+#[command(visible_alias = ".")]
+#[command(args_conflicts_with_subcommands = true)]
+#[command(flatten_help = true)]
+#[command(disable_help_subcommand = true)]
+pub struct DotCmd {
+    /// Optional dotfiles subcommand
+    #[command(subcommand)]
+    pub command: Option<DotCommands>,
+
+    /// IDE-related options (used when no subcommand is given)
+    #[command(flatten)]
+    pub ide: Ide,
+}
+
+impl DotCmd {
+    pub async fn run(&self) -> Result<()> {
+        match &self.command {
+            Some(DotCommands::Ide(args)) => args.run(),
+            Some(DotCommands::Link(args)) => args.run(),
+            Some(DotCommands::Unlink(args)) => args.run(),
+            Some(DotCommands::Status(args)) => args.run(),
+            Some(DotCommands::Render(args)) => args.run(),
+            Some(DotCommands::Diff(args)) => args.run(),
+            Some(DotCommands::Doctor(args)) => args.run().await,
+            Some(DotCommands::Edit(args)) => args.run(),
+            None => self.ide.run(),
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DotCommands {
+    /// Open the dotfiles directory in an IDE
+    ///
+    /// Uses the IDE pinned in the dotfiles' .af.toml or remembered from an earlier
+    /// --ide choice. If running inside an IDE (or its terminal), it will use that
+    /// IDE to open the path. Otherwise, it tries to open in GoLand.
+    Ide(Ide),
+
+    /// Symlink dotfiles into the home directory
+    ///
+    /// Links are declared in dotfiles.toml in the dotfiles directory, either as
+    /// stow-style package directories or as explicit source/target entries.
+    /// Existing files are left untouched unless --backup or --adopt is used.
+    Link(links::Link),
+
+    /// Remove symlinks pointing into the dotfiles directory
+    Unlink(links::Unlink),
+
+    /// Show the state of each declared link and template (alias: st)
+    #[command(visible_alias = "st")]
+    Status(links::Status),
+
+    /// Render templates (*.tmpl) with per-machine variables
+    ///
+    /// Placeholders like {{ hostname }} are replaced with built-in variables
+    /// (hostname, os, arch, user, home) and values from the machine's data file.
+    /// Targets edited since they were last rendered are left untouched unless
+    /// --force is used.
+    Render(templates::Render),
+
+    /// Show how rendered templates differ from the deployed files
+    Diff(templates::Diff),
+
+    /// Check the developer environment the other commands depend on
+    ///
+    /// Reports on git, Homebrew, ssh-agent keys for hosts in ~/.ssh/config,
+    /// $PROJECTS_PATH and $DOTFILES_PATH, installed IDEs and browsers, and the
+    /// GitHub token. Exits with a non-zero code if any check fails.
+    Doctor(doctor::Doctor),
+
+    /// Pick a tracked dotfile and edit it (alias: e)
+    ///
+    /// Opens the file in $VISUAL/$EDITOR, or in the IDE running af when neither
+    /// is set. Afterwards offers to show the diff and commit the change.
+    #[command(visible_alias = "e")]
+    Edit(edit::Edit),
+}
+
+/// Location of the dotfiles directory
+#[derive(Debug, Args)]
+pub struct DotfilesPath {
+    /// Path to the dotfiles directory (overrides $DOTFILES_PATH)
+    #[arg(
+        long,
+        env = DOTFILES_PATH,
+        value_hint = ValueHint::DirPath,
+        value_parser = value_parser!(ClioPath).exists().is_dir(),
+    )]
+    pub path: Option<ClioPath>,
+}
+
+impl DotfilesPath {
+    pub fn dir(&self) -> Result<&Path> {
+        match &self.path {
+            Some(path) => Ok(path.path()),
+            None => bail!("Dotfiles directory not set, use --path or ${DOTFILES_PATH}"),
+        }
+    }
+
+    /// Loads the dotfiles manifest, linking into $HOME
+    pub fn load(&self) -> Result<Dotfiles> {
+        let home = env::var(HOME).map_err(|_| anyhow!("${HOME} is not set"))?;
+
+        Dotfiles::load(self.dir()?, home.as_ref())
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct Ide {
+    #[command(flatten)]
+    pub dotfiles: DotfilesPath,
+
+    /// IDE to open the dotfiles with (remembered for next time)
+    #[arg(long, short, value_parser = PossibleValuesParser::new(ides::ids()))]
+    pub ide: Option<String>,
+}
+
+impl Ide {
+    pub fn run(&self) -> Result<()> {
+        let Some(path) = &self.dotfiles.path else {
+            return Ok(());
+        };
+
+        let installed = discovery::installed();
+        let project = Project::discover(path);
+
+        let installation = match preferences::preferred(&installed, &project, self.ide.as_deref())?
+        {
+            Some(installation) => installation,
+            None => {
+                let detected = detect::detect().map(|d| d.id());
+
+                [detected, ides::get(GO)]
+                    .into_iter()
+                    .flatten()
+                    .find_map(|id| installed.iter().find(|i| i.id() == id))
+                    .or(installed.first())
+                    .ok_or_else(|| anyhow!("No installed IDEs found"))?
+            }
+        };
+
+        installation.open(Some(path), None)
+    }
+}
+
+/// Prints a line of a patch, colored like `git diff`
+pub fn print_diff_line(_: DiffDelta, _: Option<DiffHunk>, line: DiffLine) -> bool {
+    let content = String::from_utf8_lossy(line.content());
+    let content = content.trim_end_matches('\n');
+
+    match line.origin() {
+        '+' => println!("{}", style(format!("+{content}")).green()),
+        '-' => println!("{}", style(format!("-{content}")).red()),
+        ' ' => println!(" {content}"),
+        'H' => println!("{}", style(content).cyan()),
+        'F' => println!("{}", style(content).bold()),
+        _ => println!("{content}"),
+    }
+
+    true
+}
@@ -0,0 +1,124 @@
+use crate::cmd::dot::{DotfilesPath, print_diff_line};
+use crate::dotfiles::Dotfiles;
+use crate::dotfiles::templates::{Status, Variables};
+use crate::state::State;
+
+use anyhow::{Result, bail};
+use clap::{Args, ValueHint};
+use console::style;
+use git2::Patch;
+use log::debug;
+use std::path::PathBuf;
+
+/// Source of per-machine template variables
+#[derive(Debug, Args)]
+pub struct Data {
+    /// File with template variables (defaults to $XDG_CONFIG_HOME/af/machine.toml)
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    data: Option<PathBuf>,
+}
+
+impl Data {
+    pub fn load(&self, dotfiles: &Dotfiles) -> Result<Variables> {
+        Variables::load(self.data.as_deref(), &dotfiles.home)
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct Render {
+    #[command(flatten)]
+    dotfiles: DotfilesPath,
+
+    #[command(flatten)]
+    data: Data,
+
+    /// Overwrite targets edited locally or not rendered by af
+    #[arg(long, short)]
+    force: bool,
+
+    /// Print what would be written without changing anything
+    #[arg(long, short = 'n')]
+    dry_run: bool,
+}
+
+impl Render {
+    pub fn run(&self) -> Result<()> {
+        let dotfiles = self.dotfiles.load()?;
+        let variables = self.data.load(&dotfiles)?;
+        let mut state = State::load()?;
+        let mut skipped = 0;
+
+        for template in dotfiles.templates()? {
+            let rendered = template.render(&variables)?;
+            let status = template.status(&rendered, &state);
+            let description = template.describe(&dotfiles.root);
+
+            match status {
+                Status::Rendered => {
+                    debug!("Up to date: {description}");
+                    template.record(&rendered, &mut state);
+                    continue;
+                }
+                status if status.is_conflict() && !self.force => {
+                    skipped += 1;
+                    println!("{:>8} {description} ({status})", style("skip").yellow());
+                    continue;
+                }
+                _ => println!("{:>8} {description}", style("render").green()),
+            }
+
+            if !self.dry_run {
+                template.write(&rendered, &mut state)?;
+            }
+        }
+
+        if !self.dry_run {
+            state.save()?;
+        }
+
+        if skipped > 0 {
+            bail!(
+                "{skipped} template(s) skipped to keep local edits, check them with `af dot diff` and rerun with --force"
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct Diff {
+    #[command(flatten)]
+    dotfiles: DotfilesPath,
+
+    #[command(flatten)]
+    data: Data,
+}
+
+impl Diff {
+    pub fn run(&self) -> Result<()> {
+        let dotfiles = self.dotfiles.load()?;
+        let variables = self.data.load(&dotfiles)?;
+
+        for template in dotfiles.templates()? {
+            let rendered = template.render(&variables)?;
+            let deployed = template.deployed()?.unwrap_or_default();
+
+            if deployed == rendered {
+                continue;
+            }
+
+            let mut patch = Patch::from_buffers(
+                deployed.as_bytes(),
+                Some(&template.target),
+                rendered.as_bytes(),
+                Some(&template.source),
+                None,
+            )?;
+
+            patch.print(&mut print_diff_line)?;
+        }
+
+        Ok(())
+    }
+}
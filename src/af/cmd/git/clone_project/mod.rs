@@ -1,22 +1,27 @@
+use crate::cmd::git::{self, checkout_reference, reference_exists};
 use crate::consts::*;
+use crate::ides::preferences::{self, Project};
+use crate::ides::{Location, detect, discovery};
 use crate::repo::Repo;
-use crate::{ides, utils};
+use crate::utils::{self, BlobUrl};
 use anyhow::{Result, anyhow};
 use clap::{Args, ValueHint, value_parser};
 use clio::ClioPath;
 use console::style;
-use dialoguer::{Confirm, FuzzySelect, Input, theme::ColorfulTheme};
-use git2::{Cred, FetchOptions, RemoteCallbacks, Repository, StatusOptions, build::RepoBuilder};
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-use log::{debug, info, trace};
-use regex::Regex;
-use std::{env, fs, time::Duration};
+use dialoguer::{Confirm, Input, theme::ColorfulTheme};
+use git2::{FetchOptions, Repository, StatusOptions, build::RepoBuilder};
+use indicatif::MultiProgress;
+use log::{debug, info, trace, warn};
+use std::fs;
 use thiserror::Error;
 
 /// Clone a project repository and optionally open it in an IDE
 #[derive(Debug, Args)]
 pub struct CloneProject {
     /// The repository URL to clone (e.g. git@github.com:org/project.git)
+    ///
+    /// GitHub blob URLs (e.g. https://github.com/org/project/blob/main/src/lib.rs#L42)
+    /// locate an existing clone if present and open the IDE at that file and line.
     #[arg(value_parser = utils::parse_repository)]
     repository_url: Option<String>,
 
@@ -31,7 +36,7 @@ pub struct CloneProject {
     /// Root directory for placing the cloned project (uses $PROJECTS_PATH if set)
     #[arg(
         long,
-        env = "PROJECTS_PATH",
+        env = PROJECTS_PATH,
         required_unless_present = "directory",
         value_hint = ValueHint::DirPath,
         value_parser = value_parser!(ClioPath).exists().is_dir(),
@@ -49,14 +54,18 @@ pub struct CloneProject {
     /// If used URL is in HTTP(S) format, convert it to SSH format before cloning
     #[arg(long, default_value_t = true, require_equals = true)]
     convert_to_ssh: std::primitive::bool,
+
+    /// Check out the reference from a GitHub blob URL
+    #[arg(long)]
+    checkout: bool,
 }
 
 impl CloneProject {
     pub async fn run(&self, multi_progress: &MultiProgress) -> Result<()> {
         trace!("Arguments: {:?}", self);
 
-        let repository_url = match &self.repository_url {
-            Some(url) => self.parse_repository(url)?,
+        let input_url = match &self.repository_url {
+            Some(url) => url.clone(),
             None => {
                 let theme = &ColorfulTheme::default();
 
@@ -71,13 +80,19 @@ impl CloneProject {
 
                 if utils::validate_repository(&clipboard).is_ok() {
                     info!("Using clipboard contents: {}", &clipboard);
-                    input = input.default(self.parse_repository(clipboard)?);
+                    input = input.default(clipboard);
                 }
 
                 input.interact()?
             }
         };
 
+        let mut blob = utils::parse_blob_url(&input_url);
+        let repository_url = match &blob {
+            Some(blob) => self.parse_repository(&blob.repository)?,
+            None => self.parse_repository(&input_url)?,
+        };
+
         let repo = Repo::parse(&repository_url)?;
         let directory = self
             .directory
@@ -91,6 +106,28 @@ impl CloneProject {
                 anyhow!("At least one of --directory or --root-directory must be provided")
             })?;
 
+        // A blob URL points at a file in a project which is likely cloned already
+        if blob.is_some()
+            && !self.force
+            && let Ok(existing) = Repository::open(directory.to_path_buf())
+        {
+            info!(
+                "Using existing repository: {}",
+                style(utils::format_directory(&directory)).bold(),
+            );
+
+            resolve_reference(&existing, blob.as_mut());
+            self.checkout_maybe(&existing, blob.as_ref())?;
+
+            return self
+                .open_ide_maybe(
+                    &repo,
+                    &directory,
+                    location(&directory, blob.as_ref()).as_ref(),
+                )
+                .await;
+        }
+
         // Clone repository with progress
         let cloned_repo_maybe = clone_repository(
             multi_progress,
@@ -101,22 +138,27 @@ impl CloneProject {
         )
         .await;
 
-        if let Err(err) = &cloned_repo_maybe {
-            if let Some(err) = err.downcast_ref::<CloneRepositoryError>() {
-                return match err {
-                    CloneRepositoryError::OperationCancelled => {
-                        let confirmed = Confirm::with_theme(&ColorfulTheme::default())
-                            .with_prompt("Do you want to open the repository in IDE?")
-                            .interact()?;
-
-                        if !confirmed {
-                            return Ok(());
-                        }
-
-                        self.open_ide_maybe(&repo, &directory).await
+        if let Err(err) = &cloned_repo_maybe
+            && let Some(err) = err.downcast_ref::<CloneRepositoryError>()
+        {
+            return match err {
+                CloneRepositoryError::OperationCancelled => {
+                    let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+                        .with_prompt("Do you want to open the repository in IDE?")
+                        .interact()?;
+
+                    if !confirmed {
+                        return Ok(());
                     }
-                };
-            }
+
+                    self.open_ide_maybe(
+                        &repo,
+                        &directory,
+                        location(&directory, blob.as_ref()).as_ref(),
+                    )
+                    .await
+                }
+            };
         }
 
         let cloned_repo = cloned_repo_maybe?;
@@ -126,44 +168,64 @@ impl CloneProject {
             cloned_repo.remote_rename(ORIGIN, UPSTREAM)?;
         }
 
+        resolve_reference(&cloned_repo, blob.as_mut());
+        self.checkout_maybe(&cloned_repo, blob.as_ref())?;
+
         // Open IDE if requested
-        self.open_ide_maybe(&repo, &directory).await?;
+        self.open_ide_maybe(
+            &repo,
+            &directory,
+            location(&directory, blob.as_ref()).as_ref(),
+        )
+        .await?;
 
         Ok(())
     }
 
-    /// Opens the cloned project in an IDE if available.
-    async fn open_ide_maybe(&self, repo: &Repo<'_>, directory: &ClioPath) -> Result<()> {
+    /// Checks out the blob URL's reference if requested.
+    fn checkout_maybe(&self, repository: &Repository, blob: Option<&BlobUrl>) -> Result<()> {
+        match blob {
+            Some(blob) if self.checkout => checkout_reference(repository, &blob.reference),
+            _ => Ok(()),
+        }
+    }
+
+    /// Opens the cloned project in an IDE if available, optionally at a file location.
+    async fn open_ide_maybe(
+        &self,
+        repo: &Repo<'_>,
+        directory: &ClioPath,
+        location: Option<&Location>,
+    ) -> Result<()> {
         if !self.open_ide {
             return Ok(());
         }
 
-        let re = Regex::new(r"application\.com\.jetbrains\.(\w+)(?:-.+)?(?:\.\d+)*")?;
-        let xpc_service_name = env::var(XPC_SERVICE_NAME).unwrap_or_default();
-
-        let ide = match repo.find_ide().await? {
-            Some(ide) => Some(ide),
-            None => re
-                .captures(&xpc_service_name)
-                .map(|c| c.get(1).map_or("", |m| m.as_str())),
-        };
+        let installed = discovery::installed();
 
-        debug!("Detected IDE: {:?}", ide);
+        if installed.is_empty() {
+            warn!("No installed IDEs found");
+            return Ok(());
+        }
 
-        let ides = ides::list();
+        // Pinned and remembered IDEs skip the (GitHub API backed) language lookup
+        let project = Project::discover(directory);
+        let installation = match preferences::preferred(&installed, &project, None)? {
+            Some(installation) => Some(installation),
+            None => {
+                let ide = match repo.find_ide(&installed).await? {
+                    Some(ide) => Some(ide),
+                    None => detect::detect().map(|detected| detected.id()),
+                };
 
-        let index = ides
-            .binary_search(&ide.unwrap_or(ides[0]))
-            .map_err(|e| anyhow!("{:?}", e))?;
+                debug!("Detected IDE: {:?}", ide);
 
-        let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
-            .with_prompt("Select an IDE to open the project, or press 'Esc' to skip")
-            .default(index)
-            .items(&ides)
-            .interact_opt()?;
+                preferences::choose(&installed, &project, None, ide)?
+            }
+        };
 
-        if let Some(selected) = selection {
-            utils::run_command(ides[selected], &[directory.to_str().unwrap()])?;
+        if let Some(installation) = installation {
+            installation.open(Some(directory), location)?;
         }
 
         Ok(())
@@ -178,6 +240,23 @@ impl CloneProject {
     }
 }
 
+/// Extends the blob URL's reference with path segments naming a longer one,
+/// e.g. `feat/x` from `blob/feat/x/src/lib.rs`
+fn resolve_reference(repository: &Repository, blob: Option<&mut BlobUrl>) {
+    if let Some(blob) = blob {
+        blob.resolve_reference(|reference| reference_exists(repository, reference));
+        debug!("Blob URL reference: {}", blob.reference);
+    }
+}
+
+/// File location the blob URL points at within the project directory
+fn location(directory: &ClioPath, blob: Option<&BlobUrl>) -> Option<Location> {
+    blob.map(|blob| {
+        let path = directory.to_path_buf().join(&blob.path);
+        Location::new(path, blob.line, blob.column)
+    })
+}
+
 #[derive(Error, Debug)]
 enum CloneRepositoryError {
     #[error("Operation Canceled")]
@@ -253,29 +332,8 @@ async fn clone_repository(
         fs::remove_dir_all(directory.to_path_buf())?;
     }
 
-    let pb = mp.add(ProgressBar::no_length().with_message("Cloning"));
-    pb.enable_steady_tick(Duration::from_millis(80));
-    pb.set_style(
-        ProgressStyle::with_template(
-            "{msg:.green.bold} {spinner}[{elapsed_precise}] {wide_bar} {percent:>3}%",
-        )?
-        .tick_strings(&["⢎  ", "⠎⠁ ", "⠊⠑ ", "⠈⠱ ", " ⡱ ", "⢀⡰ ", "⢄⡠ ", "⢆⡀ ", ""]),
-    );
-
-    let mut callbacks = RemoteCallbacks::new();
-
-    callbacks
-        .credentials(|_url, _username_from_url, _allowed| Cred::ssh_key_from_agent(repo.username));
-
-    callbacks.transfer_progress(|progress| {
-        if pb.length().is_none() {
-            pb.set_length(progress.total_objects() as u64);
-        }
-
-        pb.set_position(progress.received_objects() as u64);
-
-        true
-    });
+    let pb = git::progress_bar(mp, "Cloning")?;
+    let callbacks = git::remote_callbacks(&pb, repo.username);
 
     let mut fetch_opts = FetchOptions::new();
     fetch_opts.remote_callbacks(callbacks);
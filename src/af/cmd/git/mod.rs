@@ -1,6 +1,10 @@
-use anyhow::Result;
+use crate::consts::{ORIGIN, UPSTREAM};
+
+use anyhow::{Result, bail};
 use clap::Subcommand;
-use indicatif::MultiProgress;
+use git2::{BranchType, Cred, RemoteCallbacks, Repository, build::CheckoutBuilder};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use std::time::Duration;
 
 pub mod clone_project;
 
@@ -17,3 +21,96 @@ impl Git {
         }
     }
 }
+
+/// Spinner with a bar filled by [`remote_callbacks`] as objects are transferred
+pub fn progress_bar(mp: &MultiProgress, message: &'static str) -> Result<ProgressBar> {
+    let pb = mp.add(ProgressBar::no_length().with_message(message));
+    pb.enable_steady_tick(Duration::from_millis(80));
+    pb.set_style(
+        ProgressStyle::with_template(
+            "{msg:.green.bold} {spinner}[{elapsed_precise}] {wide_bar} {percent:>3}%",
+        )?
+        .tick_strings(&["⢎  ", "⠎⠁ ", "⠊⠑ ", "⠈⠱ ", " ⡱ ", "⢀⡰ ", "⢄⡠ ", "⢆⡀ ", ""]),
+    );
+
+    Ok(pb)
+}
+
+/// Callbacks authenticating with the SSH agent and reporting fetch and push
+/// progress to the bar
+pub fn remote_callbacks<'a>(pb: &'a ProgressBar, username: &'a str) -> RemoteCallbacks<'a> {
+    let mut callbacks = RemoteCallbacks::new();
+
+    callbacks.credentials(|_url, _username_from_url, _allowed| Cred::ssh_key_from_agent(username));
+
+    callbacks.transfer_progress(|progress| {
+        if pb.length().is_none() {
+            pb.set_length(progress.total_objects() as u64);
+        }
+
+        pb.set_position(progress.received_objects() as u64);
+
+        true
+    });
+
+    callbacks.push_transfer_progress(|current, total, _bytes| {
+        pb.set_length(total as u64);
+        pb.set_position(current as u64);
+    });
+
+    callbacks
+}
+
+/// Whether [`checkout_reference`] would find the branch, tag or commit
+pub fn reference_exists(repository: &Repository, reference: &str) -> bool {
+    repository.find_branch(reference, BranchType::Local).is_ok()
+        || [UPSTREAM, ORIGIN].into_iter().any(|remote| {
+            repository
+                .find_branch(&format!("{remote}/{reference}"), BranchType::Remote)
+                .is_ok()
+        })
+        || repository.revparse_single(reference).is_ok()
+}
+
+/// Checks out a branch, tag or commit
+///
+/// Local branches take precedence. A missing local branch is created from the
+/// matching remote-tracking branch, anything else is checked out detached.
+pub fn checkout_reference(repository: &Repository, reference: &str) -> Result<()> {
+    let mut checkout = CheckoutBuilder::new();
+    checkout.safe();
+
+    if let Ok(branch) = repository.find_branch(reference, BranchType::Local) {
+        let commit = branch.get().peel_to_commit()?;
+        repository.checkout_tree(commit.as_object(), Some(&mut checkout))?;
+        repository.set_head(&format!("refs/heads/{reference}"))?;
+
+        return Ok(());
+    }
+
+    for remote in [UPSTREAM, ORIGIN] {
+        let remote_branch = format!("{remote}/{reference}");
+
+        if let Ok(tracking) = repository.find_branch(&remote_branch, BranchType::Remote) {
+            let commit = tracking.get().peel_to_commit()?;
+            let mut branch = repository.branch(reference, &commit, false)?;
+            branch.set_upstream(Some(&remote_branch))?;
+
+            repository.checkout_tree(commit.as_object(), Some(&mut checkout))?;
+            repository.set_head(&format!("refs/heads/{reference}"))?;
+
+            return Ok(());
+        }
+    }
+
+    match repository.revparse_single(reference) {
+        Ok(object) => {
+            let commit = object.peel_to_commit()?;
+            repository.checkout_tree(commit.as_object(), Some(&mut checkout))?;
+            repository.set_head_detached(commit.id())?;
+
+            Ok(())
+        }
+        Err(_) => bail!("Reference {reference} not found, fetch the repository and try again"),
+    }
+}
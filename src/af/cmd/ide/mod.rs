@@ -0,0 +1,161 @@
+use crate::consts::PROJECT_CONFIG;
+use crate::discovery::Roots;
+use crate::ides::discovery;
+use crate::ides::preferences::{self, Project};
+use crate::ides::{self, Location, detect};
+use crate::state::State;
+use crate::utils;
+
+use anyhow::{Result, anyhow};
+use clap::{Subcommand, ValueHint, builder::PossibleValuesParser};
+use console::style;
+use git2::Repository;
+use std::{env, path::PathBuf};
+
+#[derive(Debug, Subcommand)]
+pub enum Ide {
+    /// Find installed IDEs (alias: f, list)
+    #[command(visible_aliases = ["f", "list"])]
+    Find {
+        /// Filter results by IDE identifiers (comma-separated)
+        #[arg(
+            long = "ide",
+            short,
+            value_name = "IDE",
+            value_delimiter = ',',
+            value_parser = PossibleValuesParser::new(ides::ids()),
+        )]
+        ids: Vec<String>,
+
+        /// Show all matching results (not just the first one per IDE)
+        #[arg(long, short)]
+        all: bool,
+
+        /// Display only the full paths to launchers
+        #[arg(long, short, conflicts_with = "json")]
+        path: bool,
+
+        /// Print results as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Open a file or directory in an IDE, optionally at a line and column (alias: o)
+    ///
+    /// Without a path, opens the current repository. The IDE pinned in the
+    /// project's .af.toml or remembered from an earlier choice is used without
+    /// asking; otherwise the choice is remembered for the project.
+    #[command(visible_aliases = ["o"])]
+    Open {
+        /// IDE to open the path with (remembered for the project)
+        #[arg(
+            long,
+            short,
+            value_parser = PossibleValuesParser::new(ides::ids()),
+        )]
+        ide: Option<String>,
+
+        /// Path to open, optionally followed by :line[:column] (e.g. src/lib.rs:42:5)
+        #[arg(value_name = "PATH[:LINE[:COLUMN]]")]
+        location: Option<Location>,
+    },
+
+    /// Forget the IDE remembered for a project
+    Forget {
+        /// Path inside the project (defaults to the current directory)
+        #[arg(value_hint = ValueHint::AnyPath)]
+        path: Option<PathBuf>,
+    },
+}
+
+impl Ide {
+    pub fn run(&self) -> Result<()> {
+        match self {
+            Ide::Find {
+                ids,
+                all,
+                path,
+                json,
+            } => {
+                let ids = match ids.is_empty() {
+                    true => ides::ids(),
+                    false => ids.iter().map(String::as_str).collect(),
+                };
+
+                let installations = discovery::find_ides(&Roots::from_env(), &ids, *all, true);
+
+                if *json {
+                    println!("{}", serde_json::to_string_pretty(&installations)?);
+                    return Ok(());
+                }
+
+                for installation in installations {
+                    if *path {
+                        println!("{}", installation.path.display());
+                    } else {
+                        println!("{installation}");
+                    }
+                }
+
+                Ok(())
+            }
+
+            Ide::Open { ide, location } => {
+                let (project, location) = match location {
+                    Some(location) => {
+                        let path = location.path.canonicalize()?;
+                        let project = Project::discover(&path);
+
+                        // Opening a directory doesn't need a separate file location
+                        let location = (!path.is_dir())
+                            .then(|| Location::new(&path, location.line, location.column));
+
+                        (project, location)
+                    }
+                    None => {
+                        let repo = Repository::open_from_env().map_err(|_| {
+                            anyhow!("Not inside a git repository, provide a path to open")
+                        })?;
+                        let root = repo
+                            .workdir()
+                            .ok_or_else(|| anyhow!("Repository has no working directory"))?;
+
+                        (Project::discover(root), None)
+                    }
+                };
+
+                let installed = discovery::installed();
+                let suggested = detect::detect().map(|detected| detected.id());
+
+                match preferences::choose(&installed, &project, ide.as_deref(), suggested)? {
+                    Some(installation) => installation.open(Some(&project.root), location.as_ref()),
+                    None => Ok(()),
+                }
+            }
+
+            Ide::Forget { path } => {
+                let path = match path {
+                    Some(path) => path.clone(),
+                    None => env::current_dir()?,
+                };
+
+                let project = Project::discover(&path);
+                let root = style(utils::format_path(&project.root)).bold();
+                let mut state = State::load()?;
+
+                if project.forget(&mut state) {
+                    state.save()?;
+                    println!("Forgot the IDE remembered for {root}");
+                } else {
+                    println!("No IDE remembered for {root}");
+                }
+
+                if let Some(pinned) = project.pinned() {
+                    println!("{PROJECT_CONFIG} still pins {}", style(pinned).bold());
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
@@ -1,4 +1,5 @@
 pub mod browser;
 pub mod dot;
 pub mod git;
+pub mod ide;
 pub mod shortcuts;
@@ -0,0 +1,399 @@
+use crate::cmd::git::{self, checkout_reference};
+use crate::cmd::shortcuts::abbreviations::gcmff::{Checkout, State};
+use crate::cmd::shortcuts::abbreviations::{GitPushRemote, get_existing_remote};
+use crate::consts::{AF, GIT, HEAD};
+
+use anyhow::{Context, Result, anyhow, bail};
+use console::style;
+use git2::{
+    Direction, ErrorCode, FetchOptions, Oid, PushOptions, Repository, Signature,
+    build::CheckoutBuilder,
+};
+use indicatif::MultiProgress;
+use std::{cell::RefCell, path::PathBuf};
+
+/// Runs `gcmff` in-process: checks out the default branch, fetches the remote
+/// and fast-forwards the branch to it, stashing uncommitted changes around it
+/// only if auto-stash is configured
+pub fn checkout_fetch_fast_forward(
+    mp: &MultiProgress,
+    repo: &mut Repository,
+    auto_stash: bool,
+) -> Result<()> {
+    let state = State::inspect(repo)?;
+    let State {
+        remote,
+        default_branch: branch,
+        ..
+    } = &state;
+
+    if state.dirty && !auto_stash {
+        bail!("The working tree has uncommitted changes, commit or stash them first");
+    }
+
+    match &state.checkout {
+        // Git won't check the branch out here, so update it where it is
+        Checkout::Worktree(path) => {
+            fetch(mp, repo, remote)?;
+            fast_forward(&Repository::open(path)?, remote, branch)
+        }
+        checkout => {
+            let stashed = state.dirty;
+
+            if stashed {
+                let signature = repo
+                    .signature()
+                    .or_else(|_| Signature::now(AF, "af@localhost"))?;
+                repo.stash_save(&signature, "af: gcmff auto-stash", None)?;
+            }
+
+            let result = (|| {
+                if *checkout == Checkout::Switch {
+                    checkout_reference(repo, branch)?;
+                    println!("Switched to {}", style(branch).bold());
+                }
+
+                fetch(mp, repo, remote)?;
+                fast_forward(repo, remote, branch)
+            })();
+
+            if stashed && let Err(err) = repo.stash_pop(0, None) {
+                let pop = anyhow!(err)
+                    .context("The stashed changes don't apply cleanly, they're kept in the stash");
+
+                return Err(match result {
+                    Ok(()) => pop,
+                    Err(err) => anyhow!("Failed to update {branch}: {err:#}\n{pop:#}"),
+                });
+            }
+
+            result
+        }
+    }
+}
+
+/// Runs `gp` in-process, checking the lease against the remote-tracking
+/// branch for `--force-with-lease`
+pub fn push(
+    mp: &MultiProgress,
+    repo: &Repository,
+    remote_priority: GitPushRemote,
+    no_verify: bool,
+    force_with_lease: bool,
+    force: bool,
+) -> Result<()> {
+    let remote_name = get_existing_remote(repo, remote_priority)
+        .ok_or_else(|| anyhow!("No valid remote found from priority list"))?;
+    let branch = current_branch(repo)?;
+    let local_ref = format!("refs/heads/{branch}");
+    let tracking_ref = format!("refs/remotes/{remote_name}/{branch}");
+    let local = repo.refname_to_id(&local_ref)?;
+
+    if !no_verify && hooks_dir(repo)?.join("pre-push").exists() {
+        eprintln!(
+            "{:>8} pre-push hook (hooks don't run with --exec)",
+            style("skip").yellow()
+        );
+    }
+
+    let mut remote = repo.find_remote(&remote_name)?;
+    let pb = git::progress_bar(mp, "Pushing")?;
+
+    let result = (|| {
+        if force_with_lease {
+            let expected = repo.refname_to_id(&tracking_ref).ok();
+            let connection = remote.connect_auth(
+                Direction::Push,
+                Some(git::remote_callbacks(&pb, GIT)),
+                None,
+            )?;
+            let actual = connection
+                .list()?
+                .iter()
+                .find(|head| head.name() == local_ref)
+                .map(|head| head.oid());
+
+            if actual != expected {
+                bail!(
+                    "Stale lease: {remote_name}/{branch} is at {} but {tracking_ref} expects {}, fetch and review the remote changes first",
+                    short_id(actual),
+                    short_id(expected),
+                );
+            }
+        }
+
+        // Set when the remote refuses the update, unlike connection failures
+        let rejection = RefCell::new(None);
+
+        let mut callbacks = git::remote_callbacks(&pb, GIT);
+        callbacks.push_update_reference(|_refname, status| {
+            *rejection.borrow_mut() = status.map(str::to_string);
+            Ok(())
+        });
+
+        let mut options = PushOptions::new();
+        options.remote_callbacks(callbacks);
+
+        let prefix = if force || force_with_lease { "+" } else { "" };
+        let pushed = remote.push(
+            &[format!("{prefix}{local_ref}:{local_ref}")],
+            Some(&mut options),
+        );
+        drop(options);
+
+        let rejection = match pushed {
+            // Checked before sending when the remote's refs are known
+            Err(err) if err.code() == ErrorCode::NotFastForward => Some(err.message().to_string()),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("Failed to push {branch} to {remote_name}"));
+            }
+            Ok(()) => rejection.into_inner(),
+        };
+
+        if let Some(message) = rejection {
+            bail!(
+                "Push of {branch} to {remote_name} was rejected ({message}), fetch and integrate the remote changes first"
+            );
+        }
+
+        Ok(())
+    })();
+
+    pb.finish_and_clear();
+    mp.remove(&pb);
+    result?;
+
+    repo.reference(&tracking_ref, local, true, "af: update by push")?;
+    println!(
+        "Pushed {} to {} ({})",
+        style(&branch).bold(),
+        style(&remote_name).bold(),
+        short_id(Some(local))
+    );
+
+    Ok(())
+}
+
+/// Directory Git runs the hooks from, shared by all worktrees unless
+/// `core.hooksPath` points elsewhere
+fn hooks_dir(repo: &Repository) -> Result<PathBuf> {
+    match repo.config()?.get_path("core.hooksPath") {
+        // Relative paths are relative to where hooks run, the worktree root
+        Ok(path) if path.is_relative() => Ok(repo.workdir().unwrap_or(repo.path()).join(path)),
+        Ok(path) => Ok(path),
+        Err(_) => Ok(repo.commondir().join("hooks")),
+    }
+}
+
+fn current_branch(repo: &Repository) -> Result<String> {
+    let head = repo.head()?;
+
+    match (head.is_branch(), head.shorthand()) {
+        (true, Some(branch)) => Ok(branch.to_string()),
+        _ => bail!("{HEAD} is not pointing to a branch"),
+    }
+}
+
+fn fetch(mp: &MultiProgress, repo: &Repository, remote_name: &str) -> Result<()> {
+    let mut remote = repo.find_remote(remote_name)?;
+    let pb = git::progress_bar(mp, "Fetching")?;
+
+    let mut options = FetchOptions::new();
+    options.remote_callbacks(git::remote_callbacks(&pb, GIT));
+
+    let result = remote.fetch(&[] as &[&str], Some(&mut options), None);
+
+    pb.finish_and_clear();
+    mp.remove(&pb);
+
+    result.with_context(|| format!("Failed to fetch {remote_name}"))
+}
+
+/// Moves the checked out branch to its remote-tracking branch if that doesn't
+/// lose any commits
+fn fast_forward(repo: &Repository, remote: &str, branch: &str) -> Result<()> {
+    let tracking = repo
+        .find_reference(&format!("refs/remotes/{remote}/{branch}"))
+        .with_context(|| format!("{remote}/{branch} not found"))?;
+    let fetched = repo.reference_to_annotated_commit(&tracking)?;
+    let (analysis, _) = repo.merge_analysis(&[&fetched])?;
+
+    if analysis.is_up_to_date() {
+        println!("{} is up to date", style(branch).bold());
+        return Ok(());
+    }
+
+    if !analysis.is_fast_forward() {
+        bail!(
+            "Not possible to fast-forward {branch} to {remote}/{branch}, the branches have diverged"
+        );
+    }
+
+    let target = repo.find_commit(fetched.id())?;
+    repo.checkout_tree(target.as_object(), Some(CheckoutBuilder::new().safe()))
+        .context("Uncommitted changes are in the way, commit or stash them first")?;
+    repo.find_reference(&format!("refs/heads/{branch}"))?
+        .set_target(
+            fetched.id(),
+            &format!("af: fast-forward {branch} to {remote}/{branch}"),
+        )?;
+
+    println!(
+        "Fast-forwarded {} to {remote}/{branch} ({})",
+        style(branch).bold(),
+        short_id(Some(fetched.id()))
+    );
+
+    Ok(())
+}
+
+/// Fast-forwards a branch which isn't checked out to its remote-tracking branch
+fn short_id(oid: Option<Oid>) -> String {
+    match oid {
+        Some(oid) => oid.to_string()[..7].to_string(),
+        None => "nothing".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::shortcuts::abbreviations::testing::{commit, remote_and_clone};
+    use indicatif::ProgressDrawTarget;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn mp() -> MultiProgress {
+        MultiProgress::with_draw_target(ProgressDrawTarget::hidden())
+    }
+
+    #[test]
+    fn checks_out_fetches_and_fast_forwards() {
+        let td = TempDir::new().unwrap();
+        let (remote, mut clone) = remote_and_clone(td.path());
+        let new = commit(&remote, "refs/heads/main", "CHANGELOG.md");
+
+        checkout_fetch_fast_forward(&mp(), &mut clone, false).unwrap();
+
+        assert_eq!(current_branch(&clone).unwrap(), "main");
+        assert_eq!(clone.refname_to_id("refs/heads/main").unwrap(), new);
+        assert!(td.path().join("clone/CHANGELOG.md").exists());
+
+        // Diverged from the remote
+        commit(&remote, "refs/heads/main", "LICENSE");
+        commit(&clone, "refs/heads/main", "local.txt");
+        clone
+            .checkout_head(Some(CheckoutBuilder::new().force()))
+            .unwrap();
+        assert!(
+            checkout_fetch_fast_forward(&mp(), &mut clone, false)
+                .unwrap_err()
+                .to_string()
+                .contains("the branches have diverged")
+        );
+
+        fs::write(td.path().join("clone/README.md"), "changed").unwrap();
+        assert_eq!(
+            checkout_fetch_fast_forward(&mp(), &mut clone, false)
+                .unwrap_err()
+                .to_string(),
+            "The working tree has uncommitted changes, commit or stash them first"
+        );
+    }
+
+    #[test]
+    fn stashes_around_uncommitted_changes() {
+        let td = TempDir::new().unwrap();
+        let (remote, mut clone) = remote_and_clone(td.path());
+        let readme = td.path().join("clone/README.md");
+        let new = commit(&remote, "refs/heads/main", "CHANGELOG.md");
+
+        // With auto-stash, the changes are stashed and popped on main
+        fs::write(&readme, "changed").unwrap();
+        let mut config = clone.config().unwrap();
+        config.set_str("user.name", "Test User").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        checkout_fetch_fast_forward(&mp(), &mut clone, true).unwrap();
+
+        assert_eq!(current_branch(&clone).unwrap(), "main");
+        assert_eq!(clone.refname_to_id("refs/heads/main").unwrap(), new);
+        assert!(td.path().join("clone/CHANGELOG.md").exists());
+        assert_eq!(fs::read_to_string(&readme).unwrap(), "changed");
+        assert!(clone.refname_to_id("refs/stash").is_err());
+    }
+
+    #[test]
+    fn finds_hooks_of_linked_worktrees() {
+        let td = TempDir::new().unwrap();
+        let (_remote, clone) = remote_and_clone(td.path());
+        let main = clone.find_branch("main", git2::BranchType::Local).unwrap();
+        let mut options = git2::WorktreeAddOptions::new();
+        options.reference(Some(main.get()));
+        clone
+            .worktree("linked", &td.path().join("linked"), Some(&options))
+            .unwrap();
+
+        let linked = Repository::open(td.path().join("linked")).unwrap();
+        assert_eq!(
+            hooks_dir(&linked).unwrap().canonicalize().unwrap(),
+            td.path().join("clone/.git/hooks").canonicalize().unwrap()
+        );
+
+        clone
+            .config()
+            .unwrap()
+            .set_str("core.hooksPath", ".githooks")
+            .unwrap();
+        assert_eq!(
+            hooks_dir(&linked).unwrap(),
+            linked.workdir().unwrap().join(".githooks")
+        );
+    }
+
+    #[test]
+    fn pushes_with_lease() {
+        let td = TempDir::new().unwrap();
+        let (remote, clone) = remote_and_clone(td.path());
+        let pushed = commit(&clone, "refs/heads/feature", "feature.txt");
+
+        push(&mp(), &clone, GitPushRemote::Origin, true, false, false).unwrap();
+        assert_eq!(remote.refname_to_id("refs/heads/feature").unwrap(), pushed);
+        assert_eq!(
+            clone.refname_to_id("refs/remotes/origin/feature").unwrap(),
+            pushed
+        );
+
+        // Someone else pushed to the branch since
+        let theirs = commit(&remote, "refs/heads/feature", "theirs.txt");
+        let amended = commit(&clone, "refs/heads/feature", "amended.txt");
+
+        assert!(
+            push(&mp(), &clone, GitPushRemote::Origin, true, false, false)
+                .unwrap_err()
+                .to_string()
+                .starts_with("Push of feature to origin was rejected")
+        );
+        assert!(
+            push(&mp(), &clone, GitPushRemote::Origin, true, true, false)
+                .unwrap_err()
+                .to_string()
+                .starts_with("Stale lease: origin/feature is at ")
+        );
+        assert_eq!(remote.refname_to_id("refs/heads/feature").unwrap(), theirs);
+
+        push(&mp(), &clone, GitPushRemote::Origin, true, false, true).unwrap();
+        assert_eq!(remote.refname_to_id("refs/heads/feature").unwrap(), amended);
+
+        // Not reaching the remote isn't a rejection
+        clone
+            .remote_set_url("origin", td.path().join("missing.git").to_str().unwrap())
+            .unwrap();
+        assert!(
+            push(&mp(), &clone, GitPushRemote::Origin, true, false, false)
+                .unwrap_err()
+                .to_string()
+                .starts_with("Failed to push feature to origin")
+        );
+    }
+}
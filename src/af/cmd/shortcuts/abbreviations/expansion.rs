@@ -0,0 +1,287 @@
+use crate::cmd::shortcuts::init::Shell;
+
+/// Command line printed by an abbreviation, rendered for the target shell
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expansion {
+    /// Program and its arguments
+    Command(Vec<String>),
+    /// Runs each part only if the previous one succeeded
+    Sequence(Vec<Expansion>),
+    /// Pipes the output of each part into the next one
+    Pipe(Vec<Expansion>),
+    /// Runs the first part, then the second one even if the first failed
+    Finally(Box<Expansion>, Box<Expansion>),
+}
+
+impl Expansion {
+    pub fn command<I, S>(args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Expansion::Command(args.into_iter().map(Into::into).collect())
+    }
+
+    pub fn render(&self, shell: Shell) -> String {
+        match self {
+            Expansion::Command(args) => args
+                .iter()
+                .map(|arg| quote(shell, arg))
+                .collect::<Vec<_>>()
+                .join(" "),
+            Expansion::Sequence(parts) => {
+                let separator = match shell {
+                    Shell::Bash | Shell::Zsh => " && ",
+                    Shell::Fish => "; and ",
+                    // Failing externals abort the rest of the pipeline
+                    Shell::Nushell => "; ",
+                };
+
+                parts
+                    .iter()
+                    .map(|part| match part {
+                        Expansion::Finally(..) => group(shell, &part.render(shell)),
+                        _ => part.render(shell),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(separator)
+            }
+            Expansion::Pipe(parts) => parts
+                .iter()
+                .map(|part| match part {
+                    Expansion::Sequence(_) | Expansion::Finally(..) => {
+                        group(shell, &part.render(shell))
+                    }
+                    _ => part.render(shell),
+                })
+                .collect::<Vec<_>>()
+                .join(" | "),
+            Expansion::Finally(body, always) => {
+                let body = body.render(shell);
+                let always = always.render(shell);
+
+                match shell {
+                    Shell::Bash | Shell::Fish | Shell::Zsh => format!("{body}; {always}"),
+                    // Failing externals would abort the rest
+                    Shell::Nushell => format!("try {{ {body} }}; {always}"),
+                }
+            }
+        }
+    }
+}
+
+/// Parts run as one part of a sequence or pipe
+fn group(shell: Shell, line: &str) -> String {
+    match shell {
+        Shell::Bash | Shell::Zsh => format!("{{ {line}; }}"),
+        Shell::Fish => format!("begin; {line}; end"),
+        Shell::Nushell => format!("do {{ {line} }}"),
+    }
+}
+
+/// Argument as a single word of the shell, quoted only if needed
+pub fn quote(shell: Shell, arg: &str) -> String {
+    let bare = |c: char| c.is_ascii_alphanumeric() || "_-./:@+,".contains(c);
+
+    match shell {
+        Shell::Bash | Shell::Zsh => {
+            // `=cmd` expands to a path in zsh
+            if !arg.is_empty() && !arg.starts_with('=') && arg.chars().all(|c| bare(c) || c == '=')
+            {
+                return arg.to_string();
+            }
+
+            format!("'{}'", arg.replace('\'', r"'\''"))
+        }
+        Shell::Fish => {
+            if !arg.is_empty() && arg.chars().all(|c| bare(c) || c == '=') {
+                return arg.to_string();
+            }
+
+            format!("'{}'", arg.replace('\\', r"\\").replace('\'', r"\'"))
+        }
+        Shell::Nushell => {
+            // Leading digits may parse as numbers, durations or file sizes
+            if arg.starts_with(|c: char| c.is_ascii_alphabetic() || "_-./".contains(c))
+                && arg.chars().all(bare)
+            {
+                return arg.to_string();
+            }
+
+            if !arg.contains('\'') {
+                return format!("'{arg}'");
+            }
+
+            let mut quoted = String::from('"');
+            for c in arg.chars() {
+                match c {
+                    '\\' => quoted.push_str(r"\\"),
+                    '"' => quoted.push_str(r#"\""#),
+                    '\n' => quoted.push_str(r"\n"),
+                    '\r' => quoted.push_str(r"\r"),
+                    '\t' => quoted.push_str(r"\t"),
+                    c => quoted.push(c),
+                }
+            }
+            quoted.push('"');
+            quoted
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::ValueEnum;
+    use proptest::prelude::*;
+    use std::{io, process::Command};
+
+    /// Arguments the shell itself passes to `printf` for the rendered command,
+    /// `None` if the shell isn't installed
+    ///
+    /// The external `printf` behaves the same from every shell, unlike the
+    /// builtins.
+    fn read_back(shell: Shell, args: &[String]) -> Option<Vec<String>> {
+        let program = match shell {
+            Shell::Bash => "bash",
+            Shell::Fish => "fish",
+            Shell::Nushell => "nu",
+            Shell::Zsh => "zsh",
+        };
+        let line = Expansion::command(
+            ["/usr/bin/printf", r"%s\0"]
+                .into_iter()
+                .map(str::to_string)
+                .chain(args.iter().cloned()),
+        )
+        .render(shell);
+
+        let output = match Command::new(program).arg("-c").arg(&line).output() {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return None,
+            output => output.unwrap(),
+        };
+        assert!(
+            output.status.success(),
+            "{shell:?}: {line}: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        Some(
+            String::from_utf8(output.stdout)
+                .unwrap()
+                .split_terminator('\0')
+                .map(str::to_string)
+                .collect(),
+        )
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn round_trips_arguments(args in prop::collection::vec("[^\\x00]{0,20}", 1..5)) {
+            for shell in Shell::value_variants() {
+                if let Some(read) = read_back(*shell, &args) {
+                    prop_assert_eq!(&read, &args, "{:?}", shell);
+                }
+            }
+        }
+
+        #[test]
+        fn keeps_plain_words_bare(arg in "[a-z][a-z0-9_./-]{0,20}") {
+            for shell in Shell::value_variants() {
+                prop_assert_eq!(quote(*shell, &arg), arg.clone());
+            }
+        }
+    }
+
+    #[test]
+    fn renders_sequences_and_pipes_per_shell() {
+        let line = Expansion::Sequence(vec![
+            Expansion::command(["git", "checkout", "feat/it's"]),
+            Expansion::Pipe(vec![
+                Expansion::command(["git", "diff", "--", "my file.rs"]),
+                Expansion::command(["pbcopy"]),
+            ]),
+        ]);
+
+        assert_eq!(
+            line.render(Shell::Zsh),
+            r"git checkout 'feat/it'\''s' && git diff -- 'my file.rs' | pbcopy"
+        );
+        assert_eq!(
+            line.render(Shell::Fish),
+            r"git checkout 'feat/it\'s'; and git diff -- 'my file.rs' | pbcopy"
+        );
+        assert_eq!(
+            line.render(Shell::Nushell),
+            r#"git checkout "feat/it's"; git diff -- 'my file.rs' | pbcopy"#
+        );
+
+        let grouped = Expansion::Pipe(vec![
+            Expansion::Sequence(vec![
+                Expansion::command(["echo", "a"]),
+                Expansion::command(["echo", "b"]),
+            ]),
+            Expansion::command(["cat"]),
+        ]);
+        assert_eq!(grouped.render(Shell::Bash), "{ echo a && echo b; } | cat");
+
+        let always = Expansion::Sequence(vec![
+            Expansion::command(["git", "stash", "push"]),
+            Expansion::Finally(
+                Box::new(Expansion::command(["git", "pull"])),
+                Box::new(Expansion::command(["git", "stash", "pop"])),
+            ),
+        ]);
+        assert_eq!(
+            always.render(Shell::Zsh),
+            "git stash push && { git pull; git stash pop; }"
+        );
+        assert_eq!(
+            always.render(Shell::Fish),
+            "git stash push; and begin; git pull; git stash pop; end"
+        );
+        assert_eq!(
+            always.render(Shell::Nushell),
+            "git stash push; do { try { git pull }; git stash pop }"
+        );
+        assert_eq!(
+            grouped.render(Shell::Fish),
+            "begin; echo a; and echo b; end | cat"
+        );
+    }
+
+    #[test]
+    fn posix_shells_read_back_quoted_arguments() {
+        let args = [
+            "plain",
+            "two words",
+            "it's",
+            "$(touch pwned)",
+            "`id`",
+            "a\\b\"c",
+            "*.rs",
+            "line\nbreak",
+            "=ls",
+            "",
+            "ünïcödé",
+        ];
+        let line = Expansion::command(args).render(Shell::Bash);
+
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(format!(
+                r#"for arg in {line}; do printf '%s\0' "$arg"; done"#
+            ))
+            .output()
+            .unwrap();
+
+        let read: Vec<_> = String::from_utf8(output.stdout)
+            .unwrap()
+            .split_terminator('\0')
+            .map(str::to_string)
+            .collect();
+        assert_eq!(read, args);
+    }
+}
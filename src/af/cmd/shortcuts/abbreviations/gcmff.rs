@@ -0,0 +1,281 @@
+use crate::cmd::shortcuts::abbreviations::expansion::Expansion;
+use crate::cmd::shortcuts::abbreviations::get_remote_and_default_branch;
+use crate::config::Config;
+use crate::consts::{
+    AUTOSTASH, CHECKOUT, FETCH, FF_ONLY, GIT, GIT_DIRECTORY, HEAD, MERGE, POP, PUSH, STASH,
+    UPSTREAM_ORIGIN_SLICE,
+};
+
+use anyhow::Result;
+use console::style;
+use git2::{Repository, StatusOptions};
+use log::debug;
+use std::path::{Path, PathBuf};
+
+/// Where the default branch is checked out relative to the current worktree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Checkout {
+    Here,
+    /// Not checked out anywhere, so it can be switched to
+    Switch,
+    /// Checked out in another worktree of the repository, which Git won't
+    /// check it out again or fetch into
+    Worktree(PathBuf),
+}
+
+/// Repository state `gcmff` adapts to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct State {
+    pub remote: String,
+    pub default_branch: String,
+    /// Branch checked out here, `None` if detached
+    pub head: Option<String>,
+    /// Tracked files have uncommitted changes
+    pub dirty: bool,
+    pub checkout: Checkout,
+}
+
+impl State {
+    pub fn inspect(repo: &Repository) -> Result<Self> {
+        let (remote, default_branch) = get_remote_and_default_branch(repo, UPSTREAM_ORIGIN_SLICE)?;
+
+        let head = repo
+            .head()
+            .ok()
+            .filter(|head| head.is_branch())
+            .and_then(|head| head.shorthand().map(str::to_string));
+
+        let dirty = !repo
+            .statuses(Some(StatusOptions::new().include_untracked(false)))?
+            .is_empty();
+
+        let checkout = if head.as_deref() == Some(default_branch.as_str()) {
+            Checkout::Here
+        } else {
+            match worktree_with_branch(repo, &default_branch)? {
+                Some(path) => Checkout::Worktree(path),
+                None => Checkout::Switch,
+            }
+        };
+
+        Ok(Self {
+            remote,
+            default_branch,
+            head,
+            dirty,
+            checkout,
+        })
+    }
+
+    /// Commands to run, and a warning if they don't end on the default branch
+    pub fn plan(&self, auto_stash: bool) -> (Expansion, Option<String>) {
+        let Self {
+            remote,
+            default_branch: branch,
+            ..
+        } = self;
+        let tracking = format!("{remote}/{branch}");
+        let fetch = Expansion::command([GIT, FETCH, remote]);
+        let merge = Expansion::command([GIT, MERGE, FF_ONLY, &tracking]);
+        let checkout = Expansion::command([GIT, CHECKOUT, branch]);
+
+        match (&self.checkout, self.dirty) {
+            (Checkout::Here, true) if auto_stash => (
+                Expansion::Sequence(vec![
+                    fetch,
+                    Expansion::command([GIT, MERGE, FF_ONLY, AUTOSTASH, &tracking]),
+                ]),
+                None,
+            ),
+            (Checkout::Here, _) => (Expansion::Sequence(vec![fetch, merge]), None),
+            (Checkout::Worktree(path), _) => {
+                let path = path.to_string_lossy();
+
+                (
+                    Expansion::Sequence(vec![
+                        fetch,
+                        Expansion::command([GIT, GIT_DIRECTORY, &path, MERGE, FF_ONLY, &tracking]),
+                    ]),
+                    Some(format!(
+                        "{branch} is checked out in {path}, fast-forwarding it there"
+                    )),
+                )
+            }
+            (Checkout::Switch, false) => (Expansion::Sequence(vec![checkout, fetch, merge]), None),
+            // Popping even if the update fails, so the changes don't stay stashed
+            (Checkout::Switch, true) if auto_stash => (
+                Expansion::Sequence(vec![
+                    Expansion::command([GIT, STASH, PUSH]),
+                    Expansion::Finally(
+                        Box::new(Expansion::Sequence(vec![checkout, fetch, merge])),
+                        Box::new(Expansion::command([GIT, STASH, POP])),
+                    ),
+                ]),
+                None,
+            ),
+            (Checkout::Switch, true) => (
+                Expansion::command([GIT, FETCH, remote, &format!("{branch}:{branch}")]),
+                Some(format!(
+                    "Uncommitted changes on {}, updating {branch} without checking it out (set shortcuts.auto-stash to stash them)",
+                    self.head.as_deref().unwrap_or(HEAD),
+                )),
+            ),
+        }
+    }
+}
+
+/// Path of another worktree which has the branch checked out
+fn worktree_with_branch(repo: &Repository, branch: &str) -> Result<Option<PathBuf>> {
+    let current = repo.workdir().map(canonical);
+    let branch_ref = format!("refs/heads/{branch}");
+
+    // The main worktree owns the common directory, linked ones are listed in it
+    let main = Repository::open(repo.commondir())?;
+    let mut others = vec![main.workdir().map(Path::to_path_buf)];
+
+    for name in main.worktrees()?.iter().flatten() {
+        others.push(Some(main.find_worktree(name)?.path().to_path_buf()));
+    }
+
+    for path in others.into_iter().flatten() {
+        if Some(canonical(&path)) == current {
+            continue;
+        }
+
+        let Ok(worktree) = Repository::open(&path) else {
+            debug!("Skipping unreadable worktree {}", path.display());
+            continue;
+        };
+
+        if worktree
+            .head()
+            .ok()
+            .and_then(|h| h.name().map(str::to_string))
+            == Some(branch_ref.clone())
+        {
+            return Ok(Some(canonical(&path)));
+        }
+    }
+
+    Ok(None)
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Whether stashing around the update is configured
+pub fn auto_stash() -> bool {
+    match Config::load() {
+        Ok(config) => config.shortcuts.auto_stash,
+        Err(err) => {
+            warn(&format!("{err:#}"));
+            false
+        }
+    }
+}
+
+pub fn warn(message: &str) {
+    eprintln!("{:>8} {message}", style("warning").yellow());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::shortcuts::abbreviations::testing::commit;
+    use crate::cmd::shortcuts::init::Shell;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn state(checkout: Checkout, dirty: bool) -> State {
+        State {
+            remote: "upstream".to_string(),
+            default_branch: "main".to_string(),
+            head: Some("feature".to_string()),
+            dirty,
+            checkout,
+        }
+    }
+
+    fn plan(state: State, auto_stash: bool) -> (String, Option<String>) {
+        let (expansion, warning) = state.plan(auto_stash);
+        (expansion.render(Shell::Zsh), warning)
+    }
+
+    #[test]
+    fn plans_for_the_repository_state() {
+        assert_eq!(
+            plan(state(Checkout::Switch, false), false),
+            (
+                "git checkout main && git fetch upstream && git merge --ff-only upstream/main"
+                    .to_string(),
+                None
+            )
+        );
+        assert_eq!(
+            plan(state(Checkout::Switch, true), true).0,
+            "git stash push && { git checkout main && git fetch upstream && git merge --ff-only upstream/main; git stash pop; }"
+        );
+
+        let (line, warning) = plan(state(Checkout::Switch, true), false);
+        assert_eq!(line, "git fetch upstream main:main");
+        assert!(
+            warning
+                .unwrap()
+                .starts_with("Uncommitted changes on feature, updating main")
+        );
+
+        assert_eq!(
+            plan(state(Checkout::Here, true), true).0,
+            "git fetch upstream && git merge --ff-only --autostash upstream/main"
+        );
+
+        let (line, warning) = plan(
+            state(Checkout::Worktree("/work/my repo".into()), true),
+            false,
+        );
+        assert_eq!(
+            line,
+            "git fetch upstream && git -C '/work/my repo' merge --ff-only upstream/main"
+        );
+        assert_eq!(
+            warning.as_deref(),
+            Some("main is checked out in /work/my repo, fast-forwarding it there")
+        );
+    }
+
+    #[test]
+    fn finds_the_default_branch_in_other_worktrees() {
+        let td = TempDir::new().unwrap();
+        let repo = Repository::init(td.path().join("main")).unwrap();
+        let oid = commit(&repo, "refs/heads/main", "README.md");
+        repo.set_head("refs/heads/main").unwrap();
+        repo.checkout_head(None).unwrap();
+        repo.remote("origin", "git@github.com:acme/af.git").unwrap();
+        repo.reference("refs/remotes/origin/main", oid, true, "test")
+            .unwrap();
+
+        let commit = repo.find_commit(oid).unwrap();
+        let feature = repo.branch("feature", &commit, false).unwrap();
+        let mut options = git2::WorktreeAddOptions::new();
+        options.reference(Some(feature.get()));
+        repo.worktree("feature", &td.path().join("feature"), Some(&options))
+            .unwrap();
+
+        // From the linked worktree, main is checked out in the main one
+        let linked = Repository::open(td.path().join("feature")).unwrap();
+        let state = State::inspect(&linked).unwrap();
+        assert_eq!(state.head.as_deref(), Some("feature"));
+        assert_eq!(
+            state.checkout,
+            Checkout::Worktree(canonical(&td.path().join("main")))
+        );
+        assert!(!state.dirty);
+
+        // From the main worktree, main is here
+        assert_eq!(State::inspect(&repo).unwrap().checkout, Checkout::Here);
+
+        fs::write(td.path().join("main/notes.txt"), "untracked").unwrap();
+        assert!(!State::inspect(&repo).unwrap().dirty);
+    }
+}
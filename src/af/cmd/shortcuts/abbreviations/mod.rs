@@ -1,16 +1,29 @@
+use crate::cmd::shortcuts::abbreviations::expansion::Expansion;
+use crate::cmd::shortcuts::abbreviations::gcmff::State;
+use crate::cmd::shortcuts::init::{self, Shell};
 use crate::consts::HEAD;
 use crate::consts::{
-    CHECKOUT, DIFF, FETCH, FF_ONLY, FORCE, FORCE_WITH_LEASE, GIT, MERGE, NO_VERIFY, ORIGIN_SLICE,
-    ORIGIN_UPSTREAM_SLICE, PBCOPY, PUSH, UPSTREAM_ORIGIN_SLICE, UPSTREAM_SLICE,
+    DIFF, FORCE, FORCE_WITH_LEASE, GIT, NO_VERIFY, ORIGIN_SLICE, ORIGIN_UPSTREAM_SLICE, PBCOPY,
+    PUSH, UPSTREAM_ORIGIN_SLICE, UPSTREAM_SLICE,
 };
-use anyhow::bail;
+use anyhow::{Result, bail};
 use clap::{Subcommand, ValueEnum};
 use git2::Repository;
+use indicatif::MultiProgress;
 use log::debug;
+use serde::Deserialize;
 use std::fmt::Debug;
 use std::{iter, slice, vec};
 
-#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub mod exec;
+pub mod expansion;
+pub mod gcmff;
+#[cfg(test)]
+mod testing;
+pub mod user;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum GitPushRemote {
     #[default]
     Origin,
@@ -35,9 +48,24 @@ impl IntoIterator for GitPushRemote {
 
 #[derive(Debug, Subcommand)]
 pub enum Abbreviation {
-    /// Expands to: git checkout <default-branch> && git fetch <remote> && git merge --ff-only <remote>/<default-branch>
+    /// Updates the default branch from the remote, adapting to the repository state
+    ///
+    /// On a clean tree, expands to: git checkout <default-branch> && git fetch <remote> && git merge --ff-only <remote>/<default-branch>
+    ///
+    /// On the default branch, the checkout is skipped. With uncommitted changes
+    /// elsewhere, the branch is updated without checking it out (git fetch
+    /// <remote> <default-branch>:<default-branch>), or with `shortcuts.auto-stash`
+    /// set, the changes are stashed and popped around the update (merged with
+    /// --autostash on the default branch). When the default branch is checked
+    /// out in another worktree, it's fast-forwarded there (git -C <worktree>
+    /// merge --ff-only <remote>/<default-branch>) with a warning.
     #[command(name = "gcmff")]
-    GitCheckoutMasterFetchFastForward,
+    GitCheckoutMasterFetchFastForward {
+        /// Run the checkout, fetch and fast-forward merge instead of printing them
+        /// (fails on uncommitted changes unless `shortcuts.auto-stash` is set)
+        #[arg(long, short)]
+        exec: bool,
+    },
 
     /// Expands to: git push <remote> <branch> [optional flags]
     #[command(name = "gp")]
@@ -57,6 +85,10 @@ pub enum Abbreviation {
         /// Push with --force flag (unsafe force push, overrides remote)
         #[arg(long, short = 'F', conflicts_with = "force_with_lease")]
         force: bool,
+
+        /// Push instead of printing the command (pre-push hooks aren't run)
+        #[arg(long, short)]
+        exec: bool,
     },
 
     /// Expands to: git diff <reference> -- <files> [optionally copy to clipboard]
@@ -74,37 +106,61 @@ pub enum Abbreviation {
         #[arg(long, short)]
         pbcopy: bool,
     },
+
+    /// Expands a user abbreviation from the `[abbreviations]` config table
+    #[command(external_subcommand)]
+    User(Vec<String>),
 }
 
 impl Abbreviation {
-    pub fn run(&self) {
+    pub fn run(&self, shell: Shell, multi: &MultiProgress) -> Result<()> {
         match self {
-            Abbreviation::GitCheckoutMasterFetchFastForward => match Repository::open_from_env() {
-                Ok(repo) => match get_remote_and_default_branch(&repo, UPSTREAM_ORIGIN_SLICE) {
-                    Ok((remote, default_branch)) => {
-                        let head = repo
-                            .head()
-                            .ok()
-                            .and_then(|h| h.shorthand().map(str::to_string))
-                            .unwrap_or_default();
-
-                        if head != default_branch {
-                            print!("{GIT} {CHECKOUT} {default_branch} && ");
-                        }
+            Abbreviation::GitCheckoutMasterFetchFastForward { exec: true } => {
+                return exec::checkout_fetch_fast_forward(
+                    multi,
+                    &mut Repository::open_from_env()?,
+                    gcmff::auto_stash(),
+                );
+            }
+            Abbreviation::GitPush {
+                remote_priority,
+                no_verify,
+                force_with_lease,
+                force,
+                exec: true,
+            } => {
+                return exec::push(
+                    multi,
+                    &Repository::open_from_env()?,
+                    *remote_priority,
+                    *no_verify,
+                    *force_with_lease,
+                    *force,
+                );
+            }
+            Abbreviation::GitCheckoutMasterFetchFastForward { .. } => {
+                match Repository::open_from_env() {
+                    Ok(repo) => match State::inspect(&repo) {
+                        Ok(state) => {
+                            let (expansion, warning) = state.plan(gcmff::auto_stash());
+
+                            if let Some(warning) = warning {
+                                gcmff::warn(&warning);
+                            }
 
-                        print!(
-                            "{GIT} {FETCH} {remote} && {GIT} {MERGE} {FF_ONLY} {remote}/{default_branch}"
-                        );
-                    }
-                    Err(err) => debug!("Failed to get remote and default branch: {err:#}"),
-                },
-                Err(err) => debug!("Failed to open repository from environment: {err:#}"),
-            },
+                            print!("{}", expansion.render(shell));
+                        }
+                        Err(err) => gcmff::warn(&format!("Can't expand gcmff: {err:#}")),
+                    },
+                    Err(err) => debug!("Failed to open repository from environment: {err:#}"),
+                }
+            }
             Abbreviation::GitPush {
                 remote_priority,
                 no_verify,
                 force_with_lease,
                 force,
+                ..
             } => match Repository::open_from_env() {
                 Ok(repo) => {
                     let found_remote = get_existing_remote(&repo, *remote_priority);
@@ -125,7 +181,8 @@ impl Abbreviation {
                                         cmd.push(FORCE);
                                     }
 
-                                    return print!("{}", cmd.join(" "));
+                                    print!("{}", Expansion::command(cmd).render(shell));
+                                    return Ok(());
                                 }
 
                                 debug!("Branch name could not be determined");
@@ -146,7 +203,7 @@ impl Abbreviation {
             } => {
                 if Repository::open_from_env().is_err() {
                     debug!("Failed to open repository from environment");
-                    return;
+                    return Ok(());
                 }
 
                 let mut cmd = vec![GIT, DIFF];
@@ -160,13 +217,18 @@ impl Abbreviation {
                     cmd.extend(files.iter().map(String::as_str));
                 }
 
+                let mut expansion = Expansion::command(cmd);
+
                 if *pbcopy {
-                    cmd.extend(vec!["|", PBCOPY]);
+                    expansion = Expansion::Pipe(vec![expansion, Expansion::command([PBCOPY])]);
                 }
 
-                print!("{}", cmd.join(" "))
+                print!("{}", expansion.render(shell))
             }
+            Abbreviation::User(args) => user::run(args, shell),
         }
+
+        Ok(())
     }
 }
 
@@ -174,15 +236,35 @@ impl Abbreviation {
 pub enum Shortcut {
     /// Group of abbreviation subcommands (alias: a, abbr, abbreviation)
     #[command(visible_aliases = ["a", "abbr", "abbreviation"])]
-    #[command(subcommand)]
-    Abbreviations(Abbreviation),
+    Abbreviations {
+        /// Shell to quote and join the expanded commands for
+        #[arg(long, short, value_enum, global = true, default_value_t)]
+        shell: Shell,
+
+        #[command(subcommand)]
+        abbreviation: Abbreviation,
+    },
+
+    /// Print shell integration expanding the abbreviations as they are typed
+    Init {
+        /// Shell to integrate with
+        #[arg(value_enum)]
+        shell: init::Shell,
+    },
 }
 
 impl Shortcut {
-    pub fn run(&self) {
+    pub fn run(&self, multi: &MultiProgress) -> Result<()> {
         match self {
             // Delegates to the selected abbreviation command
-            Shortcut::Abbreviations(cmd) => cmd.run(),
+            Shortcut::Abbreviations {
+                shell,
+                abbreviation,
+            } => abbreviation.run(*shell, multi),
+            Shortcut::Init { shell } => {
+                init::run(*shell);
+                Ok(())
+            }
         }
     }
 }
@@ -217,13 +299,13 @@ where
             let head_spec = format!("{clean_pattern}{HEAD}");
 
             // Try HEAD first
-            if let Ok((_, Some(reference))) = repo.revparse_ext(&head_spec) {
-                if let Some(ref_short) = reference.shorthand() {
-                    return Ok((
-                        remote_name.to_string(),
-                        ref_short.replace(&clean_pattern, ""),
-                    ));
-                }
+            if let Ok((_, Some(reference))) = repo.revparse_ext(&head_spec)
+                && let Some(ref_short) = reference.shorthand()
+            {
+                return Ok((
+                    remote_name.to_string(),
+                    ref_short.replace(&clean_pattern, ""),
+                ));
             }
 
             // Fallback: check for "main" and "master" branches
@@ -0,0 +1,54 @@
+//! Git repositories the abbreviation tests run against
+
+use git2::{Oid, Repository};
+use std::path::Path;
+use tempfile::TempDir;
+
+pub fn init_repo() -> (TempDir, Repository) {
+    let temp_dir = TempDir::new().unwrap();
+    let repo = Repository::init(temp_dir.path()).unwrap();
+    (temp_dir, repo)
+}
+
+/// Commits a file on top of the reference (or as the first commit)
+pub fn commit(repo: &Repository, reference: &str, file: &str) -> Oid {
+    let signature = git2::Signature::now("Test User", "test@example.com").unwrap();
+    let parent = repo
+        .find_reference(reference)
+        .and_then(|r| r.peel_to_commit())
+        .ok();
+
+    let mut tree = repo
+        .treebuilder(parent.as_ref().map(|p| p.tree().unwrap()).as_ref())
+        .unwrap();
+    tree.insert(file, repo.blob(file.as_bytes()).unwrap(), 0o100644)
+        .unwrap();
+    let tree = repo.find_tree(tree.write().unwrap()).unwrap();
+
+    repo.commit(
+        Some(reference),
+        &signature,
+        &signature,
+        file,
+        &tree,
+        &parent.iter().collect::<Vec<_>>(),
+    )
+    .unwrap()
+}
+
+/// Bare "remote" with one commit on `main` and a clone of it on `feature`
+pub fn remote_and_clone(td: &Path) -> (Repository, Repository) {
+    let remote = Repository::init_bare(td.join("remote.git")).unwrap();
+    remote.set_head("refs/heads/main").unwrap();
+    commit(&remote, "refs/heads/main", "README.md");
+
+    let clone =
+        Repository::clone(td.join("remote.git").to_str().unwrap(), td.join("clone")).unwrap();
+    {
+        let head = clone.head().unwrap().peel_to_commit().unwrap();
+        clone.branch("feature", &head, false).unwrap();
+    }
+    clone.set_head("refs/heads/feature").unwrap();
+
+    (remote, clone)
+}
@@ -0,0 +1,416 @@
+use crate::cmd::shortcuts::abbreviations::{
+    GitPushRemote, expansion, get_existing_remote, get_remote_and_default_branch,
+};
+use crate::cmd::shortcuts::init::Shell;
+use crate::config::Config;
+use crate::consts::{CURSOR_MARKER, HEAD};
+use crate::repo::Repo;
+use crate::utils;
+
+use anyhow::{Context as _, Result, anyhow, bail};
+use git2::{BranchType, Repository};
+use log::debug;
+use serde::Deserialize;
+use std::cell::RefCell;
+
+/// Abbreviation defined in the user configuration, e.g.
+///
+/// ```toml
+/// [abbreviations.grb]
+/// template = "git rebase {remote}/{default_branch}"
+/// when = ["non-default-branch"]
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct UserAbbreviation {
+    /// Expansion with placeholders in braces (`{{` and `}}` for literal braces,
+    /// `{cursor}` where the shell integration leaves the cursor)
+    pub template: String,
+    pub description: Option<String>,
+    /// Conditions which must all hold for the abbreviation to expand
+    #[serde(default)]
+    pub when: Vec<Condition>,
+    /// Remotes tried for `{remote}` and `{default_branch}` (default: upstream-first)
+    pub remote: Option<GitPushRemote>,
+}
+
+/// Repository state an abbreviation can require
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Condition {
+    /// The default branch is checked out
+    DefaultBranch,
+    /// A branch other than the default one is checked out
+    NonDefaultBranch,
+    /// The checked out branch tracks a remote branch
+    HasUpstream,
+    NoUpstream,
+    /// The worktree and index have no changes
+    Clean,
+    Dirty,
+}
+
+/// Repository the abbreviation expands in, resolving placeholders on demand
+pub struct Context {
+    repo: Option<Repository>,
+    remotes: GitPushRemote,
+}
+
+impl Context {
+    pub fn new(repo: Option<Repository>, remotes: GitPushRemote) -> Self {
+        Self { repo, remotes }
+    }
+
+    fn repo(&self) -> Result<&Repository> {
+        self.repo
+            .as_ref()
+            .ok_or_else(|| anyhow!("Not in a repository"))
+    }
+
+    fn remote(&self) -> Result<String> {
+        get_existing_remote(self.repo()?, self.remotes)
+            .ok_or_else(|| anyhow!("No remote found from priority list"))
+    }
+
+    fn default_branch(&self) -> Result<String> {
+        Ok(self.remote_and_default_branch()?.1)
+    }
+
+    /// First remote with a default branch, which can come after the first
+    /// configured remote
+    fn remote_and_default_branch(&self) -> Result<(String, String)> {
+        get_remote_and_default_branch(self.repo()?, self.remotes)
+    }
+
+    fn branch(&self) -> Result<String> {
+        let head = self.repo()?.head()?;
+
+        match (head.is_branch(), head.shorthand()) {
+            (true, Some(branch)) => Ok(branch.to_string()),
+            _ => bail!("{HEAD} is not pointing to a branch"),
+        }
+    }
+
+    fn upstream_of_branch(&self) -> Result<String> {
+        let branch = self.branch()?;
+        let upstream = self
+            .repo()?
+            .find_branch(&branch, BranchType::Local)?
+            .upstream()
+            .with_context(|| format!("Branch {branch} has no upstream"))?;
+
+        Ok(upstream.name()?.unwrap_or_default().to_string())
+    }
+
+    /// Organisation and name of the repository from the remote's URL
+    fn org_and_name(&self) -> Result<(String, String)> {
+        let remote = self.remote()?;
+        let remote = self.repo()?.find_remote(&remote)?;
+        let url = utils::convert_to_ssh(remote.url().unwrap_or_default())?;
+        let repo = Repo::parse(&url)?;
+
+        Ok((repo.org.to_string(), repo.name.to_string()))
+    }
+
+    /// Value of a placeholder
+    pub fn resolve(&self, key: &str) -> Result<String> {
+        match key {
+            "remote" => self.remote(),
+            "default_branch" => self.default_branch(),
+            "branch" => self.branch(),
+            "upstream_of_branch" => self.upstream_of_branch(),
+            "repo.org" => Ok(self.org_and_name()?.0),
+            "repo.name" => Ok(self.org_and_name()?.1),
+            _ => bail!("Unknown placeholder {{{key}}}"),
+        }
+    }
+
+    pub fn holds(&self, condition: Condition) -> Result<bool> {
+        Ok(match condition {
+            Condition::DefaultBranch => self.branch()? == self.default_branch()?,
+            Condition::NonDefaultBranch => self.branch()? != self.default_branch()?,
+            Condition::HasUpstream => self.upstream_of_branch().is_ok(),
+            Condition::NoUpstream => self.upstream_of_branch().is_err(),
+            Condition::Clean => self.is_clean()?,
+            Condition::Dirty => !self.is_clean()?,
+        })
+    }
+
+    fn is_clean(&self) -> Result<bool> {
+        let mut options = git2::StatusOptions::new();
+        options.include_untracked(false);
+
+        Ok(self.repo()?.statuses(Some(&mut options))?.is_empty())
+    }
+}
+
+impl UserAbbreviation {
+    /// Expansion in the context followed by the extra arguments, with values
+    /// quoted for the shell, `None` if a condition doesn't hold
+    pub fn expand(
+        &self,
+        context: &Context,
+        args: &[String],
+        shell: Shell,
+    ) -> Result<Option<String>> {
+        for condition in &self.when {
+            if !context.holds(*condition)? {
+                debug!("Condition {condition:?} doesn't hold");
+                return Ok(None);
+            }
+        }
+
+        // `{remote}/{default_branch}` has to name a branch of that remote
+        let paired = if placeholders(&self.template)?
+            .iter()
+            .any(|key| key == "default_branch")
+        {
+            Some(context.remote_and_default_branch()?)
+        } else {
+            None
+        };
+
+        let mut expansion = render(&self.template, |key| {
+            let value = match (key, &paired) {
+                // Kept for the shell integration to place the cursor
+                ("cursor", _) => return Ok(CURSOR_MARKER.to_string()),
+                ("remote", Some((remote, _))) => remote.clone(),
+                ("default_branch", Some((_, branch))) => branch.clone(),
+                _ => context.resolve(key)?,
+            };
+
+            Ok(expansion::quote(shell, &value))
+        })?;
+
+        for arg in args {
+            expansion.push(' ');
+            expansion.push_str(&expansion::quote(shell, arg));
+        }
+
+        Ok(Some(expansion))
+    }
+}
+
+/// Replaces `{placeholder}`s in the template with their values
+pub fn render(template: &str, resolve: impl Fn(&str) -> Result<String>) -> Result<String> {
+    let mut out = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find(['{', '}']) {
+        out.push_str(&rest[..start]);
+        let brace = &rest[start..];
+
+        if let Some(after) = brace.strip_prefix("{{") {
+            out.push('{');
+            rest = after;
+        } else if let Some(after) = brace.strip_prefix("}}") {
+            out.push('}');
+            rest = after;
+        } else if let Some(after) = brace.strip_prefix('{') {
+            let end = after
+                .find('}')
+                .ok_or_else(|| anyhow!("Unclosed placeholder in {template:?}"))?;
+
+            out.push_str(&resolve(after[..end].trim())?);
+            rest = &after[end + 1..];
+        } else {
+            bail!("Unmatched }} in {template:?}");
+        }
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Names of the placeholders in the template
+fn placeholders(template: &str) -> Result<Vec<String>> {
+    let keys = RefCell::new(vec![]);
+
+    render(template, |key| {
+        keys.borrow_mut().push(key.to_string());
+        Ok(String::new())
+    })?;
+
+    Ok(keys.into_inner())
+}
+
+/// Prints the expansion of the user abbreviation named by the first argument,
+/// or nothing if it can't be expanded here
+pub fn run(args: &[String], shell: Shell) {
+    let Some((name, args)) = args.split_first() else {
+        return;
+    };
+
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(err) => return eprintln!("{err:#}"),
+    };
+
+    let Some(abbreviation) = config.abbreviations.get(name) else {
+        return debug!("Unknown abbreviation {name}");
+    };
+
+    let repo = Repository::open_from_env()
+        .inspect_err(|err| debug!("Failed to open repository from environment: {err:#}"))
+        .ok();
+    let remotes = abbreviation.remote.unwrap_or(GitPushRemote::UpstreamFirst);
+
+    match abbreviation.expand(&Context::new(repo, remotes), args, shell) {
+        Ok(Some(expansion)) => print!("{expansion}"),
+        Ok(None) => {}
+        Err(err) => debug!("Failed to expand {name}: {err:#}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::shortcuts::abbreviations::testing::{commit, init_repo};
+    use std::collections::BTreeMap;
+    use tempfile::TempDir;
+
+    const CONFIG: &str = r#"
+        [abbreviations.grb]
+        template = "git rebase {remote}/{default_branch}"
+        when = ["non-default-branch"]
+
+        [abbreviations.gpr]
+        template = "gh pr create --repo {repo.org}/{repo.name} --head {branch}"
+        description = "Open a pull request for the branch"
+        remote = "origin"
+
+        [abbreviations.gup]
+        template = "git log {{upstream}}: {upstream_of_branch}..HEAD"
+        when = ["has-upstream", "clean"]
+    "#;
+
+    fn abbreviations() -> BTreeMap<String, UserAbbreviation> {
+        toml::from_str::<Config>(CONFIG).unwrap().abbreviations
+    }
+
+    /// Repository on `feature` with `upstream` and `origin` remotes, whose
+    /// default branch is `main`
+    fn feature_repo() -> (TempDir, Repository) {
+        let (td, repo) = init_repo();
+        let oid = commit(&repo, "refs/heads/main", "README.md");
+        let commit = repo.find_commit(oid).unwrap();
+
+        repo.remote("upstream", "https://github.com/acme/af.git")
+            .unwrap();
+        repo.remote("origin", "git@github.com:jane/af-fork.git")
+            .unwrap();
+
+        for reference in ["upstream/main", "origin/feature"] {
+            repo.reference(&format!("refs/remotes/{reference}"), oid, true, "test")
+                .unwrap();
+        }
+        repo.reference_symbolic(
+            "refs/remotes/upstream/HEAD",
+            "refs/remotes/upstream/main",
+            true,
+            "test",
+        )
+        .unwrap();
+
+        repo.branch("feature", &commit, true).unwrap();
+        repo.set_head("refs/heads/feature").unwrap();
+        repo.checkout_head(None).unwrap();
+        drop(commit);
+
+        (td, repo)
+    }
+
+    fn expand(repo: Repository, name: &str) -> Option<String> {
+        let abbreviation = &abbreviations()[name];
+        let remotes = abbreviation.remote.unwrap_or(GitPushRemote::UpstreamFirst);
+
+        abbreviation
+            .expand(&Context::new(Some(repo), remotes), &[], Shell::Zsh)
+            .unwrap()
+    }
+
+    #[test]
+    fn renders_templates() {
+        let resolve = |key: &str| match key {
+            "branch" => Ok("feature".to_string()),
+            _ => bail!("Unknown placeholder {{{key}}}"),
+        };
+
+        assert_eq!(
+            render("echo {{ {branch} }} {{}}", resolve).unwrap(),
+            "echo { feature } {}"
+        );
+        assert!(render("echo {branch", resolve).is_err());
+        assert!(render("echo }", resolve).is_err());
+        assert_eq!(
+            render("echo {nope}", resolve).unwrap_err().to_string(),
+            "Unknown placeholder {nope}"
+        );
+    }
+
+    #[test]
+    fn expands_in_repository_context() {
+        let (_td, repo) = feature_repo();
+        assert_eq!(
+            expand(repo, "grb").as_deref(),
+            Some("git rebase upstream/main")
+        );
+
+        let (_td, repo) = feature_repo();
+        assert_eq!(
+            expand(repo, "gpr").as_deref(),
+            Some("gh pr create --repo jane/af-fork --head feature")
+        );
+
+        let (_td, repo) = feature_repo();
+        repo.find_branch("feature", BranchType::Local)
+            .unwrap()
+            .set_upstream(Some("origin/feature"))
+            .unwrap();
+        assert_eq!(
+            expand(repo, "gup").as_deref(),
+            Some("git log {upstream}: origin/feature..HEAD")
+        );
+    }
+
+    #[test]
+    fn takes_the_remote_with_the_default_branch() {
+        let (_td, repo) = feature_repo();
+        repo.find_reference("refs/remotes/upstream/HEAD")
+            .unwrap()
+            .delete()
+            .unwrap();
+        repo.find_reference("refs/remotes/upstream/main")
+            .unwrap()
+            .delete()
+            .unwrap();
+        let oid = repo.refname_to_id("refs/heads/main").unwrap();
+        repo.reference("refs/remotes/origin/main", oid, true, "test")
+            .unwrap();
+
+        assert_eq!(
+            expand(repo, "grb").as_deref(),
+            Some("git rebase origin/main")
+        );
+    }
+
+    #[test]
+    fn skips_when_conditions_fail() {
+        let (_td, repo) = feature_repo();
+        repo.set_head("refs/heads/main").unwrap();
+        assert_eq!(expand(repo, "grb"), None);
+
+        // No upstream set for the branch
+        let (_td, repo) = feature_repo();
+        assert_eq!(expand(repo, "gup"), None);
+
+        let abbreviation = &abbreviations()["grb"];
+        let outside = Context::new(None, GitPushRemote::UpstreamFirst);
+        assert!(abbreviation.expand(&outside, &[], Shell::Zsh).is_err());
+
+        assert!(
+            toml::from_str::<Config>("[abbreviations.x]\ntemplate = \"x\"\nwhen = [\"sunny\"]\n")
+                .is_err()
+        );
+    }
+}
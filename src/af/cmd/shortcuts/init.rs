@@ -0,0 +1,241 @@
+use crate::cmd::shortcuts::abbreviations::Abbreviation;
+use crate::config::Config;
+use crate::consts::{AF, CURSOR_MARKER};
+
+use clap::{Command, Subcommand, ValueEnum};
+use console::style;
+
+/// Shells `af shortcuts init` can integrate with
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Fish,
+    #[value(alias = "nu")]
+    Nushell,
+    #[default]
+    Zsh,
+}
+
+/// Names of the built-in abbreviations followed by the user ones
+pub fn abbreviation_names(config: &Config) -> Vec<String> {
+    let mut names: Vec<String> = Abbreviation::augment_subcommands(Command::new(AF))
+        .get_subcommands()
+        .map(|cmd| cmd.get_name().to_string())
+        .collect();
+
+    for name in config.abbreviations.keys() {
+        if names.contains(name) {
+            eprintln!(
+                "{:>8} {name} (shadowed by a built-in)",
+                style("skip").yellow()
+            );
+        } else if !is_valid_name(name) {
+            eprintln!(
+                "{:>8} {name} (only letters, digits, `_`, `-` and `.` are allowed)",
+                style("skip").yellow()
+            );
+        } else {
+            names.push(name.clone());
+        }
+    }
+
+    names
+}
+
+/// Names are pasted unquoted into the scripts, so keep them to plain words
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+/// Script registering the abbreviations, expanding them with `af shortcuts
+/// abbreviations <name>` and typing the key as is when that prints nothing
+pub fn script(shell: Shell, names: &[String]) -> String {
+    let list = names.join(" ");
+
+    match shell {
+        Shell::Bash => format!(
+            r#"# af abbreviations, add to ~/.bashrc: eval "$({AF} shortcuts init bash)"
+__af_abbreviations=({list})
+
+# Expands the abbreviation before the cursor, succeeding only if it placed the cursor
+__af_expand() {{
+    local line=${{READLINE_LINE:0:READLINE_POINT}}
+    local word=${{line#"${{line%%[![:space:]]*}}"}}
+    local name expansion before
+
+    for name in "${{__af_abbreviations[@]}}"; do
+        [[ $name == "$word" ]] || continue
+        expansion=$(command {AF} shortcuts abbreviations --shell bash "$word")
+        [[ -n $expansion ]] || return 1
+
+        if [[ $expansion == *'{CURSOR_MARKER}'* ]]; then
+            before=${{expansion%%'{CURSOR_MARKER}'*}}
+            READLINE_LINE=$before${{expansion#*'{CURSOR_MARKER}'}}${{READLINE_LINE:READLINE_POINT}}
+            READLINE_POINT=${{#before}}
+            return 0
+        fi
+
+        READLINE_LINE=$expansion${{READLINE_LINE:READLINE_POINT}}
+        READLINE_POINT=${{#expansion}}
+        return 1
+    done
+
+    return 1
+}}
+
+__af_expand_space() {{
+    __af_expand && return
+    READLINE_LINE="${{READLINE_LINE:0:READLINE_POINT}} ${{READLINE_LINE:READLINE_POINT}}"
+    READLINE_POINT=$((READLINE_POINT + 1))
+}}
+
+bind -x '" ": __af_expand_space'
+"#
+        ),
+        Shell::Fish => format!(
+            r#"# af abbreviations, add to ~/.config/fish/config.fish: {AF} shortcuts init fish | source
+function __af_expand
+    set -l expansion (command {AF} shortcuts abbreviations --shell fish $argv[1] | string collect)
+    test -n "$expansion"; or return 1
+    printf '%s' $expansion
+end
+
+for name in {list}
+    abbr --add $name --position command --set-cursor='{CURSOR_MARKER}' --function __af_expand
+end
+"#
+        ),
+        Shell::Nushell => format!(
+            r#"# af abbreviations, add to config.nu: {AF} shortcuts init nushell | save -f ($nu.default-config-dir | path join af.nu); source af.nu
+const AF_ABBREVIATIONS = [{list}]
+
+def __af_expand [] {{
+    let line = (commandline)
+    let word = ($line | str trim --left)
+
+    if ($word in $AF_ABBREVIATIONS) and ((commandline get-cursor) == ($line | str length)) {{
+        let result = (^{AF} shortcuts abbreviations --shell nushell $word | complete)
+        let expansion = $result.stdout
+
+        if ($result.stderr | is-not-empty) {{
+            print --stderr ($result.stderr | str trim --right)
+        }}
+
+        if ($expansion | is-not-empty) {{
+            if ($expansion | str contains '{CURSOR_MARKER}') {{
+                let parts = ($expansion | split row '{CURSOR_MARKER}')
+                commandline edit --replace ($parts | str join '')
+                commandline set-cursor ($parts | first | str length)
+            }} else {{
+                commandline edit --replace $"($expansion) "
+            }}
+            return
+        }}
+    }}
+
+    commandline edit --insert ' '
+}}
+
+$env.config.keybindings = ($env.config.keybindings | append {{
+    name: af_abbreviations
+    modifier: none
+    keycode: space
+    mode: [emacs vi_insert]
+    event: {{ send: executehostcommand cmd: '__af_expand' }}
+}})
+"#
+        ),
+        Shell::Zsh => format!(
+            r#"# af abbreviations, add to ~/.zshrc: eval "$({AF} shortcuts init zsh)"
+typeset -ga __af_abbreviations=({list})
+
+# Private file collecting warnings of the expansions, removed when the shell exits
+typeset -g __af_warnings=$(mktemp "${{TMPDIR:-/tmp}}/af-abbreviations.XXXXXX")
+
+__af_cleanup() {{
+    rm -f -- $__af_warnings
+}}
+
+autoload -Uz add-zsh-hook
+add-zsh-hook zshexit __af_cleanup
+
+# Expands the abbreviation before the cursor, succeeding only if it placed the cursor
+__af_expand() {{
+    local -a words=(${{(z)LBUFFER}})
+    [[ ${{#words}} == 1 && $LBUFFER != *[[:space:]] ]] || return 1
+    (( ${{__af_abbreviations[(Ie)$words[1]]}} )) || return 1
+
+    local expansion
+    expansion=$(command {AF} shortcuts abbreviations --shell zsh "$words[1]" 2>$__af_warnings)
+
+    # Warnings would garble the prompt, show them below it instead
+    [[ -s $__af_warnings ]] && zle -M "$(<$__af_warnings)"
+    [[ -n $expansion ]] || return 1
+
+    if [[ $expansion == *'{CURSOR_MARKER}'* ]]; then
+        LBUFFER=${{expansion%%'{CURSOR_MARKER}'*}}
+        RBUFFER=${{expansion#*'{CURSOR_MARKER}'}}$RBUFFER
+        return 0
+    fi
+
+    LBUFFER=$expansion
+    return 1
+}}
+
+__af_expand_space() {{
+    __af_expand || zle self-insert
+}}
+
+zle -N __af_expand_space
+bindkey ' ' __af_expand_space
+bindkey -M isearch ' ' self-insert
+"#
+        ),
+    }
+}
+
+/// Prints the integration script, registering the built-ins even if the user
+/// configuration can't be read
+pub fn run(shell: Shell) {
+    let config = Config::load().unwrap_or_else(|err| {
+        eprintln!("{:>8} user abbreviations ({err:#})", style("skip").yellow());
+        Config::default()
+    });
+
+    print!("{}", script(shell, &abbreviation_names(&config)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registers_builtin_and_user_abbreviations() {
+        let config: Config = toml::from_str(
+            r#"
+            [abbreviations.grb]
+            template = "git rebase {remote}/{default_branch}"
+
+            [abbreviations.gp]
+            template = "git push"
+
+            [abbreviations."x; rm -rf"]
+            template = "echo"
+            "#,
+        )
+        .unwrap();
+
+        let names = abbreviation_names(&config);
+        assert_eq!(names, ["gcmff", "gp", "gd", "grb"]);
+
+        for shell in Shell::value_variants() {
+            let script = script(*shell, &names);
+            assert!(script.contains("gcmff gp gd grb"), "{shell:?}");
+            assert!(script.contains(CURSOR_MARKER), "{shell:?}");
+        }
+    }
+}
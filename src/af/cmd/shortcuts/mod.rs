@@ -1 +1,2 @@
 pub mod abbreviations;
+pub mod init;
@@ -0,0 +1,70 @@
+use crate::cmd::browser::rules::Rule;
+use crate::cmd::shortcuts::abbreviations::user::UserAbbreviation;
+use crate::consts::{PROJECT_CONFIG, USER_CONFIG};
+use crate::utils;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+/// User configuration read from `$XDG_CONFIG_HOME/af/config.toml`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub browser: BrowserConfig,
+    /// User abbreviations expanded by `af shortcuts abbreviations <name>`
+    #[serde(default)]
+    pub abbreviations: BTreeMap<String, UserAbbreviation>,
+    #[serde(default)]
+    pub shortcuts: ShortcutsConfig,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ShortcutsConfig {
+    /// Stash uncommitted changes while `gcmff` updates the default branch and
+    /// pop them afterwards
+    #[serde(default)]
+    pub auto_stash: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct BrowserConfig {
+    /// URL routing rules for `af browser open`, evaluated in order
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+}
+
+impl Config {
+    /// Loads the user configuration, falling back to defaults if it doesn't exist
+    pub fn load() -> Result<Self> {
+        read_toml(&utils::config_dir().join(USER_CONFIG))
+    }
+}
+
+/// Per-project configuration read from `.af.toml` in the repository root
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ProjectConfig {
+    /// IDE pinned for the project, takes precedence over remembered choices
+    pub ide: Option<String>,
+}
+
+impl ProjectConfig {
+    /// Loads `.af.toml` from the project root, falling back to defaults if it doesn't exist
+    pub fn load(root: &Path) -> Result<Self> {
+        read_toml(&root.join(PROJECT_CONFIG))
+    }
+}
+
+fn read_toml<T: Default + for<'de> Deserialize<'de>>(path: &Path) -> Result<T> {
+    match fs::read_to_string(path) {
+        Ok(content) => {
+            toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(T::default()),
+        Err(err) => Err(err).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
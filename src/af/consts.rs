@@ -5,8 +5,27 @@ pub const AF: &str = "af";
 
 // Env vars
 pub const XPC_SERVICE_NAME: &str = "XPC_SERVICE_NAME";
+pub const CF_BUNDLE_IDENTIFIER: &str = "__CFBundleIdentifier";
+pub const TERM_PROGRAM: &str = "TERM_PROGRAM";
+pub const TERMINAL_EMULATOR: &str = "TERMINAL_EMULATOR";
+pub const IDEA_INITIAL_DIRECTORY: &str = "IDEA_INITIAL_DIRECTORY";
+pub const INTELLIJ_COMMAND_HISTFILE: &str = "__INTELLIJ_COMMAND_HISTFILE__";
 pub const HOME: &str = "HOME";
+pub const DOTFILES_PATH: &str = "DOTFILES_PATH";
+pub const PROJECTS_PATH: &str = "PROJECTS_PATH";
+pub const SSH_AUTH_SOCK: &str = "SSH_AUTH_SOCK";
+pub const GITHUB_TOKEN: &str = "GITHUB_TOKEN";
+pub const GH_TOKEN: &str = "GH_TOKEN";
 pub const HOMEBREW_PREFIX: &str = "HOMEBREW_PREFIX";
+pub const PATH: &str = "PATH";
+pub const XDG_DATA_HOME: &str = "XDG_DATA_HOME";
+pub const XDG_DATA_DIRS: &str = "XDG_DATA_DIRS";
+pub const XDG_STATE_HOME: &str = "XDG_STATE_HOME";
+pub const XDG_CONFIG_HOME: &str = "XDG_CONFIG_HOME";
+pub const XDG_CACHE_HOME: &str = "XDG_CACHE_HOME";
+pub const USER: &str = "USER";
+pub const VISUAL: &str = "VISUAL";
+pub const EDITOR: &str = "EDITOR";
 
 // Languages
 pub const C: &str = "c";
@@ -22,18 +41,39 @@ pub const GOLAND: &str = "goland";
 pub const RUBYMINE: &str = "rubymine";
 pub const RUSTROVER: &str = "rustrover";
 pub const WEBSTORM: &str = "webstorm";
+pub const IDEA: &str = "idea";
+pub const PYCHARM: &str = "pycharm";
+pub const CODE: &str = "code";
+pub const ZED: &str = "zed";
+pub const NVIM: &str = "nvim";
+pub const VIM: &str = "vim";
 
 // Commands
 pub const WHICH: &str = "which";
 pub const GIT: &str = "git";
 pub const PBCOPY: &str = "pbcopy";
 pub const BREW: &str = "brew";
+pub const SSH_ADD: &str = "ssh-add";
+pub const SH: &str = "sh";
+pub const OPEN: &str = "open";
+pub const XDG_SETTINGS: &str = "xdg-settings";
 
 // Flags
 pub const FLAG_VERSION: &str = "--version";
 pub const FLAG_PREFIX: &str = "--prefix";
-pub const FLAG_URL: &str = "--url";
 pub const FLAG_NEW_TAB: &str = "--new-tab";
+pub const FLAG_NEW_WINDOW: &str = "--new-window";
+pub const FLAG_PRIVATE_WINDOW: &str = "--private-window";
+pub const FLAG_INCOGNITO: &str = "--incognito";
+pub const FLAG_INPRIVATE: &str = "--inprivate";
+pub const FLAG_PRIVATE: &str = "--private";
+pub const FLAG_APP: &str = "--app";
+pub const FLAG_BACKGROUND: &str = "-g";
+pub const FLAG_LIST_PUBLIC_KEYS: &str = "-L";
+pub const FLAG_MESSAGE: &str = "--message";
+pub const FLAG_APPLICATION: &str = "-a";
+pub const FLAG_PROFILE_DIRECTORY: &str = "--profile-directory";
+pub const FLAG_PROFILE: &str = "-P";
 
 // Git specific
 pub const HEAD: &str = "HEAD";
@@ -48,10 +88,15 @@ pub const FETCH: &str = "fetch";
 pub const MERGE: &str = "merge";
 pub const CHECKOUT: &str = "checkout";
 pub const DIFF: &str = "diff";
+pub const COMMIT: &str = "commit";
 pub const NO_VERIFY: &str = "--no-verify";
 pub const FORCE_WITH_LEASE: &str = "--force-with-lease";
 pub const FF_ONLY: &str = "--ff-only";
 pub const FORCE: &str = "--force";
+pub const STASH: &str = "stash";
+pub const POP: &str = "pop";
+pub const AUTOSTASH: &str = "--autostash";
+pub const GIT_DIRECTORY: &str = "-C";
 
 // Colors
 pub const GREY: Color = Color::TrueColor {
@@ -65,5 +110,32 @@ pub const MUTED_TEAL: Color = Color::TrueColor {
     b: 170,
 };
 
+// Files
+pub const PROJECT_CONFIG: &str = ".af.toml";
+pub const STATE_FILE: &str = "state.json";
+pub const USER_CONFIG: &str = "config.toml";
+pub const BROWSER_CACHE: &str = "browsers.json";
+pub const DOTFILES_MANIFEST: &str = "dotfiles.toml";
+pub const DOTFILES_DATA: &str = "machine.toml";
+pub const CHROMIUM_LOCAL_STATE: &str = "Local State";
+pub const CHROMIUM_HISTORY: &str = "History";
+pub const CHROMIUM_BOOKMARKS: &str = "Bookmarks";
+pub const CHROMIUM_SESSIONS: &str = "Sessions";
+pub const FIREFOX_PROFILES: &str = "profiles.ini";
+pub const FIREFOX_INSTALLS: &str = "installs.ini";
+pub const FIREFOX_SIGNED_IN_USER: &str = "signedInUser.json";
+pub const FIREFOX_PLACES: &str = "places.sqlite";
+pub const FIREFOX_CONTAINERS: &str = "containers.json";
+pub const FIREFOX_SESSION: &str = "sessionstore.jsonlz4";
+pub const MIMEAPPS: &str = "mimeapps.list";
+pub const LAUNCH_SERVICES: &str =
+    "Library/Preferences/com.apple.LaunchServices/com.apple.launchservices.secure.plist";
+pub const FIREFOX_SESSION_RECOVERY: &str = "sessionstore-backups/recovery.jsonlz4";
+
 // Misc
+pub const EXT_CONTAINER: &str = "ext+container:";
+pub const CURSOR_MARKER: &str = "{cursor}";
 pub const DEFAULT_HOMEBREW_PREFIX: &str = "/opt/homebrew";
+pub const TEMPLATE_EXTENSION: &str = "tmpl";
+pub const BACKUP_SUFFIX: &str = ".af-backup";
+pub const DEFAULT_XDG_DATA_DIRS: &str = "/usr/local/share:/usr/share";
@@ -0,0 +1,149 @@
+use crate::consts::{
+    DEFAULT_XDG_DATA_DIRS, FLAG_VERSION, HOME, PATH, XDG_DATA_DIRS, XDG_DATA_HOME,
+};
+
+use anyhow::{Result, bail};
+use log::trace;
+use std::{
+    env,
+    io::Read,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Time an executable gets to print its version before it's killed
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Directories scanned when looking for IDE and browser installations
+#[derive(Debug, Clone, Default)]
+pub struct Roots {
+    pub home: PathBuf,
+    /// Directories from `$PATH`
+    pub path: Vec<PathBuf>,
+    /// XDG data directories (`$XDG_DATA_HOME` followed by `$XDG_DATA_DIRS`)
+    pub data_dirs: Vec<PathBuf>,
+    /// Directories containing macOS application bundles
+    pub applications: Vec<PathBuf>,
+    /// Snap root (usually `/snap`)
+    pub snap: PathBuf,
+    /// Flatpak installation roots (system-wide and per-user)
+    pub flatpak: Vec<PathBuf>,
+}
+
+impl Roots {
+    pub fn from_env() -> Self {
+        let home = PathBuf::from(env::var(HOME).unwrap_or_default());
+
+        let path = env::var_os(PATH)
+            .map(|p| env::split_paths(&p).collect())
+            .unwrap_or_default();
+
+        let data_home = env::var(XDG_DATA_HOME)
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| home.join(".local/share"));
+
+        let data_dirs = env::var(XDG_DATA_DIRS)
+            .ok()
+            .filter(|dirs| !dirs.is_empty())
+            .unwrap_or(DEFAULT_XDG_DATA_DIRS.to_string());
+
+        Self {
+            data_dirs: std::iter::once(data_home.clone())
+                .chain(env::split_paths(&data_dirs))
+                .collect(),
+            applications: vec!["/Applications".into(), home.join("Applications")],
+            snap: "/snap".into(),
+            flatpak: vec!["/var/lib/flatpak".into(), data_home.join("flatpak")],
+            path,
+            home,
+        }
+    }
+}
+
+/// Runs `<path> --version`, killing it if it doesn't finish in time
+pub fn probe(path: &Path, timeout: Duration) -> Result<String> {
+    let mut child = Command::new(path)
+        .arg(FLAG_VERSION)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    let started = Instant::now();
+
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+
+        if started.elapsed() > timeout {
+            child.kill()?;
+            child.wait()?;
+            bail!("timed out after {}s", timeout.as_secs_f32());
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    let mut stdout = String::new();
+    if let Some(mut pipe) = child.stdout.take() {
+        pipe.read_to_string(&mut stdout)?;
+    }
+
+    trace!("{} {FLAG_VERSION}: {}", path.display(), stdout.trim());
+
+    if !status.success() {
+        bail!("non-zero exit code: {}", status.code().unwrap_or(-1));
+    }
+
+    Ok(stdout.trim().to_string())
+}
+
+#[cfg(test)]
+pub mod testing {
+    use super::Roots;
+    use std::{fs, os::unix::fs::PermissionsExt, path::Path};
+    use tempfile::TempDir;
+
+    pub fn write_executable(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    /// Roots inside the temporary directory
+    pub fn roots(td: &TempDir) -> Roots {
+        let root = td.path();
+
+        Roots {
+            home: root.join("home"),
+            path: vec![root.join("bin")],
+            data_dirs: vec![root.join("share")],
+            applications: vec![root.join("Applications")],
+            snap: root.join("snap"),
+            flatpak: vec![root.join("flatpak")],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::write_executable;
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn kills_slow_probes() {
+        let td = TempDir::new().unwrap();
+        let slow = td.path().join("slow");
+        write_executable(&slow, "#!/bin/sh\nsleep 10\n");
+
+        let started = Instant::now();
+        let err = probe(&slow, Duration::from_millis(100)).unwrap_err();
+
+        assert_eq!(err.to_string(), "timed out after 0.1s");
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
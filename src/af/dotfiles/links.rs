@@ -0,0 +1,387 @@
+use crate::consts::BACKUP_SUFFIX;
+
+use anyhow::{Context, Result};
+use std::{
+    ffi::OsString,
+    fmt::{Display, Formatter},
+    fs, io,
+    os::unix::fs::symlink,
+    path::{Component, Path, PathBuf},
+};
+
+/// Symlink from a target (e.g. `~/.zshrc`) to a source in the dotfiles repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    pub source: PathBuf,
+    pub target: PathBuf,
+}
+
+/// State of a link's target
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// Target is a symlink to the source
+    Linked,
+    /// Target doesn't exist
+    Missing,
+    /// Target is a symlink to a path that doesn't exist
+    Broken,
+    /// Target is a symlink to somewhere other than the source
+    Foreign,
+    /// Target is a regular file or directory
+    Conflict,
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Status::Linked => "linked",
+            Status::Missing => "missing",
+            Status::Broken => "broken",
+            Status::Foreign => "foreign",
+            Status::Conflict => "conflict",
+        };
+
+        f.pad(label)
+    }
+}
+
+/// What to do with targets that exist but aren't links to their sources
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnConflict {
+    /// Leave them untouched
+    #[default]
+    Skip,
+    /// Move them aside (e.g. `~/.zshrc.af-backup`)
+    Backup,
+    /// Move them into the dotfiles repository, replacing the source
+    Adopt,
+}
+
+/// Change needed to link a target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Already linked
+    Keep,
+    /// Create the symlink
+    Create,
+    /// Replace a broken symlink
+    Replace,
+    /// Move the target to the backup path, then link
+    Backup(PathBuf),
+    /// Move the target over the source, then link
+    Adopt,
+    /// Leave the target untouched
+    Skip(&'static str),
+}
+
+impl Action {
+    pub fn verb(&self) -> &'static str {
+        match self {
+            Action::Keep => "ok",
+            Action::Create => "link",
+            Action::Replace => "relink",
+            Action::Backup(_) => "backup",
+            Action::Adopt => "adopt",
+            Action::Skip(_) => "skip",
+        }
+    }
+}
+
+impl Link {
+    pub fn new(source: impl Into<PathBuf>, target: impl Into<PathBuf>) -> Self {
+        Self {
+            source: source.into(),
+            target: target.into(),
+        }
+    }
+
+    pub fn status(&self) -> Status {
+        let Ok(metadata) = fs::symlink_metadata(&self.target) else {
+            return Status::Missing;
+        };
+
+        if !metadata.is_symlink() {
+            return Status::Conflict;
+        }
+
+        let Some(destination) = self.destination() else {
+            return Status::Broken;
+        };
+
+        if !destination.exists() {
+            return Status::Broken;
+        }
+
+        match (
+            fs::canonicalize(&destination),
+            fs::canonicalize(&self.source),
+        ) {
+            (Ok(destination), Ok(source)) if destination == source => Status::Linked,
+            _ => Status::Foreign,
+        }
+    }
+
+    /// Path the target symlink points to, resolved against the target's directory
+    pub fn destination(&self) -> Option<PathBuf> {
+        let destination = fs::read_link(&self.target).ok()?;
+        let parent = self.target.parent()?;
+
+        Some(normalize(&parent.join(destination)))
+    }
+
+    /// Returns true if the target is a symlink pointing inside `root`
+    pub fn points_into(&self, root: &Path) -> bool {
+        self.destination().is_some_and(|destination| {
+            let destination = fs::canonicalize(&destination).unwrap_or(destination);
+            destination.starts_with(root)
+        })
+    }
+
+    pub fn plan(&self, on_conflict: OnConflict) -> Action {
+        let status = self.status();
+        let source_exists = fs::symlink_metadata(&self.source).is_ok();
+
+        match status {
+            Status::Linked => Action::Keep,
+            Status::Conflict if on_conflict == OnConflict::Adopt => Action::Adopt,
+            _ if !source_exists => Action::Skip("source doesn't exist"),
+            Status::Missing => Action::Create,
+            Status::Broken => Action::Replace,
+            Status::Foreign | Status::Conflict => match on_conflict {
+                OnConflict::Skip => Action::Skip("target exists"),
+                OnConflict::Backup => Action::Backup(backup_path(&self.target)),
+                OnConflict::Adopt => Action::Skip("only files and directories can be adopted"),
+            },
+        }
+    }
+
+    pub fn apply(&self, action: &Action) -> Result<()> {
+        match action {
+            Action::Keep | Action::Skip(_) => return Ok(()),
+            Action::Create => {}
+            Action::Replace => fs::remove_file(&self.target)?,
+            Action::Backup(backup) => fs::rename(&self.target, backup).with_context(|| {
+                format!(
+                    "Failed to back up {} to {}",
+                    self.target.display(),
+                    backup.display()
+                )
+            })?,
+            Action::Adopt => self.adopt()?,
+        }
+
+        if let Some(parent) = self.target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        symlink(&self.source, &self.target)
+            .with_context(|| format!("Failed to link {}", self.target.display()))
+    }
+
+    /// Removes the target if it's a symlink into `root`, returns false otherwise
+    pub fn unlink(&self, root: &Path, dry_run: bool) -> Result<bool> {
+        if !self.points_into(root) {
+            return Ok(false);
+        }
+
+        if !dry_run {
+            fs::remove_file(&self.target)
+                .with_context(|| format!("Failed to remove {}", self.target.display()))?;
+        }
+
+        Ok(true)
+    }
+
+    fn adopt(&self) -> Result<()> {
+        match fs::symlink_metadata(&self.source) {
+            Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(&self.source)?,
+            Ok(_) => fs::remove_file(&self.source)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+
+        if let Some(parent) = self.source.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::rename(&self.target, &self.source)
+            .or_else(|_| {
+                fs::copy(&self.target, &self.source).and_then(|_| fs::remove_file(&self.target))
+            })
+            .with_context(|| {
+                format!(
+                    "Failed to move {} to {}",
+                    self.target.display(),
+                    self.source.display()
+                )
+            })
+    }
+
+    /// Formats the link as `target -> source` with the source relative to `root`
+    pub fn describe(&self, root: &Path) -> String {
+        super::describe(root, &self.source, &self.target)
+    }
+}
+
+/// First free `<target>.af-backup[.N]` path
+fn backup_path(target: &Path) -> PathBuf {
+    let with_suffix = |suffix: String| {
+        let mut path = OsString::from(target.as_os_str());
+        path.push(suffix);
+        PathBuf::from(path)
+    };
+
+    (0..)
+        .map(|n| match n {
+            0 => with_suffix(BACKUP_SUFFIX.to_string()),
+            n => with_suffix(format!("{BACKUP_SUFFIX}.{n}")),
+        })
+        .find(|path| fs::symlink_metadata(path).is_err())
+        .unwrap()
+}
+
+/// Lexically resolves `.` and `..` components (the path may not exist)
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    struct Fixture {
+        _td: TempDir,
+        root: PathBuf,
+        home: PathBuf,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let td = TempDir::new().unwrap();
+            let path = fs::canonicalize(td.path()).unwrap();
+            let (root, home) = (path.join("dotfiles"), path.join("home"));
+
+            fs::create_dir_all(&root).unwrap();
+            fs::create_dir_all(&home).unwrap();
+
+            Self {
+                _td: td,
+                root,
+                home,
+            }
+        }
+
+        fn link(&self, name: &str) -> Link {
+            let source = self.root.join(name);
+            fs::write(&source, name).unwrap();
+
+            Link::new(source, self.home.join(format!(".{name}")))
+        }
+    }
+
+    #[test]
+    fn reports_status() {
+        let fx = Fixture::new();
+        let link = fx.link("zshrc");
+
+        assert_eq!(link.status(), Status::Missing);
+
+        symlink(&link.source, &link.target).unwrap();
+        assert_eq!(link.status(), Status::Linked);
+
+        fs::remove_file(&link.target).unwrap();
+        symlink(fx.home.join("nowhere"), &link.target).unwrap();
+        assert_eq!(link.status(), Status::Broken);
+
+        fs::remove_file(&link.target).unwrap();
+        fs::write(fx.home.join("other"), "").unwrap();
+        symlink("other", &link.target).unwrap();
+        assert_eq!(link.status(), Status::Foreign);
+        assert!(!link.points_into(&fx.root));
+
+        fs::remove_file(&link.target).unwrap();
+        fs::write(&link.target, "").unwrap();
+        assert_eq!(link.status(), Status::Conflict);
+    }
+
+    #[test]
+    fn links_missing_and_nested_targets() {
+        let fx = Fixture::new();
+        let link = Link::new(
+            fx.link("gitconfig").source,
+            fx.home.join(".config/git/config"),
+        );
+
+        let action = link.plan(OnConflict::Skip);
+        assert_eq!(action, Action::Create);
+
+        link.apply(&action).unwrap();
+        assert_eq!(link.status(), Status::Linked);
+        assert_eq!(link.plan(OnConflict::Skip), Action::Keep);
+    }
+
+    #[test]
+    fn resolves_conflicts() {
+        let fx = Fixture::new();
+        let link = fx.link("vimrc");
+        fs::write(&link.target, "local").unwrap();
+
+        assert_eq!(link.plan(OnConflict::Skip), Action::Skip("target exists"));
+
+        let backup = fx.home.join(".vimrc.af-backup");
+        fs::write(&backup, "older").unwrap();
+
+        let action = link.plan(OnConflict::Backup);
+        assert_eq!(action, Action::Backup(fx.home.join(".vimrc.af-backup.1")));
+
+        link.apply(&action).unwrap();
+        assert_eq!(link.status(), Status::Linked);
+        assert_eq!(
+            fs::read_to_string(fx.home.join(".vimrc.af-backup.1")).unwrap(),
+            "local"
+        );
+
+        // Adopting moves the local file into the repository
+        fs::remove_file(&link.target).unwrap();
+        fs::write(&link.target, "adopted").unwrap();
+
+        let action = link.plan(OnConflict::Adopt);
+        assert_eq!(action, Action::Adopt);
+
+        link.apply(&action).unwrap();
+        assert_eq!(link.status(), Status::Linked);
+        assert_eq!(fs::read_to_string(&link.source).unwrap(), "adopted");
+    }
+
+    #[test]
+    fn unlinks_only_links_into_root() {
+        let fx = Fixture::new();
+        let (ours, foreign) = (fx.link("bashrc"), fx.link("inputrc"));
+
+        ours.apply(&Action::Create).unwrap();
+        fs::write(fx.home.join("inputrc"), "").unwrap();
+        symlink(fx.home.join("inputrc"), &foreign.target).unwrap();
+
+        assert!(ours.unlink(&fx.root, true).unwrap());
+        assert_eq!(ours.status(), Status::Linked);
+
+        assert!(ours.unlink(&fx.root, false).unwrap());
+        assert_eq!(ours.status(), Status::Missing);
+
+        assert!(!foreign.unlink(&fx.root, false).unwrap());
+        assert_eq!(foreign.status(), Status::Foreign);
+    }
+}
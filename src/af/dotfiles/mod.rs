@@ -0,0 +1,276 @@
+pub mod links;
+pub mod templates;
+
+use crate::consts::{DOTFILES_MANIFEST, TEMPLATE_EXTENSION};
+use crate::dotfiles::links::Link;
+use crate::dotfiles::templates::Template;
+use crate::utils;
+
+use anyhow::{Context, Result, bail};
+use console::style;
+use serde::Deserialize;
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Links declared in `dotfiles.toml` in the root of the dotfiles repository
+///
+/// Sources ending with `.tmpl` are templates, rendered to their targets instead
+/// of being linked (package templates drop the extension from the target).
+///
+/// ```toml
+/// # Stow-style package directories, their files are linked into $HOME
+/// packages = ["git", "zsh"]
+///
+/// [[links]]
+/// source = "starship/starship.toml"
+/// target = "~/.config/starship.toml"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    #[serde(default)]
+    pub packages: Vec<PathBuf>,
+
+    #[serde(default)]
+    pub links: Vec<Entry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Entry {
+    /// Path relative to the dotfiles root
+    pub source: PathBuf,
+    /// Absolute path, or relative to (or starting with `~` for) the home directory
+    pub target: PathBuf,
+}
+
+impl Manifest {
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = root.join(DOTFILES_MANIFEST);
+
+        match fs::read_to_string(&path) {
+            Ok(content) => toml::from_str(&content)
+                .with_context(|| format!("Failed to parse {}", path.display())),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                bail!("No {DOTFILES_MANIFEST} found in {}", root.display())
+            }
+            Err(err) => Err(err).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+}
+
+/// Dotfiles repository with the home directory its links are created in
+#[derive(Debug)]
+pub struct Dotfiles {
+    pub root: PathBuf,
+    pub home: PathBuf,
+    pub manifest: Manifest,
+}
+
+impl Dotfiles {
+    pub fn load(root: &Path, home: &Path) -> Result<Self> {
+        let root = fs::canonicalize(root)
+            .with_context(|| format!("Dotfiles directory not found: {}", root.display()))?;
+        let manifest = Manifest::load(&root)?;
+
+        Ok(Self {
+            root,
+            home: home.to_path_buf(),
+            manifest,
+        })
+    }
+
+    /// Files to symlink, sorted by target
+    pub fn links(&self) -> Result<Vec<Link>> {
+        let links = self.resolve()?;
+
+        Ok(links
+            .into_iter()
+            .filter(|link| !is_template(&link.source))
+            .collect())
+    }
+
+    /// Templates to render, sorted by target
+    pub fn templates(&self) -> Result<Vec<Template>> {
+        let links = self.resolve()?;
+
+        Ok(links
+            .into_iter()
+            .filter(|link| is_template(&link.source))
+            .map(|link| Template::new(link.source, link.target))
+            .collect())
+    }
+
+    /// Resolves packages and explicit entries into source/target pairs
+    fn resolve(&self) -> Result<Vec<Link>> {
+        let mut links = vec![];
+
+        for package in &self.manifest.packages {
+            let dir = self.root.join(package);
+
+            if !dir.is_dir() {
+                bail!("Package directory not found: {}", dir.display());
+            }
+
+            for file in package_files(&dir)? {
+                let mut target = self.home.join(file.strip_prefix(&dir)?);
+
+                if is_template(&file) {
+                    target.set_extension("");
+                }
+
+                links.push(Link::new(&file, target));
+            }
+        }
+
+        for entry in &self.manifest.links {
+            links.push(Link::new(
+                self.root.join(&entry.source),
+                self.expand(&entry.target),
+            ));
+        }
+
+        links.sort_by(|a, b| a.target.cmp(&b.target));
+
+        let mut targets = HashSet::new();
+        if let Some(link) = links.iter().find(|link| !targets.insert(&link.target)) {
+            bail!("Multiple sources are linked to {}", link.target.display());
+        }
+
+        Ok(links)
+    }
+
+    fn expand(&self, target: &Path) -> PathBuf {
+        match target.strip_prefix("~") {
+            Ok(rest) => self.home.join(rest),
+            Err(_) => self.home.join(target),
+        }
+    }
+}
+
+/// Formats a source/target pair as `target -> source` with the source relative to `root`
+fn describe(root: &Path, source: &Path, target: &Path) -> String {
+    let source = source.strip_prefix(root).unwrap_or(source);
+
+    format!(
+        "{} {} {}",
+        style(utils::format_path(target)).bold(),
+        style("->").dim(),
+        source.display()
+    )
+}
+
+fn is_template(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == TEMPLATE_EXTENSION)
+}
+
+/// Lists files (and symlinks) in a package directory, descending into subdirectories
+fn package_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = vec![];
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if entry.file_type()?.is_dir() {
+            files.extend(package_files(&path)?);
+        } else {
+            files.push(path);
+        }
+    }
+
+    files.sort();
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn resolves_packages_and_entries() {
+        let (repo, home) = (TempDir::new().unwrap(), TempDir::new().unwrap());
+        let root = repo.path();
+
+        fs::create_dir_all(root.join("zsh/.config/zsh")).unwrap();
+        fs::write(root.join("zsh/.zshrc"), "").unwrap();
+        fs::write(root.join("zsh/.config/zsh/aliases.zsh"), "").unwrap();
+        fs::write(root.join("zsh/.zprofile.tmpl"), "").unwrap();
+        fs::write(
+            root.join(DOTFILES_MANIFEST),
+            r#"
+                packages = ["zsh"]
+
+                [[links]]
+                source = "starship.toml"
+                target = "~/.config/starship.toml"
+
+                [[links]]
+                source = "hosts"
+                target = "/etc/hosts"
+            "#,
+        )
+        .unwrap();
+
+        let dotfiles = Dotfiles::load(root, home.path()).unwrap();
+        let root = &dotfiles.root;
+        let links: Vec<_> = dotfiles
+            .links()
+            .unwrap()
+            .into_iter()
+            .map(|link| (link.source, link.target))
+            .collect();
+
+        assert_eq!(
+            links,
+            vec![
+                (root.join("hosts"), PathBuf::from("/etc/hosts")),
+                (
+                    root.join("starship.toml"),
+                    home.path().join(".config/starship.toml")
+                ),
+                (
+                    root.join("zsh/.config/zsh/aliases.zsh"),
+                    home.path().join(".config/zsh/aliases.zsh")
+                ),
+                (root.join("zsh/.zshrc"), home.path().join(".zshrc")),
+            ]
+        );
+
+        assert_eq!(
+            dotfiles.templates().unwrap(),
+            vec![Template::new(
+                root.join("zsh/.zprofile.tmpl"),
+                home.path().join(".zprofile")
+            )]
+        );
+    }
+
+    #[test]
+    fn rejects_duplicate_targets() {
+        let (repo, home) = (TempDir::new().unwrap(), TempDir::new().unwrap());
+
+        fs::write(
+            repo.path().join(DOTFILES_MANIFEST),
+            r#"
+                [[links]]
+                source = "a"
+                target = "~/.rc"
+
+                [[links]]
+                source = "b"
+                target = ".rc"
+            "#,
+        )
+        .unwrap();
+
+        let dotfiles = Dotfiles::load(repo.path(), home.path()).unwrap();
+        assert!(dotfiles.links().is_err());
+    }
+}
@@ -0,0 +1,319 @@
+use crate::consts::{DOTFILES_DATA, USER};
+use crate::state::State;
+use crate::utils;
+
+use anyhow::{Context, Result, anyhow, bail};
+use git2::{ObjectType, Oid};
+use std::{
+    collections::BTreeMap,
+    env,
+    fmt::{Display, Formatter},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Variables available in templates as `{{ name }}`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Variables(BTreeMap<String, String>);
+
+impl Variables {
+    /// Built-ins overridden by the machine's data file
+    /// (`$XDG_CONFIG_HOME/af/machine.toml` unless another file is given)
+    pub fn load(data: Option<&Path>, home: &Path) -> Result<Self> {
+        let mut variables = Self::builtin(home);
+
+        let path = match data {
+            Some(path) => path.to_path_buf(),
+            None => utils::config_dir().join(DOTFILES_DATA),
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(content) => variables
+                .extend_from_toml(&content)
+                .with_context(|| format!("Failed to parse {}", path.display()))?,
+            // The default data file is optional
+            Err(err) if err.kind() == io::ErrorKind::NotFound && data.is_none() => {}
+            Err(err) => {
+                return Err(err).with_context(|| format!("Failed to read {}", path.display()));
+            }
+        }
+
+        Ok(variables)
+    }
+
+    /// `hostname`, `os`, `arch`, `user` and `home`
+    pub fn builtin(home: &Path) -> Self {
+        let hostname = hostname::get()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        Self(BTreeMap::from([
+            ("hostname".to_string(), hostname),
+            ("os".to_string(), env::consts::OS.to_string()),
+            ("arch".to_string(), env::consts::ARCH.to_string()),
+            ("user".to_string(), env::var(USER).unwrap_or_default()),
+            ("home".to_string(), home.display().to_string()),
+        ]))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(String::as_str)
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.0.insert(name.into(), value.into());
+    }
+
+    /// Adds values from a TOML document, nested tables become dotted names (e.g. `git.email`)
+    pub fn extend_from_toml(&mut self, content: &str) -> Result<()> {
+        let table: toml::Table = toml::from_str(content)?;
+        self.extend_from_table("", &table)
+    }
+
+    fn extend_from_table(&mut self, prefix: &str, table: &toml::Table) -> Result<()> {
+        for (key, value) in table {
+            let name = format!("{prefix}{key}");
+
+            match value {
+                toml::Value::String(value) => self.insert(name, value.as_str()),
+                toml::Value::Table(table) => self.extend_from_table(&format!("{name}."), table)?,
+                toml::Value::Array(_) => bail!("Unsupported array value for {name}"),
+                value => self.insert(name, value.to_string()),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Replaces `{{ name }}` placeholders with variable values
+pub fn render(template: &str, variables: &Variables) -> Result<String> {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+
+        let line = template[..template.len() - rest.len() + start]
+            .matches('\n')
+            .count()
+            + 1;
+        let Some(end) = rest[start..].find("}}") else {
+            bail!("Unclosed placeholder on line {line}");
+        };
+
+        let name = rest[start + 2..start + end].trim();
+
+        if !is_valid_name(name) {
+            bail!("Invalid placeholder {{{{{name}}}}} on line {line}");
+        }
+
+        let value = variables
+            .get(name)
+            .ok_or_else(|| anyhow!("Undefined variable {name} on line {line}"))?;
+
+        output.push_str(value);
+        rest = &rest[start + end + 2..];
+    }
+
+    output.push_str(rest);
+
+    Ok(output)
+}
+
+fn is_valid_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+/// Template in the dotfiles repository rendered to a target (e.g. `~/.gitconfig`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    pub source: PathBuf,
+    pub target: PathBuf,
+}
+
+/// State of a template's target compared to its rendered output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// Target matches the rendered output
+    Rendered,
+    /// Target doesn't exist
+    Missing,
+    /// Target is unchanged since the last render, but the output differs
+    Outdated,
+    /// Target was edited after the last render
+    Modified,
+    /// Target exists but wasn't rendered by af
+    Unmanaged,
+}
+
+impl Status {
+    /// Returns true if writing the output could lose local changes
+    pub fn is_conflict(&self) -> bool {
+        matches!(self, Status::Modified | Status::Unmanaged)
+    }
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Status::Rendered => "rendered",
+            Status::Missing => "missing",
+            Status::Outdated => "outdated",
+            Status::Modified => "modified",
+            Status::Unmanaged => "unmanaged",
+        };
+
+        f.pad(label)
+    }
+}
+
+impl Template {
+    pub fn new(source: impl Into<PathBuf>, target: impl Into<PathBuf>) -> Self {
+        Self {
+            source: source.into(),
+            target: target.into(),
+        }
+    }
+
+    pub fn render(&self, variables: &Variables) -> Result<String> {
+        let template = fs::read_to_string(&self.source)
+            .with_context(|| format!("Failed to read {}", self.source.display()))?;
+
+        render(&template, variables).with_context(|| format!("In {}", self.source.display()))
+    }
+
+    /// Formats the template as `target -> source` with the source relative to `root`
+    pub fn describe(&self, root: &Path) -> String {
+        super::describe(root, &self.source, &self.target)
+    }
+
+    /// Current content of the target, `None` if it doesn't exist
+    pub fn deployed(&self) -> Result<Option<String>> {
+        match fs::symlink_metadata(&self.target) {
+            Ok(metadata) if metadata.is_symlink() => {
+                bail!("{} is a symlink", self.target.display())
+            }
+            Ok(_) => Ok(Some(fs::read_to_string(&self.target)?)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn status(&self, rendered: &str, state: &State) -> Status {
+        let deployed = match self.deployed() {
+            Ok(Some(deployed)) => deployed,
+            Ok(None) => return Status::Missing,
+            // Symlinks (e.g. left over from linking) and unreadable files
+            Err(_) => return Status::Unmanaged,
+        };
+
+        if deployed == rendered {
+            return Status::Rendered;
+        }
+
+        match state.dotfiles.rendered.get(&self.target) {
+            Some(hash) if *hash == blob_hash(&deployed) => Status::Outdated,
+            Some(_) => Status::Modified,
+            None => Status::Unmanaged,
+        }
+    }
+
+    /// Writes the rendered output, replacing whatever is at the target
+    pub fn write(&self, rendered: &str, state: &mut State) -> Result<()> {
+        if fs::symlink_metadata(&self.target).is_ok_and(|m| m.is_symlink()) {
+            fs::remove_file(&self.target)?;
+        }
+
+        if let Some(parent) = self.target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(&self.target, rendered)
+            .with_context(|| format!("Failed to write {}", self.target.display()))?;
+
+        self.record(rendered, state);
+
+        Ok(())
+    }
+
+    /// Remembers the output as the last render of the target
+    pub fn record(&self, rendered: &str, state: &mut State) {
+        state
+            .dotfiles
+            .rendered
+            .insert(self.target.clone(), blob_hash(rendered));
+    }
+}
+
+fn blob_hash(content: &str) -> String {
+    Oid::hash_object(ObjectType::Blob, content.as_bytes())
+        .map(|oid| oid.to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn variables() -> Variables {
+        let mut variables = Variables::default();
+        variables
+            .extend_from_toml(
+                "name = \"Jane\"\nwork = true\n\n[git]\nemail = \"jane@example.com\"\n",
+            )
+            .unwrap();
+        variables
+    }
+
+    #[test]
+    fn renders_placeholders() {
+        let rendered = render(
+            "[user]\n\tname = {{name}}\n\temail = {{ git.email }}\n# work: {{ work }}\n",
+            &variables(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            rendered,
+            "[user]\n\tname = Jane\n\temail = jane@example.com\n# work: true\n"
+        );
+    }
+
+    #[test]
+    fn reports_template_errors_with_lines() {
+        let err = render("ok\n{{ missing }}\n", &variables()).unwrap_err();
+        assert_eq!(err.to_string(), "Undefined variable missing on line 2");
+
+        let err = render("{{ name", &variables()).unwrap_err();
+        assert_eq!(err.to_string(), "Unclosed placeholder on line 1");
+
+        assert!(render("{{ $(rm) }}", &variables()).is_err());
+    }
+
+    #[test]
+    fn detects_local_edits() {
+        let td = TempDir::new().unwrap();
+        let template = Template::new(
+            td.path().join("gitconfig.tmpl"),
+            td.path().join(".gitconfig"),
+        );
+        let mut state = State::default();
+
+        assert_eq!(template.status("v1", &state), Status::Missing);
+
+        template.write("v1", &mut state).unwrap();
+        assert_eq!(template.status("v1", &state), Status::Rendered);
+        assert_eq!(template.status("v2", &state), Status::Outdated);
+
+        fs::write(&template.target, "edited").unwrap();
+        assert_eq!(template.status("v2", &state), Status::Modified);
+        assert!(template.status("v2", &state).is_conflict());
+
+        assert_eq!(template.status("v2", &State::default()), Status::Unmanaged);
+    }
+}
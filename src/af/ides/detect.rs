@@ -0,0 +1,409 @@
+use crate::consts::*;
+use crate::ides::{self, Family, Ide};
+
+use log::{debug, trace};
+use regex::Regex;
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+/// How much a detection result can be trusted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+/// The hint that led to a detection result
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    /// macOS `XPC_SERVICE_NAME` of the hosting application
+    XpcServiceName,
+    /// macOS `__CFBundleIdentifier` of the hosting application
+    BundleIdentifier,
+    /// `TERM_PROGRAM` set by VS Code and Zed terminals
+    TermProgram,
+    /// Variables set by the JetBrains terminal (`TERMINAL_EMULATOR`, `IDEA_INITIAL_DIRECTORY`, ...)
+    JetBrainsTerminal,
+    /// An IDE process among the ancestors of the current process
+    ParentProcess,
+}
+
+/// IDE (or editor with a terminal) `af` is currently running in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DetectedIde {
+    pub ide: &'static Ide,
+    pub confidence: Confidence,
+    pub signal: Signal,
+}
+
+impl DetectedIde {
+    pub fn id(&self) -> &'static str {
+        self.ide.id
+    }
+}
+
+/// Detects the hosting IDE from environment variables and the process tree
+#[derive(Debug, Clone)]
+pub struct Detector {
+    env: HashMap<String, String>,
+    proc: PathBuf,
+    pid: u32,
+}
+
+impl Detector {
+    pub fn new(env: HashMap<String, String>, proc: impl Into<PathBuf>, pid: u32) -> Self {
+        Self {
+            env,
+            proc: proc.into(),
+            pid,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(env::vars().collect(), "/proc", process::id())
+    }
+
+    /// Returns the most confident detection result
+    pub fn detect(&self) -> Option<DetectedIde> {
+        let mut found = self.detect_all();
+        found.sort_by_key(|d| std::cmp::Reverse(d.confidence));
+
+        debug!("Detected IDEs: {:?}", found);
+
+        found.into_iter().next()
+    }
+
+    /// Returns detection results of every signal, in the order they were checked
+    pub fn detect_all(&self) -> Vec<DetectedIde> {
+        let jetbrains_terminal = self.in_jetbrains_terminal();
+        let parents = self.parent_processes();
+
+        let mut found = vec![];
+
+        found.extend(
+            self.var(XPC_SERVICE_NAME)
+                .and_then(from_xpc_service_name)
+                .map(|ide| detected(ide, Confidence::High, Signal::XpcServiceName)),
+        );
+
+        found.extend(
+            self.var(CF_BUNDLE_IDENTIFIER)
+                .and_then(from_bundle_identifier)
+                .map(|ide| detected(ide, Confidence::High, Signal::BundleIdentifier)),
+        );
+
+        found.extend(
+            self.var(TERM_PROGRAM)
+                .and_then(from_term_program)
+                .map(|ide| detected(ide, Confidence::High, Signal::TermProgram)),
+        );
+
+        if jetbrains_terminal {
+            found.extend(
+                self.var(INTELLIJ_COMMAND_HISTFILE)
+                    .and_then(from_jetbrains_path)
+                    .map(|ide| detected(ide, Confidence::Medium, Signal::JetBrainsTerminal)),
+            );
+        }
+
+        // A JetBrains terminal confirms a JetBrains IDE found among the parents,
+        // while a vim ancestor most likely just ran a shell command
+        found.extend(parents.into_iter().map(|ide| {
+            let confidence = match ide.family {
+                Family::JetBrains if jetbrains_terminal => Confidence::High,
+                Family::Vim => Confidence::Low,
+                _ => Confidence::Medium,
+            };
+
+            detected(ide, confidence, Signal::ParentProcess)
+        }));
+
+        found
+    }
+
+    fn var(&self, key: &str) -> Option<&str> {
+        self.env
+            .get(key)
+            .map(String::as_str)
+            .filter(|v| !v.is_empty())
+    }
+
+    fn in_jetbrains_terminal(&self) -> bool {
+        self.var(TERMINAL_EMULATOR)
+            .is_some_and(|v| v.starts_with("JetBrains"))
+            || self.var(IDEA_INITIAL_DIRECTORY).is_some()
+    }
+
+    /// Walks the parent-process chain in `/proc` and returns known IDEs, nearest first
+    fn parent_processes(&self) -> Vec<&'static Ide> {
+        let mut found = vec![];
+        let mut pid = self.pid;
+
+        // Bounded to survive loops in malformed fixtures
+        for _ in 0..64 {
+            let Some(ppid) = parent_pid(&self.proc, pid) else {
+                break;
+            };
+
+            if ppid <= 1 {
+                break;
+            }
+
+            let names = process_names(&self.proc, ppid);
+            trace!("Parent process {ppid}: {names:?}");
+
+            if let Some(ide) = names.iter().find_map(|name| from_process_name(name))
+                && !found.contains(&ide)
+            {
+                found.push(ide);
+            }
+
+            pid = ppid;
+        }
+
+        found
+    }
+}
+
+/// Convenience wrapper detecting the IDE from the real environment
+pub fn detect() -> Option<DetectedIde> {
+    Detector::from_env().detect()
+}
+
+fn detected(ide: &'static Ide, confidence: Confidence, signal: Signal) -> DetectedIde {
+    DetectedIde {
+        ide,
+        confidence,
+        signal,
+    }
+}
+
+/// Maps product names used by JetBrains in bundle ids and paths (e.g. `intellij`) to the registry
+fn from_jetbrains_product(product: &str) -> Option<&'static Ide> {
+    let product = product.to_lowercase();
+
+    let id = match product.as_str() {
+        p if p.starts_with("intellij") || p.starts_with("idea") => IDEA,
+        p if p.starts_with("pycharm") => PYCHARM,
+        p => p,
+    };
+
+    ides::find(id).filter(|ide| ide.family == Family::JetBrains)
+}
+
+fn from_xpc_service_name(value: &str) -> Option<&'static Ide> {
+    let re =
+        Regex::new(r"application\.(com\.jetbrains\.\w+|com\.microsoft\.VSCode\w*|dev\.zed\.Zed)")
+            .ok()?;
+    let captures = re.captures(value)?;
+
+    from_bundle_identifier(&captures[1])
+}
+
+fn from_bundle_identifier(value: &str) -> Option<&'static Ide> {
+    if let Some(product) = value.strip_prefix("com.jetbrains.") {
+        return from_jetbrains_product(product.split(['-', '.']).next()?);
+    }
+
+    match value {
+        v if v.starts_with("com.microsoft.VSCode") => ides::find(CODE),
+        v if v.starts_with("dev.zed.Zed") => ides::find(ZED),
+        _ => None,
+    }
+}
+
+fn from_term_program(value: &str) -> Option<&'static Ide> {
+    match value.to_lowercase().as_str() {
+        "vscode" => ides::find(CODE),
+        "zed" => ides::find(ZED),
+        _ => None,
+    }
+}
+
+/// Extracts the product from JetBrains config/cache paths (e.g. `.../JetBrains/GoLand2024.3/...`)
+fn from_jetbrains_path(value: &str) -> Option<&'static Ide> {
+    let re = Regex::new(r"JetBrains/([A-Za-z]+)\d").ok()?;
+
+    from_jetbrains_product(&re.captures(value)?[1])
+}
+
+fn from_process_name(name: &str) -> Option<&'static Ide> {
+    let name = name.trim_end_matches(".sh");
+
+    match name {
+        "zed-editor" | "zeditor" => return ides::find(ZED),
+        "code-insiders" => return ides::find(CODE),
+        _ => {}
+    }
+
+    ides::registry()
+        .iter()
+        .find(|ide| ide.id == name || ide.bins.contains(&name))
+}
+
+/// Reads the parent pid from `/proc/<pid>/stat`
+fn parent_pid(proc: &Path, pid: u32) -> Option<u32> {
+    let stat = fs::read_to_string(proc.join(pid.to_string()).join("stat")).ok()?;
+
+    // The command name may contain spaces and parentheses, so skip past the last ')'
+    stat.rsplit_once(')')?
+        .1
+        .split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()
+}
+
+/// Reads candidate names of a process from `/proc/<pid>/comm` and the first `cmdline` argument
+fn process_names(proc: &Path, pid: u32) -> Vec<String> {
+    let dir = proc.join(pid.to_string());
+    let mut names = vec![];
+
+    if let Ok(comm) = fs::read_to_string(dir.join("comm")) {
+        names.push(comm.trim().to_string());
+    }
+
+    if let Ok(cmdline) = fs::read(dir.join("cmdline"))
+        && let Some(arg0) = cmdline.split(|b| *b == 0).next()
+    {
+        let arg0 = String::from_utf8_lossy(arg0);
+
+        if let Some(name) = Path::new(arg0.as_ref()).file_name() {
+            names.push(name.to_string_lossy().to_string());
+        }
+    }
+
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn env(vars: &[(&str, &str)]) -> HashMap<String, String> {
+        vars.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    /// Creates `/proc`-like fixtures from `(pid, ppid, comm, cmdline)` entries
+    fn proc(processes: &[(u32, u32, &str, &str)]) -> TempDir {
+        let td = TempDir::new().unwrap();
+
+        for (pid, ppid, comm, cmdline) in processes {
+            let dir = td.path().join(pid.to_string());
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(
+                dir.join("stat"),
+                format!("{pid} ({comm}) S {ppid} 1 1 0 -1"),
+            )
+            .unwrap();
+            fs::write(dir.join("comm"), format!("{comm}\n")).unwrap();
+            fs::write(dir.join("cmdline"), cmdline.replace(' ', "\0")).unwrap();
+        }
+
+        td
+    }
+
+    #[test]
+    fn detects_from_xpc_service_name() {
+        let detector = Detector::new(
+            env(&[(
+                XPC_SERVICE_NAME,
+                "application.com.jetbrains.intellij.12345.67890",
+            )]),
+            "/nonexistent",
+            1000,
+        );
+
+        let detected = detector.detect().unwrap();
+        assert_eq!(detected.id(), IDEA);
+        assert_eq!(detected.confidence, Confidence::High);
+        assert_eq!(detected.signal, Signal::XpcServiceName);
+    }
+
+    #[test]
+    fn detects_from_bundle_identifier_and_term_program() {
+        let bundle = Detector::new(
+            env(&[(CF_BUNDLE_IDENTIFIER, "com.jetbrains.rustrover-EAP")]),
+            "/nonexistent",
+            1000,
+        );
+        assert_eq!(bundle.detect().unwrap().id(), RUSTROVER);
+
+        let vscode = Detector::new(env(&[(TERM_PROGRAM, "vscode")]), "/nonexistent", 1000);
+        assert_eq!(vscode.detect().unwrap().id(), CODE);
+
+        let zed = Detector::new(env(&[(TERM_PROGRAM, "zed")]), "/nonexistent", 1000);
+        assert_eq!(zed.detect().unwrap().id(), ZED);
+
+        let apple = Detector::new(env(&[(TERM_PROGRAM, "Apple_Terminal")]), "/nonexistent", 1);
+        assert_eq!(apple.detect(), None);
+    }
+
+    #[test]
+    fn detects_jetbrains_terminal_from_history_path() {
+        let detector = Detector::new(
+            env(&[
+                (TERMINAL_EMULATOR, "JetBrains-JediTerm"),
+                (
+                    INTELLIJ_COMMAND_HISTFILE,
+                    "/home/u/.cache/JetBrains/GoLand2024.3/terminal/history/af-history",
+                ),
+            ]),
+            "/nonexistent",
+            1000,
+        );
+
+        let detected = detector.detect().unwrap();
+        assert_eq!(detected.id(), GOLAND);
+        assert_eq!(detected.confidence, Confidence::Medium);
+        assert_eq!(detected.signal, Signal::JetBrainsTerminal);
+    }
+
+    #[test]
+    fn detects_parent_process_on_linux() {
+        let proc = proc(&[
+            (300, 200, "af", "/usr/bin/af dot"),
+            (200, 100, "zsh", "-zsh"),
+            (100, 1, "clion", "/opt/clion/bin/clion"),
+        ]);
+
+        let plain = Detector::new(env(&[]), proc.path(), 300);
+        let detected = plain.detect().unwrap();
+        assert_eq!(detected.id(), CLION);
+        assert_eq!(detected.confidence, Confidence::Medium);
+        assert_eq!(detected.signal, Signal::ParentProcess);
+
+        let terminal = Detector::new(
+            env(&[(IDEA_INITIAL_DIRECTORY, "/home/u/project")]),
+            proc.path(),
+            300,
+        );
+        assert_eq!(terminal.detect().unwrap().confidence, Confidence::High);
+    }
+
+    #[test]
+    fn prefers_nearest_parent_and_reads_cmdline() {
+        let proc = proc(&[
+            (30, 20, "bash", "bash"),
+            (20, 10, "electron", "/usr/share/code/code --type=renderer"),
+            (10, 1, "goland", "/opt/goland/bin/goland"),
+        ]);
+
+        let detector = Detector::new(env(&[]), proc.path(), 30);
+        let all = detector.detect_all();
+
+        assert_eq!(
+            all.iter().map(DetectedIde::id).collect::<Vec<_>>(),
+            [CODE, GOLAND]
+        );
+        assert_eq!(detector.detect().unwrap().id(), CODE);
+    }
+}
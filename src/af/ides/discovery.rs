@@ -0,0 +1,601 @@
+use crate::consts::*;
+use crate::discovery::{self, PROBE_TIMEOUT, Roots};
+use crate::ides::{self, Family, Ide, Location};
+use crate::utils;
+
+use anyhow::bail;
+use glob::glob;
+use log::{debug, trace};
+use rayon::prelude::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize, Serializer};
+use std::{
+    collections::BTreeSet,
+    fmt,
+    fmt::{Display, Formatter},
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Release channel of an IDE installation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    #[default]
+    Release,
+    Eap,
+    Preview,
+    Insiders,
+    Nightly,
+}
+
+impl Channel {
+    /// Guesses the channel from a version suffix, bundle name or launcher path
+    fn guess(s: &str) -> Option<Channel> {
+        let s = s.to_lowercase();
+
+        [
+            ("insiders", Channel::Insiders),
+            ("preview", Channel::Preview),
+            ("nightly", Channel::Nightly),
+            ("eap", Channel::Eap),
+        ]
+        .into_iter()
+        .find(|(needle, _)| s.split(['-', '_', ' ', '.']).any(|token| token == *needle))
+        .map(|(_, channel)| channel)
+    }
+
+    /// Guesses the channel from the name of the `.app` bundle the launcher is
+    /// in, or else of the launcher itself
+    fn guess_from_path(path: &Path) -> Option<Channel> {
+        let launcher = path
+            .ancestors()
+            .find(|dir| dir.extension().is_some_and(|ext| ext == "app"))
+            .unwrap_or(path);
+
+        Channel::guess(&launcher.file_name()?.to_string_lossy())
+    }
+}
+
+impl Display for Channel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Channel::Release => "release",
+            Channel::Eap => "eap",
+            Channel::Preview => "preview",
+            Channel::Insiders => "insiders",
+            Channel::Nightly => "nightly",
+        };
+
+        write!(f, "{s}")
+    }
+}
+
+/// Where an IDE installation was discovered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Source {
+    Path,
+    ToolboxScript,
+    Applications,
+    ToolboxApp,
+    Desktop,
+    Snap,
+    Flatpak,
+}
+
+/// An IDE installation found on this machine
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Installation {
+    #[serde(rename = "id", serialize_with = "serialize_ide_id")]
+    pub ide: &'static Ide,
+    pub name: String,
+    pub version: Option<String>,
+    pub channel: Channel,
+    pub path: PathBuf,
+    pub source: Source,
+}
+
+fn serialize_ide_id<S: Serializer>(ide: &&'static Ide, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(ide.id)
+}
+
+impl Display for Installation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<name_max$} {:<version_max$} {:<channel_max$} {}",
+            self.name,
+            self.version.as_deref().unwrap_or("unknown"),
+            self.channel,
+            self.path.display(),
+            name_max = ides::registry()
+                .iter()
+                .map(|ide| ide.name.len())
+                .max()
+                .unwrap_or_default(),
+            version_max = 13,
+            channel_max = 8,
+        )
+    }
+}
+
+impl Installation {
+    pub fn id(&self) -> &'static str {
+        self.ide.id
+    }
+
+    /// Opens the project and/or file location with this installation
+    ///
+    /// Terminal editors inherit the standard streams and block until they exit.
+    pub fn open(&self, project: Option<&Path>, location: Option<&Location>) -> anyhow::Result<()> {
+        let args = self.ide.open_args(project, location);
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+        debug!("Opening with {}: {:?}", self.path.display(), args);
+
+        if self.ide.family == Family::Vim {
+            let status = Command::new(&self.path).args(&args).status()?;
+
+            if !status.success() {
+                bail!("{} exited with {status}", self.ide.id);
+            }
+
+            return Ok(());
+        }
+
+        utils::run_command(&self.path.to_string_lossy(), &args)?;
+
+        Ok(())
+    }
+}
+
+/// JetBrains Toolbox data directories on macOS and Linux
+fn toolbox_dirs(roots: &Roots) -> Vec<PathBuf> {
+    vec![
+        roots
+            .home
+            .join("Library/Application Support/JetBrains/Toolbox"),
+        roots.home.join(".local/share/JetBrains/Toolbox"),
+    ]
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProductInfo {
+    version: Option<String>,
+    version_suffix: Option<String>,
+}
+
+struct Candidate(&'static Ide, PathBuf, Source);
+
+/// Entry point to find installed IDEs, filtered by registry identifiers
+///
+/// Installations are returned in registry order. Unless `all` is set only the
+/// first installation of each IDE is returned. Unless `probe` is set, launchers
+/// aren't run to read versions missing from the installation metadata.
+pub fn find_ides(roots: &Roots, ids: &[&str], all: bool, probe: bool) -> Vec<Installation> {
+    let ides: Vec<&'static Ide> = ides::registry()
+        .iter()
+        .filter(|ide| ids.contains(&ide.id))
+        .collect();
+
+    let mut candidates = vec![];
+
+    candidates.extend(find_on_path(roots, &ides));
+    candidates.extend(find_in_toolbox_scripts(roots, &ides));
+    candidates.extend(find_in_applications(roots, &ides));
+    candidates.extend(find_in_toolbox_apps(roots, &ides));
+    candidates.extend(find_in_desktop_files(roots, &ides));
+    candidates.extend(find_in_snaps(roots, &ides));
+    candidates.extend(find_in_flatpaks(roots, &ides));
+
+    // The same launcher is often reachable through several sources (e.g. a
+    // `PATH` symlink to a Toolbox script), so deduplicate on the resolved path
+    let mut seen_paths = BTreeSet::new();
+    candidates.retain(|Candidate(_, path, _)| {
+        seen_paths.insert(fs::canonicalize(path).unwrap_or_else(|_| path.clone()))
+    });
+
+    candidates.sort_by_key(|Candidate(ide, _, source)| {
+        let position = ides.iter().position(|i| i.id == ide.id);
+        (position, *source)
+    });
+
+    if !all {
+        let mut seen_ids = BTreeSet::new();
+        candidates.retain(|Candidate(ide, _, _)| seen_ids.insert(ide.id));
+    }
+
+    candidates
+        .into_par_iter()
+        .map(|Candidate(ide, path, source)| installation(roots, ide, path, source, probe))
+        .collect()
+}
+
+/// Finds all installed IDEs known to the registry, without probing versions
+pub fn installed() -> Vec<Installation> {
+    find_ides(&Roots::from_env(), &ides::ids(), false, false)
+}
+
+fn installation(
+    roots: &Roots,
+    ide: &'static Ide,
+    path: PathBuf,
+    source: Source,
+    probe: bool,
+) -> Installation {
+    let (version, suffix) = match ide.family {
+        Family::JetBrains => product_info(roots, ide, &path, source)
+            .map(|info| (info.version, info.version_suffix))
+            .unwrap_or_default(),
+        _ if source == Source::Flatpak || !probe => (None, None),
+        _ => (probe_version(&path), None),
+    };
+
+    let channel = suffix
+        .as_deref()
+        .and_then(Channel::guess)
+        .or_else(|| Channel::guess_from_path(&path))
+        .unwrap_or_default();
+
+    trace!(
+        "{} {source:?} {}: {version:?} ({channel})",
+        ide.id,
+        path.display()
+    );
+
+    Installation {
+        ide,
+        name: ide.name.to_string(),
+        version,
+        channel,
+        path,
+        source,
+    }
+}
+
+/// Reads `product-info.json` of a JetBrains installation without starting the IDE
+fn product_info(roots: &Roots, ide: &Ide, path: &Path, source: Source) -> Option<ProductInfo> {
+    let mut starts = vec![path.to_path_buf()];
+
+    if let Ok(resolved) = fs::canonicalize(path) {
+        starts.push(resolved);
+    }
+
+    // Toolbox scripts and some PATH launchers are small shell wrappers that
+    // `exec` the real launcher, so follow the first absolute path in them
+    if let Some(target) = script_target(path) {
+        starts.push(target);
+    }
+
+    if source == Source::Snap
+        && let Some(snap) = ide.snap
+    {
+        starts.push(roots.snap.join(snap).join("current/bin"));
+    }
+
+    starts
+        .iter()
+        .flat_map(|start| start.ancestors().take(5))
+        .flat_map(|dir| {
+            [
+                dir.join("product-info.json"),
+                dir.join("Resources/product-info.json"),
+            ]
+        })
+        .find(|candidate| candidate.is_file())
+        .and_then(|file| fs::read_to_string(file).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+}
+
+fn script_target(path: &Path) -> Option<PathBuf> {
+    let metadata = fs::metadata(path).ok()?;
+
+    // Real launchers are binaries, wrapper scripts are tiny
+    if metadata.len() > 64 * 1024 {
+        return None;
+    }
+
+    let content = fs::read_to_string(path).ok()?;
+    let re = Regex::new(r#""(/[^"]+)""#).ok()?;
+
+    re.captures_iter(&content)
+        .map(|c| PathBuf::from(&c[1]))
+        .find(|p| p.exists())
+}
+
+/// Runs `<launcher> --version` and returns the first version-looking token of the first line
+fn probe_version(path: &Path) -> Option<String> {
+    let output = discovery::probe(path, PROBE_TIMEOUT)
+        .inspect_err(|err| debug!("{} {FLAG_VERSION} failed: {err:#}", path.display()))
+        .ok()?;
+
+    output
+        .lines()
+        .next()?
+        .split_whitespace()
+        .map(|token| token.trim_start_matches('v'))
+        .find(|token| token.starts_with(|c: char| c.is_ascii_digit()))
+        .map(str::to_string)
+}
+
+/// Look for launcher executables on `$PATH`
+fn find_on_path(roots: &Roots, ides: &[&'static Ide]) -> Vec<Candidate> {
+    ides.iter()
+        .flat_map(|&ide| {
+            ide.bins.iter().flat_map(move |bin| {
+                roots
+                    .path
+                    .iter()
+                    .map(move |dir| dir.join(bin))
+                    .filter(|path| utils::is_executable(path))
+                    .map(move |path| Candidate(ide, path, Source::Path))
+            })
+        })
+        .collect()
+}
+
+/// Look for shell scripts generated by JetBrains Toolbox
+fn find_in_toolbox_scripts(roots: &Roots, ides: &[&'static Ide]) -> Vec<Candidate> {
+    let dirs = toolbox_dirs(roots);
+
+    ides.iter()
+        .filter(|ide| ide.family == Family::JetBrains)
+        .flat_map(|&ide| {
+            dirs.iter()
+                .map(move |dir| dir.join("scripts").join(ide.id))
+                .filter(|path| utils::is_executable(path))
+                .map(move |path| Candidate(ide, path, Source::ToolboxScript))
+        })
+        .collect()
+}
+
+/// Look in `/Applications` and `~/Applications` for known `.app` bundles
+fn find_in_applications(roots: &Roots, ides: &[&'static Ide]) -> Vec<Candidate> {
+    ides.iter()
+        .flat_map(|&ide| {
+            ide.apps.iter().flat_map(move |app| {
+                roots
+                    .applications
+                    .iter()
+                    .map(move |dir| dir.join(app).join(ide.app_bin))
+                    .filter(|path| path.exists())
+                    .map(move |path| Candidate(ide, path, Source::Applications))
+            })
+        })
+        .collect()
+}
+
+/// Look for IDEs installed by JetBrains Toolbox (both the current and the legacy `ch-*` layout)
+fn find_in_toolbox_apps(roots: &Roots, ides: &[&'static Ide]) -> Vec<Candidate> {
+    let dirs = toolbox_dirs(roots);
+
+    ides.iter()
+        .filter(|ide| ide.family == Family::JetBrains)
+        .flat_map(|&ide| {
+            let patterns = dirs.iter().flat_map(|dir| {
+                let apps = dir.join("apps");
+                let linux = ide
+                    .bins
+                    .iter()
+                    .filter(|bin| bin.ends_with(".sh"))
+                    .flat_map(|bin| {
+                        [
+                            apps.join("*/bin").join(bin),
+                            apps.join("*/ch-*/*/bin").join(bin),
+                        ]
+                    })
+                    .collect::<Vec<_>>();
+                let macos = ide
+                    .apps
+                    .iter()
+                    .flat_map(|app| {
+                        [
+                            apps.join("*").join(app).join(ide.app_bin),
+                            apps.join("*/ch-*/*").join(app).join(ide.app_bin),
+                        ]
+                    })
+                    .collect::<Vec<_>>();
+
+                linux.into_iter().chain(macos)
+            });
+
+            patterns
+                .flat_map(|pattern| glob(&pattern.to_string_lossy()).into_iter().flatten())
+                .flatten()
+                .map(|path| Candidate(ide, path, Source::ToolboxApp))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Look for Linux `.desktop` entries and take the launcher from their `Exec=` line
+fn find_in_desktop_files(roots: &Roots, ides: &[&'static Ide]) -> Vec<Candidate> {
+    ides.iter()
+        .flat_map(|&ide| {
+            ide.desktop_ids.iter().flat_map(move |id| {
+                roots
+                    .data_dirs
+                    .iter()
+                    .map(move |dir| dir.join("applications").join(format!("{id}.desktop")))
+                    .filter_map(|file| desktop_exec(&file))
+                    .filter_map(|exec| resolve_exec(roots, &exec))
+                    .map(move |path| Candidate(ide, path, Source::Desktop))
+            })
+        })
+        .collect()
+}
+
+/// Extracts the executable from the `Exec=` key of a `.desktop` file
+pub fn desktop_exec(file: &Path) -> Option<String> {
+    let content = fs::read_to_string(file).ok()?;
+    let mut in_entry = false;
+
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_entry = line == "[Desktop Entry]";
+            continue;
+        }
+
+        if let Some(exec) = line.strip_prefix("Exec=").filter(|_| in_entry) {
+            let exec = exec.trim();
+
+            return match exec.strip_prefix('"') {
+                Some(quoted) => quoted.split('"').next().map(str::to_string),
+                None => exec.split_whitespace().next().map(str::to_string),
+            };
+        }
+    }
+
+    None
+}
+
+/// Resolves an `Exec=` value to an executable, looking it up on `PATH` if it isn't absolute
+pub fn resolve_exec(roots: &Roots, exec: &str) -> Option<PathBuf> {
+    let path = Path::new(exec);
+
+    if path.is_absolute() {
+        return utils::is_executable(path).then(|| path.to_path_buf());
+    }
+
+    roots
+        .path
+        .iter()
+        .map(|dir| dir.join(exec))
+        .find(|path| utils::is_executable(path))
+}
+
+/// Look for snap launchers in `/snap/bin`
+fn find_in_snaps(roots: &Roots, ides: &[&'static Ide]) -> Vec<Candidate> {
+    ides.iter()
+        .filter_map(|&ide| {
+            let path = roots.snap.join("bin").join(ide.snap?);
+            path.exists().then_some(Candidate(ide, path, Source::Snap))
+        })
+        .collect()
+}
+
+/// Look for flatpak exports in system-wide and per-user installations
+fn find_in_flatpaks(roots: &Roots, ides: &[&'static Ide]) -> Vec<Candidate> {
+    ides.iter()
+        .flat_map(|&ide| {
+            roots.flatpak.iter().filter_map(move |root| {
+                let path = root.join("exports/bin").join(ide.flatpak?);
+                path.exists()
+                    .then_some(Candidate(ide, path, Source::Flatpak))
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::testing::{roots, write_executable};
+    use tempfile::TempDir;
+
+    #[test]
+    fn guesses_channel_from_launcher_name() {
+        let guess = |path: &str| Channel::guess_from_path(Path::new(path));
+
+        assert_eq!(guess("/usr/bin/code-insiders"), Some(Channel::Insiders));
+        assert_eq!(
+            guess(
+                "/Applications/Visual Studio Code - Insiders.app/Contents/Resources/app/bin/code"
+            ),
+            Some(Channel::Insiders)
+        );
+        assert_eq!(guess("/home/peap/bin/rustrover"), None);
+        assert_eq!(guess("/opt/previewer/bin/code"), None);
+        assert_eq!(guess("/usr/bin/zed-preview"), Some(Channel::Preview));
+        assert_eq!(Channel::guess("EAP"), Some(Channel::Eap));
+    }
+
+    #[test]
+    fn finds_toolbox_script_with_product_info() {
+        let td = TempDir::new().unwrap();
+        let roots = roots(&td);
+        let install = roots
+            .home
+            .join(".local/share/JetBrains/Toolbox/apps/goland");
+
+        write_executable(&install.join("bin/goland.sh"), "#!/bin/sh\n");
+        fs::write(
+            install.join("product-info.json"),
+            r#"{"name": "GoLand", "version": "2025.1", "versionSuffix": "EAP"}"#,
+        )
+        .unwrap();
+        write_executable(
+            &roots
+                .home
+                .join(".local/share/JetBrains/Toolbox/scripts/goland"),
+            &format!(
+                "#!/bin/sh\nexec \"{}\" \"$@\"\n",
+                install.join("bin/goland.sh").display()
+            ),
+        );
+
+        let found = find_ides(&roots, &[GOLAND], true, true);
+
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].source, Source::ToolboxScript);
+        assert_eq!(found[0].version.as_deref(), Some("2025.1"));
+        assert_eq!(found[0].channel, Channel::Eap);
+        assert_eq!(found[1].source, Source::ToolboxApp);
+    }
+
+    #[test]
+    fn finds_desktop_entry_and_deduplicates_path() {
+        let td = TempDir::new().unwrap();
+        let roots = roots(&td);
+        let launcher = td.path().join("opt/rustrover/bin/rustrover.sh");
+
+        write_executable(&launcher, "#!/bin/sh\n");
+        fs::create_dir_all(roots.path[0].clone()).unwrap();
+        std::os::unix::fs::symlink(&launcher, roots.path[0].join(RUSTROVER)).unwrap();
+        fs::create_dir_all(roots.data_dirs[0].join("applications")).unwrap();
+        fs::write(
+            roots.data_dirs[0].join("applications/jetbrains-rustrover.desktop"),
+            format!(
+                "[Desktop Entry]\nName=RustRover\nExec=\"{}\" %f\n",
+                launcher.display()
+            ),
+        )
+        .unwrap();
+
+        let found = find_ides(&roots, &ides::ids(), true, true);
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id(), RUSTROVER);
+        assert_eq!(found[0].source, Source::Path);
+        assert_eq!(found[0].version, None);
+    }
+
+    #[test]
+    fn probes_version_of_vscode_and_keeps_first_per_ide() {
+        let td = TempDir::new().unwrap();
+        let roots = roots(&td);
+
+        write_executable(
+            &roots.path[0].join("code-insiders"),
+            "#!/bin/sh\necho 1.96.0-insider\necho abcdef\necho arm64\n",
+        );
+        write_executable(&roots.snap.join("bin/code"), "#!/bin/sh\necho 1.95.3\n");
+
+        let all = find_ides(&roots, &[CODE], true, true);
+        let first = find_ides(&roots, &[CODE], false, true);
+
+        assert_eq!(all.len(), 2);
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].version.as_deref(), Some("1.96.0-insider"));
+        assert_eq!(first[0].channel, Channel::Insiders);
+        assert_eq!(all[1].source, Source::Snap);
+
+        // Without probing, the channel still comes from the launcher name
+        let unprobed = find_ides(&roots, &[CODE], false, false);
+        assert_eq!(unprobed[0].version, None);
+        assert_eq!(unprobed[0].channel, Channel::Insiders);
+    }
+}
@@ -0,0 +1,413 @@
+pub mod detect;
+pub mod discovery;
+pub mod preferences;
+
+use crate::consts::*;
+use anyhow::anyhow;
+use phf::ordered_map::OrderedMap;
+use phf::phf_ordered_map;
+use std::collections::BTreeSet;
+use std::fmt::{self, Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+static LANGS_IDES_MAP: OrderedMap<&str, &str> = phf_ordered_map! {
+    "c" => CLION,
+    "c++" => CLION,
+    "go" => GOLAND,
+    "javascript" => WEBSTORM,
+    "ruby" => RUBYMINE,
+    "rust" => RUSTROVER,
+};
+
+/// Family of an IDE, which decides how it is discovered and launched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Family {
+    JetBrains,
+    VsCode,
+    Zed,
+    Vim,
+}
+
+/// Static description of an IDE known to `af`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ide {
+    /// Identifier, which is also the name of the launcher command (e.g. `goland`)
+    pub id: &'static str,
+    /// Human-readable name (e.g. `GoLand`)
+    pub name: &'static str,
+    pub family: Family,
+    /// Launcher executable names looked up on `PATH` and in JetBrains Toolbox directories
+    pub bins: &'static [&'static str],
+    /// macOS application bundle names
+    pub apps: &'static [&'static str],
+    /// Launcher path inside the macOS application bundle
+    pub app_bin: &'static str,
+    /// Linux `.desktop` file names (without extension)
+    pub desktop_ids: &'static [&'static str],
+    pub snap: Option<&'static str>,
+    pub flatpak: Option<&'static str>,
+}
+
+static REGISTRY: &[Ide] = &[
+    Ide {
+        id: CLION,
+        name: "CLion",
+        family: Family::JetBrains,
+        bins: &[CLION, "clion.sh"],
+        apps: &["CLion.app"],
+        app_bin: "Contents/MacOS/clion",
+        desktop_ids: &["jetbrains-clion", "clion_clion", "com.jetbrains.CLion"],
+        snap: Some(CLION),
+        flatpak: Some("com.jetbrains.CLion"),
+    },
+    Ide {
+        id: GOLAND,
+        name: "GoLand",
+        family: Family::JetBrains,
+        bins: &[GOLAND, "goland.sh"],
+        apps: &["GoLand.app"],
+        app_bin: "Contents/MacOS/goland",
+        desktop_ids: &["jetbrains-goland", "goland_goland", "com.jetbrains.GoLand"],
+        snap: Some(GOLAND),
+        flatpak: Some("com.jetbrains.GoLand"),
+    },
+    Ide {
+        id: IDEA,
+        name: "IntelliJ IDEA",
+        family: Family::JetBrains,
+        bins: &[IDEA, "idea.sh"],
+        apps: &[
+            "IntelliJ IDEA.app",
+            "IntelliJ IDEA Ultimate.app",
+            "IntelliJ IDEA CE.app",
+        ],
+        app_bin: "Contents/MacOS/idea",
+        desktop_ids: &[
+            "jetbrains-idea",
+            "jetbrains-idea-ce",
+            "intellij-idea-ultimate_intellij-idea-ultimate",
+            "intellij-idea-community_intellij-idea-community",
+            "com.jetbrains.IntelliJ-IDEA-Ultimate",
+            "com.jetbrains.IntelliJ-IDEA-Community",
+        ],
+        snap: Some("intellij-idea-ultimate"),
+        flatpak: Some("com.jetbrains.IntelliJ-IDEA-Ultimate"),
+    },
+    Ide {
+        id: PYCHARM,
+        name: "PyCharm",
+        family: Family::JetBrains,
+        bins: &[PYCHARM, "pycharm.sh"],
+        apps: &[
+            "PyCharm.app",
+            "PyCharm CE.app",
+            "PyCharm Professional Edition.app",
+        ],
+        app_bin: "Contents/MacOS/pycharm",
+        desktop_ids: &[
+            "jetbrains-pycharm",
+            "jetbrains-pycharm-ce",
+            "pycharm-professional_pycharm-professional",
+            "pycharm-community_pycharm-community",
+            "com.jetbrains.PyCharm-Professional",
+            "com.jetbrains.PyCharm-Community",
+        ],
+        snap: Some("pycharm-professional"),
+        flatpak: Some("com.jetbrains.PyCharm-Professional"),
+    },
+    Ide {
+        id: RUBYMINE,
+        name: "RubyMine",
+        family: Family::JetBrains,
+        bins: &[RUBYMINE, "rubymine.sh"],
+        apps: &["RubyMine.app"],
+        app_bin: "Contents/MacOS/rubymine",
+        desktop_ids: &[
+            "jetbrains-rubymine",
+            "rubymine_rubymine",
+            "com.jetbrains.RubyMine",
+        ],
+        snap: Some(RUBYMINE),
+        flatpak: Some("com.jetbrains.RubyMine"),
+    },
+    Ide {
+        id: RUSTROVER,
+        name: "RustRover",
+        family: Family::JetBrains,
+        bins: &[RUSTROVER, "rustrover.sh"],
+        apps: &["RustRover.app"],
+        app_bin: "Contents/MacOS/rustrover",
+        desktop_ids: &[
+            "jetbrains-rustrover",
+            "rustrover_rustrover",
+            "com.jetbrains.RustRover",
+        ],
+        snap: Some(RUSTROVER),
+        flatpak: Some("com.jetbrains.RustRover"),
+    },
+    Ide {
+        id: WEBSTORM,
+        name: "WebStorm",
+        family: Family::JetBrains,
+        bins: &[WEBSTORM, "webstorm.sh"],
+        apps: &["WebStorm.app"],
+        app_bin: "Contents/MacOS/webstorm",
+        desktop_ids: &[
+            "jetbrains-webstorm",
+            "webstorm_webstorm",
+            "com.jetbrains.WebStorm",
+        ],
+        snap: Some(WEBSTORM),
+        flatpak: Some("com.jetbrains.WebStorm"),
+    },
+    Ide {
+        id: CODE,
+        name: "Visual Studio Code",
+        family: Family::VsCode,
+        bins: &[CODE, "code-insiders"],
+        apps: &[
+            "Visual Studio Code.app",
+            "Visual Studio Code - Insiders.app",
+        ],
+        app_bin: "Contents/Resources/app/bin/code",
+        desktop_ids: &[
+            "code",
+            "code-insiders",
+            "code_code",
+            "com.visualstudio.code",
+        ],
+        snap: Some(CODE),
+        flatpak: Some("com.visualstudio.code"),
+    },
+    Ide {
+        id: ZED,
+        name: "Zed",
+        family: Family::Zed,
+        bins: &[ZED, "zeditor", "zedit"],
+        apps: &["Zed.app", "Zed Preview.app"],
+        app_bin: "Contents/MacOS/cli",
+        desktop_ids: &["dev.zed.Zed", "dev.zed.Zed-Preview", "zed"],
+        snap: None,
+        flatpak: Some("dev.zed.Zed"),
+    },
+    Ide {
+        id: NVIM,
+        name: "Neovim",
+        family: Family::Vim,
+        bins: &[NVIM],
+        apps: &[],
+        app_bin: "",
+        desktop_ids: &["nvim"],
+        snap: Some(NVIM),
+        flatpak: Some("io.neovim.nvim"),
+    },
+    Ide {
+        id: VIM,
+        name: "Vim",
+        family: Family::Vim,
+        bins: &[VIM],
+        apps: &[],
+        app_bin: "",
+        desktop_ids: &["vim"],
+        snap: None,
+        flatpak: None,
+    },
+];
+
+/// A path to open in an IDE, optionally pointing at a line and column
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub path: PathBuf,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+}
+
+impl Location {
+    pub fn new(path: impl Into<PathBuf>, line: Option<u32>, column: Option<u32>) -> Self {
+        Self {
+            path: path.into(),
+            line,
+            column: column.filter(|_| line.is_some()),
+        }
+    }
+}
+
+impl FromStr for Location {
+    type Err = anyhow::Error;
+
+    /// Parses `<path>[:line[:column]]`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.rsplitn(3, ':').collect::<Vec<_>>();
+        parts.reverse();
+
+        let numbers = parts
+            .iter()
+            .rev()
+            .take_while(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()))
+            .count()
+            .min(parts.len() - 1);
+
+        let path = parts[..parts.len() - numbers].join(":");
+
+        if path.is_empty() {
+            return Err(anyhow!("Missing path in location: {s}"));
+        }
+
+        let mut numbers = parts[parts.len() - numbers..]
+            .iter()
+            .map(|n| n.parse::<u32>());
+
+        let line = numbers.next().transpose()?;
+        let column = numbers.next().transpose()?;
+
+        Ok(Self::new(path, line, column))
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+
+        if let Some(line) = self.line {
+            write!(f, ":{line}")?;
+        }
+
+        if let Some(column) = self.column {
+            write!(f, ":{column}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Ide {
+    /// Builds launcher arguments opening the project and/or jumping to the location
+    ///
+    /// Every family has its own way of passing a line: JetBrains IDEs use
+    /// `--line`/`--column`, VS Code `-g <file>:<line>:<column>`, Zed a
+    /// `<file>:<line>:<column>` argument and vim `+<line>`.
+    pub fn open_args(&self, project: Option<&Path>, location: Option<&Location>) -> Vec<String> {
+        let mut args = vec![];
+
+        if let Some(project) = project
+            && self.family != Family::Vim
+        {
+            args.push(project.display().to_string());
+        }
+
+        let Some(location) = location else {
+            if let Some(project) = project
+                && self.family == Family::Vim
+            {
+                args.push(project.display().to_string());
+            }
+
+            return args;
+        };
+
+        let file = location.path.display().to_string();
+
+        match (self.family, location.line) {
+            (Family::JetBrains, Some(line)) => {
+                args.extend(["--line".to_string(), line.to_string()]);
+
+                if let Some(column) = location.column {
+                    args.extend(["--column".to_string(), column.to_string()]);
+                }
+
+                args.push(file);
+            }
+            (Family::VsCode, Some(_)) => args.extend(["-g".to_string(), location.to_string()]),
+            (Family::Zed, Some(_)) => args.push(location.to_string()),
+            (Family::Vim, Some(line)) => {
+                match location.column {
+                    Some(column) => args.push(format!("+call cursor({line}, {column})")),
+                    None => args.push(format!("+{line}")),
+                }
+
+                args.push(file);
+            }
+            (_, None) => args.push(file),
+        }
+
+        args
+    }
+}
+
+pub fn get(language: &str) -> Option<&'static str> {
+    LANGS_IDES_MAP.get(language).copied()
+}
+
+pub fn list() -> Vec<&'static str> {
+    let ides = LANGS_IDES_MAP.values().copied();
+    let unique = BTreeSet::from_iter(ides);
+    unique.into_iter().collect()
+}
+
+/// All IDEs known to `af`
+pub fn registry() -> &'static [Ide] {
+    REGISTRY
+}
+
+/// Identifiers of all IDEs known to `af`
+pub fn ids() -> Vec<&'static str> {
+    REGISTRY.iter().map(|ide| ide.id).collect()
+}
+
+/// Looks up an IDE in the registry by its identifier
+pub fn find(id: &str) -> Option<&'static Ide> {
+    REGISTRY.iter().find(|ide| ide.id == id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_values() {
+        assert_eq!(list(), vec![CLION, GOLAND, RUBYMINE, RUSTROVER, WEBSTORM]);
+    }
+
+    #[test]
+    fn parses_location() {
+        let cases = [
+            ("src/lib.rs", Location::new("src/lib.rs", None, None)),
+            ("src/lib.rs:42", Location::new("src/lib.rs", Some(42), None)),
+            (
+                "src/lib.rs:42:7",
+                Location::new("src/lib.rs", Some(42), Some(7)),
+            ),
+            ("a:b/c.rs:3", Location::new("a:b/c.rs", Some(3), None)),
+            ("2024/notes.md", Location::new("2024/notes.md", None, None)),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(input.parse::<Location>().unwrap(), expected, "{input}");
+        }
+
+        assert!(":42".parse::<Location>().is_err());
+    }
+
+    #[test]
+    fn builds_open_args_per_family() {
+        let project = Path::new("/p");
+        let location = Location::new("/p/src/lib.rs", Some(42), Some(7));
+        let args = |id| find(id).unwrap().open_args(Some(project), Some(&location));
+
+        assert_eq!(
+            args(GOLAND),
+            ["/p", "--line", "42", "--column", "7", "/p/src/lib.rs"]
+        );
+        assert_eq!(args(CODE), ["/p", "-g", "/p/src/lib.rs:42:7"]);
+        assert_eq!(args(ZED), ["/p", "/p/src/lib.rs:42:7"]);
+        assert_eq!(args(VIM), ["+call cursor(42, 7)", "/p/src/lib.rs"]);
+        assert_eq!(
+            find(NVIM)
+                .unwrap()
+                .open_args(None, Some(&Location::new("f", Some(3), None))),
+            ["+3", "f"]
+        );
+        assert_eq!(find(VIM).unwrap().open_args(Some(project), None), ["/p"]);
+    }
+}
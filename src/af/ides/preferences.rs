@@ -0,0 +1,288 @@
+use crate::config::ProjectConfig;
+use crate::consts::{ORIGIN, UPSTREAM};
+use crate::ides::discovery::Installation;
+use crate::repo::Repo;
+use crate::state::State;
+use crate::{ides, utils};
+
+use anyhow::{Result, bail};
+use dialoguer::{FuzzySelect, theme::ColorfulTheme};
+use git2::Repository;
+use log::{debug, info, warn};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Where a project's IDE preference comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    /// Pinned in the project's `.af.toml`
+    Pinned,
+    /// Chosen earlier and remembered in the local state
+    Remembered,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Preference {
+    pub id: String,
+    pub origin: Origin,
+}
+
+/// A project IDE preferences are stored for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Project {
+    /// Repository working directory (or the path itself outside of repositories)
+    pub root: PathBuf,
+    /// Canonical repository URL (e.g. `github.com/org/repo`)
+    pub remote: Option<String>,
+}
+
+impl Project {
+    /// Finds the project containing the path
+    pub fn discover(path: &Path) -> Self {
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+
+        match Repository::discover(&path) {
+            Ok(repo) => Self {
+                root: repo
+                    .workdir()
+                    .and_then(|dir| fs::canonicalize(dir).ok())
+                    .unwrap_or(path),
+                remote: canonical_remote(&repo),
+            },
+            Err(_) => Self {
+                root: match path.is_file() {
+                    true => path.parent().map(Path::to_path_buf).unwrap_or(path),
+                    false => path,
+                },
+                remote: None,
+            },
+        }
+    }
+
+    /// IDE pinned in the project's `.af.toml`
+    pub fn pinned(&self) -> Option<String> {
+        match ProjectConfig::load(&self.root) {
+            Ok(config) => config.ide,
+            Err(err) => {
+                warn!("{err:#}");
+                None
+            }
+        }
+    }
+
+    /// IDE remembered for the project's remote, or for its path if there is no match
+    pub fn remembered(&self, state: &State) -> Option<String> {
+        self.remote
+            .as_ref()
+            .and_then(|remote| state.ides.by_remote.get(remote))
+            .or_else(|| state.ides.by_path.get(&self.root))
+            .cloned()
+    }
+
+    pub fn preference(&self, state: &State) -> Option<Preference> {
+        let pinned = self.pinned().map(|id| Preference {
+            id,
+            origin: Origin::Pinned,
+        });
+
+        pinned.or_else(|| {
+            self.remembered(state).map(|id| Preference {
+                id,
+                origin: Origin::Remembered,
+            })
+        })
+    }
+
+    pub fn remember(&self, state: &mut State, id: &str) {
+        if let Some(remote) = &self.remote {
+            state.ides.by_remote.insert(remote.clone(), id.to_string());
+        }
+
+        state.ides.by_path.insert(self.root.clone(), id.to_string());
+    }
+
+    /// Removes remembered choices, returns false if there were none
+    pub fn forget(&self, state: &mut State) -> bool {
+        let by_remote = self
+            .remote
+            .as_ref()
+            .and_then(|remote| state.ides.by_remote.remove(remote));
+        let by_path = state.ides.by_path.remove(&self.root);
+
+        by_remote.is_some() || by_path.is_some()
+    }
+}
+
+/// Returns the first remote (upstream before origin) as `<host>/<org>/<name>`
+fn canonical_remote(repo: &Repository) -> Option<String> {
+    [UPSTREAM, ORIGIN]
+        .iter()
+        .filter_map(|name| repo.find_remote(name).ok())
+        .find_map(|remote| {
+            let ssh = utils::convert_to_ssh(remote.url()?).ok()?;
+            Repo::parse(&ssh).ok().map(|repo| repo.short_format())
+        })
+}
+
+/// Picks the installation for a project without asking the user
+///
+/// An explicitly requested IDE wins (and is remembered), followed by the IDE
+/// pinned in `.af.toml` and the one remembered from earlier runs.
+pub fn preferred<'a>(
+    installed: &'a [Installation],
+    project: &Project,
+    explicit: Option<&str>,
+) -> Result<Option<&'a Installation>> {
+    let mut state = State::load()?;
+
+    if let Some(id) = explicit {
+        let Some(installation) = find_installed(installed, id) else {
+            bail!("IDE {id} is not installed");
+        };
+
+        project.remember(&mut state, id);
+        state.save()?;
+
+        return Ok(Some(installation));
+    }
+
+    let Some(preference) = project.preference(&state) else {
+        return Ok(None);
+    };
+
+    if ides::find(&preference.id).is_none() {
+        warn!(
+            "Unknown IDE {:?} preferred for {}",
+            preference.id,
+            project.root.display()
+        );
+        return Ok(None);
+    }
+
+    match find_installed(installed, &preference.id) {
+        Some(installation) => {
+            let origin = match preference.origin {
+                Origin::Pinned => "pinned",
+                Origin::Remembered => "remembered",
+            };
+
+            info!("Using {origin} IDE {}", installation.name);
+            Ok(Some(installation))
+        }
+        None => {
+            debug!("Preferred IDE {} is not installed", preference.id);
+            Ok(None)
+        }
+    }
+}
+
+/// Picks the installation for a project, asking the user if there is no preference
+///
+/// The answer is remembered for the project, with `suggested` preselected in the prompt.
+pub fn choose<'a>(
+    installed: &'a [Installation],
+    project: &Project,
+    explicit: Option<&str>,
+    suggested: Option<&str>,
+) -> Result<Option<&'a Installation>> {
+    if installed.is_empty() {
+        bail!("No installed IDEs found");
+    }
+
+    if let Some(installation) = preferred(installed, project, explicit)? {
+        return Ok(Some(installation));
+    }
+
+    let index = suggested
+        .and_then(|id| installed.iter().position(|i| i.id() == id))
+        .unwrap_or_default();
+
+    let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
+        .with_prompt("Select an IDE to open the project, or press 'Esc' to skip")
+        .default(index)
+        .items(installed)
+        .interact_opt()?;
+
+    let Some(installation) = selection.map(|index| &installed[index]) else {
+        return Ok(None);
+    };
+
+    let mut state = State::load()?;
+    project.remember(&mut state, installation.id());
+    state.save()?;
+
+    Ok(Some(installation))
+}
+
+fn find_installed<'a>(installed: &'a [Installation], id: &str) -> Option<&'a Installation> {
+    installed
+        .iter()
+        .find(|installation| installation.id() == id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::{GOLAND, PROJECT_CONFIG, RUSTROVER};
+    use tempfile::TempDir;
+
+    fn project(td: &TempDir) -> Project {
+        let repo = Repository::init(td.path()).unwrap();
+        repo.remote(UPSTREAM, "https://github.com/org/repo")
+            .unwrap();
+        fs::create_dir_all(td.path().join("src")).unwrap();
+
+        Project::discover(&td.path().join("src"))
+    }
+
+    #[test]
+    fn discovers_repository_root_and_remote() {
+        let td = TempDir::new().unwrap();
+        let project = project(&td);
+
+        assert_eq!(project.root, fs::canonicalize(td.path()).unwrap());
+        assert_eq!(project.remote.as_deref(), Some("github.com/org/repo"));
+    }
+
+    #[test]
+    fn pinned_takes_precedence_over_remembered() {
+        let td = TempDir::new().unwrap();
+        let project = project(&td);
+        let mut state = State::default();
+
+        assert_eq!(project.preference(&state), None);
+
+        project.remember(&mut state, GOLAND);
+        assert_eq!(
+            project.preference(&state).unwrap().origin,
+            Origin::Remembered
+        );
+
+        fs::write(td.path().join(PROJECT_CONFIG), "ide = \"rustrover\"\n").unwrap();
+        assert_eq!(
+            project.preference(&state),
+            Some(Preference {
+                id: RUSTROVER.to_string(),
+                origin: Origin::Pinned,
+            })
+        );
+    }
+
+    #[test]
+    fn remembers_by_remote_across_clones() {
+        let (first_td, second_td) = (TempDir::new().unwrap(), TempDir::new().unwrap());
+        let (first, second) = (project(&first_td), project(&second_td));
+        let mut state = State::default();
+
+        first.remember(&mut state, RUSTROVER);
+        assert_eq!(second.remembered(&state).as_deref(), Some(RUSTROVER));
+        assert!(second.forget(&mut state));
+
+        // The choice is still remembered by the first clone's path
+        assert_eq!(first.remembered(&state).as_deref(), Some(RUSTROVER));
+        assert!(first.forget(&mut state));
+        assert_eq!(first.remembered(&state), None);
+    }
+}
@@ -1,17 +1,21 @@
 pub mod cmd;
+pub mod config;
 pub mod consts;
+pub mod discovery;
+pub mod dotfiles;
 pub mod ides;
 pub mod repo;
+pub mod state;
 pub mod utils;
 
 use crate::cmd::{
-    browser::Browser, dot::DotCmd, git::Git, git::clone_project::CloneProject,
+    browser::Browser, dot::DotCmd, git::Git, git::clone_project::CloneProject, ide::Ide,
     shortcuts::abbreviations::Shortcut,
 };
 use crate::consts::AF;
 
 use anyhow::Result;
-use clap::{CommandFactory, Parser, Subcommand, command};
+use clap::{CommandFactory, Parser, Subcommand};
 use indicatif::MultiProgress;
 use log::LevelFilter;
 
@@ -115,6 +119,18 @@ pub enum Applet {
         #[command(flatten)]
         verbose: clap_verbosity_flag::Verbosity,
     },
+
+    /// Discover and inspect installed IDEs
+    #[command(version)]
+    Ide {
+        /// IDE-related subcommands
+        #[command(subcommand)]
+        ide: Ide,
+
+        /// Increase output verbosity (-v, -vv, -vvv, etc.)
+        #[command(flatten)]
+        verbose: clap_verbosity_flag::Verbosity,
+    },
 }
 
 impl Applet {
@@ -125,6 +141,7 @@ impl Applet {
             Applet::ProjectGitClone { verbose, .. } => verbose.log_level_filter(),
             Applet::Shortcuts { verbose, .. } => verbose.log_level_filter(),
             Applet::Browser { verbose, .. } => verbose.log_level_filter(),
+            Applet::Ide { verbose, .. } => verbose.log_level_filter(),
             Applet::Completions { .. } => DEFAULT_LOG_LEVEL,
         }
     }
@@ -136,18 +153,17 @@ impl Applet {
                 Ok(())
             }
 
-            Applet::Dot { dot, .. } => dot.run(),
+            Applet::Dot { dot, .. } => dot.run().await,
 
             Applet::Git { git, .. } => git.run(&multi).await,
 
-            Applet::Shortcuts { shortcut, .. } => {
-                shortcut.run();
-                Ok(())
-            }
+            Applet::Shortcuts { shortcut, .. } => shortcut.run(&multi),
 
             Applet::ProjectGitClone { clone_project, .. } => clone_project.run(&multi).await,
 
             Applet::Browser { browser, .. } => browser.run(),
+
+            Applet::Ide { ide, .. } => ide.run(),
         }
     }
 }
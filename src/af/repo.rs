@@ -1,6 +1,6 @@
 use crate::consts::*;
 use crate::ides;
-use crate::utils;
+use crate::ides::discovery::Installation;
 use anyhow::anyhow;
 use log::debug;
 use regex::Regex;
@@ -48,7 +48,7 @@ impl<'a> Repo<'a> {
             .map_err(Into::into)
     }
 
-    pub async fn find_ide(&self) -> anyhow::Result<Option<&'a str>> {
+    pub async fn find_ide(&self, installed: &[Installation]) -> anyhow::Result<Option<&'a str>> {
         let languages = self.get_languages().await?;
 
         debug!("Languages: {:?}", languages);
@@ -57,11 +57,14 @@ impl<'a> Repo<'a> {
             if let Some(ide) = ides::get(language) {
                 debug!("found IDE for language {ide}");
 
-                if utils::run_command(WHICH, &[ide])?.status.success() {
+                if installed
+                    .iter()
+                    .any(|installation| installation.id() == ide)
+                {
                     return Ok(Some(ide));
                 }
 
-                debug!("command to start IDE {ide} not found");
+                debug!("IDE {ide} is not installed");
             }
         }
 
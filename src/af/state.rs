@@ -0,0 +1,124 @@
+use crate::consts::STATE_FILE;
+use crate::utils;
+
+use anyhow::{Context, Result};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Local, machine-specific state persisted between runs
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct State {
+    /// IDEs chosen for projects
+    #[serde(default)]
+    pub ides: IdePreferences,
+
+    /// Rendered dotfile templates
+    #[serde(default)]
+    pub dotfiles: DotfilesState,
+
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+/// IDE identifiers remembered per project
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct IdePreferences {
+    /// Keyed by canonical repository URL (e.g. `github.com/org/repo`)
+    #[serde(default)]
+    pub by_remote: BTreeMap<String, String>,
+
+    /// Keyed by canonical project path
+    #[serde(default)]
+    pub by_path: BTreeMap<PathBuf, String>,
+}
+
+/// Hashes (git blob ids) of rendered templates, keyed by target path
+///
+/// Used to tell files edited after rendering apart from outdated ones.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DotfilesState {
+    #[serde(default)]
+    pub rendered: BTreeMap<PathBuf, String>,
+}
+
+impl State {
+    /// Loads state from `$XDG_STATE_HOME/af/state.json`
+    pub fn load() -> Result<Self> {
+        Self::load_from(utils::state_dir().join(STATE_FILE))
+    }
+
+    /// Loads state from the given file, starting empty if it doesn't exist
+    pub fn load_from(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut state: Self = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse state file: {}", path.display()))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                debug!("State file {} not found, starting empty", path.display());
+                Self::default()
+            }
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("Failed to read state file: {}", path.display()));
+            }
+        };
+
+        state.path = path;
+
+        Ok(state)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(&self.path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write state file: {}", self.path.display()))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn round_trips_through_file() {
+        let td = TempDir::new().unwrap();
+        let file = td.path().join("nested/state.json");
+
+        let mut state = State::load_from(&file).unwrap();
+        assert!(state.ides.by_remote.is_empty());
+
+        state
+            .ides
+            .by_remote
+            .insert("github.com/org/repo".to_string(), "rustrover".to_string());
+        state.save().unwrap();
+
+        let loaded = State::load_from(&file).unwrap();
+        assert_eq!(
+            loaded.ides.by_remote.get("github.com/org/repo").unwrap(),
+            "rustrover"
+        );
+    }
+
+    #[test]
+    fn fails_on_unreadable_file() {
+        let td = TempDir::new().unwrap();
+
+        // A directory in place of the file is an error, not an empty state
+        assert!(State::load_from(td.path()).is_err());
+    }
+}
@@ -5,10 +5,14 @@ use console::style;
 use fern::Dispatch;
 use fern::colors::{Color, ColoredLevelConfig};
 use log::{LevelFilter, trace};
+use percent_encoding::percent_decode_str;
 use regex::Regex;
+use std::collections::BTreeMap;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Output};
 use std::time::SystemTime;
-use std::{env, io};
+use std::{env, fs, io};
 
 pub fn run_command(command: &str, args: &[&str]) -> Result<Output> {
     let output = Command::new(command)
@@ -28,9 +32,64 @@ pub fn run_command(command: &str, args: &[&str]) -> Result<Output> {
     Ok(output)
 }
 
+/// Returns true if the path points to a file with any executable bit set
+pub fn is_executable(path: &Path) -> bool {
+    fs::metadata(path)
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+/// Directory for state persisted between runs (`$XDG_STATE_HOME/af`)
+pub fn state_dir() -> PathBuf {
+    xdg_dir(XDG_STATE_HOME, ".local/state")
+}
+
+/// Directory for user configuration (`$XDG_CONFIG_HOME/af`)
+pub fn config_dir() -> PathBuf {
+    xdg_dir(XDG_CONFIG_HOME, ".config")
+}
+
+/// Directory for data which can be recreated at any time (`$XDG_CACHE_HOME/af`)
+pub fn cache_dir() -> PathBuf {
+    xdg_dir(XDG_CACHE_HOME, ".cache")
+}
+
+fn xdg_dir(var: &str, fallback: &str) -> PathBuf {
+    env::var(var)
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env::var(HOME).unwrap_or_default()).join(fallback))
+        .join(AF)
+}
+
+/// Sections of an INI file with their keys and values, in file order
+pub fn parse_ini(content: &str) -> Vec<(String, BTreeMap<String, String>)> {
+    let mut sections: Vec<(String, BTreeMap<String, String>)> = vec![];
+
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with([';', '#']) {
+            continue;
+        }
+
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            sections.push((section.to_string(), BTreeMap::new()));
+        } else if let (Some((key, value)), Some((_, values))) =
+            (line.split_once('='), sections.last_mut())
+        {
+            values.insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+
+    sections
+}
+
 pub fn format_directory(directory: &ClioPath) -> String {
-    directory
-        .display()
+    format_path(directory)
+}
+
+pub fn format_path(path: &Path) -> String {
+    path.display()
         .to_string()
         .replace(env::var(HOME).unwrap_or_default().as_str(), "~")
 }
@@ -80,6 +139,68 @@ pub fn convert_to_ssh<S: AsRef<str>>(repository: S) -> Result<String> {
     anyhow::bail!("Only http(s) repository URLs are supported for conversion");
 }
 
+/// File reference parsed from a GitHub blob URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobUrl {
+    /// Repository URL without the blob part (e.g. `https://github.com/org/repo`)
+    pub repository: String,
+    pub reference: String,
+    pub path: String,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+}
+
+impl BlobUrl {
+    /// Moves leading path segments into the reference, as long as the longest
+    /// reference accepted by `exists`
+    pub fn resolve_reference(&mut self, exists: impl Fn(&str) -> bool) {
+        let segments: Vec<&str> = self.path.split('/').collect();
+
+        for len in (1..segments.len()).rev() {
+            let reference = format!("{}/{}", self.reference, segments[..len].join("/"));
+
+            if exists(&reference) {
+                self.reference = reference;
+                self.path = segments[len..].join("/");
+                return;
+            }
+        }
+    }
+}
+
+/// Parses `http[s]://<host>/<org>/<repo>/blob/<ref>/<path>[#L<line>[C<column>][-L<line>]]`
+///
+/// References containing slashes are ambiguous in such URLs, so the first path
+/// segment after `blob` is treated as the reference until
+/// [`BlobUrl::resolve_reference`] finds a longer one.
+pub fn parse_blob_url(url: &str) -> Option<BlobUrl> {
+    let re = Regex::new(
+        r"^(https?://[^/]+/[^/]+/[^/]+)/blob/([^/]+)/([^#?]+)(?:\?[^#]*)?(?:#L(\d+)(?:C(\d+))?(?:-L\d+(?:C\d+)?)?)?$",
+    )
+    .ok()?;
+
+    let caps = re.captures(url.trim())?;
+    let decode = |segment: &str| {
+        percent_decode_str(segment)
+            .decode_utf8()
+            .ok()
+            .map(|decoded| decoded.into_owned())
+    };
+    let path = caps[3]
+        .trim_end_matches('/')
+        .split('/')
+        .map(decode)
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(BlobUrl {
+        repository: caps[1].to_string(),
+        reference: decode(&caps[2])?,
+        path: path.join("/"),
+        line: caps.get(4).and_then(|m| m.as_str().parse().ok()),
+        column: caps.get(5).and_then(|m| m.as_str().parse().ok()),
+    })
+}
+
 pub fn setup_logger(level: LevelFilter) -> (LevelFilter, Box<dyn log::Log>) {
     let colors = ColoredLevelConfig::new()
         .error(Color::Red)
@@ -185,6 +306,67 @@ mod tests {
         assert_eq!(ssh, "git@github.com:org/repo.git");
     }
 
+    // parse_blob_url
+
+    #[test]
+    fn parses_blob_url_with_line() {
+        let blob = parse_blob_url("https://github.com/org/repo/blob/main/src/lib.rs#L42").unwrap();
+        assert_eq!(
+            blob,
+            BlobUrl {
+                repository: "https://github.com/org/repo".to_string(),
+                reference: "main".to_string(),
+                path: "src/lib.rs".to_string(),
+                line: Some(42),
+                column: None,
+            }
+        );
+    }
+
+    #[test]
+    fn parses_blob_url_with_range_and_column() {
+        let blob = parse_blob_url("https://github.com/org/repo/blob/v1.2.0/a/b.go?plain=1#L7C3-L9")
+            .unwrap();
+        assert_eq!(blob.reference, "v1.2.0");
+        assert_eq!(blob.path, "a/b.go");
+        assert_eq!(blob.line, Some(7));
+        assert_eq!(blob.column, Some(3));
+    }
+
+    #[test]
+    fn decodes_blob_url_segments() {
+        let blob =
+            parse_blob_url("https://github.com/org/repo/blob/r%C3%A9l/docs/My%20Notes.md").unwrap();
+        assert_eq!(blob.reference, "rél");
+        assert_eq!(blob.path, "docs/My Notes.md");
+    }
+
+    #[test]
+    fn resolves_references_with_slashes() {
+        let mut blob =
+            parse_blob_url("https://github.com/org/repo/blob/feat/x/y/src/lib.rs").unwrap();
+        assert_eq!(blob.reference, "feat");
+
+        blob.resolve_reference(|reference| ["feat", "feat/x"].contains(&reference));
+        assert_eq!(blob.reference, "feat/x");
+        assert_eq!(blob.path, "y/src/lib.rs");
+
+        let mut blob = parse_blob_url("https://github.com/org/repo/blob/main/src/lib.rs").unwrap();
+        blob.resolve_reference(|_| false);
+        assert_eq!(blob.reference, "main");
+        assert_eq!(blob.path, "src/lib.rs");
+    }
+
+    #[test]
+    fn ignores_non_blob_urls() {
+        assert_eq!(parse_blob_url("https://github.com/org/repo"), None);
+        assert_eq!(
+            parse_blob_url("https://github.com/org/repo/tree/main/src"),
+            None
+        );
+        assert_eq!(parse_blob_url("git@github.com:org/repo.git"), None);
+    }
+
     #[test]
     fn trims_input() {
         let url = "  https://github.com/org/repo  ";